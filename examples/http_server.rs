@@ -15,6 +15,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         addr: "127.0.0.1:8083".parse()?,
         max_connections: 1024,
         read_buffer_size: 8192,
+        ..ServerConfig::default()
     };
 
     // Create a new HTTP server
@@ -23,14 +24,13 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Add routes
 
     // 1. Simple GET route
-    server.add_route("/", vec![Method::GET], |_req| async move {
+    server.get("/", |_req| async move {
         Ok(HttpResponse::new(StatusCode::Ok)
-            .with_content_type("text/html")
-            .with_body_string("<html><body><h1>Welcome to microhttp-rs!</h1></body></html>"))
+            .with_html("<html><body><h1>Welcome to microhttp-rs!</h1></body></html>"))
     }).await;
 
-    // 2. Route with path parameter
-    server.add_route("/hello", vec![Method::GET], |req| async move {
+    // 2. Route with query parameter
+    server.get("/hello", |req| async move {
         // Get the 'name' query parameter if it exists
         let name = req.get_query_param("name").map_or("World", |s| s.as_str());
 
@@ -39,6 +39,15 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .with_body_string(format!("Hello, {name}!")))
     }).await;
 
+    // 3. Route with path parameter
+    server.get("/users/:id", |req| async move {
+        let id = req.get_path_param("id").map_or("unknown", |s| s.as_str());
+
+        Ok(HttpResponse::new(StatusCode::Ok)
+            .with_content_type("text/plain")
+            .with_body_string(format!("User ID: {id}")))
+    }).await;
+
     // Define data structures for JSON
     #[derive(Debug, Serialize, Deserialize)]
     struct Message {
@@ -52,7 +61,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         age: Option<u32>,
     }
 
-    // 3. Route that handles multiple HTTP methods with JSON
+    // 4. Route that handles multiple HTTP methods with JSON
     server.add_route("/api/data", vec![Method::GET, Method::POST], |req| async move {
         match req.method {
             Method::GET => {
@@ -79,8 +88,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }).await;
 
-    // 4. Route that demonstrates JSON request parsing
-    server.add_route("/api/users", vec![Method::POST], |req| async move {
+    // 5. Route that demonstrates JSON request parsing
+    server.post("/api/users", |req| async move {
         // Check if the request is JSON
         if !req.is_json() {
             return Ok(HttpResponse::new(StatusCode::BadRequest)
@@ -108,8 +117,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         }
     }).await;
 
-    // 5. Route that returns different status codes
-    server.add_route("/status", vec![Method::GET], |req| async move {
+    // 6. Route that returns different status codes
+    server.get("/status", |req| async move {
         // Get the 'code' query parameter if it exists
         let status_code = match req.get_query_param("code").map(|s| s.as_str()) {
             Some("200") => StatusCode::Ok,
@@ -125,8 +134,8 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             .with_body_string(format!("Status: {}", status_code as u16)))
     }).await;
 
-    // 6. Route that demonstrates headers
-    server.add_route("/headers", vec![Method::GET], |req| async move {
+    // 7. Route that demonstrates headers
+    server.get("/headers", |req| async move {
         let mut response_body = String::from("Request Headers:\n\n");
 
         for (name, value) in &req.headers {
@@ -142,6 +151,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     info!("Server configured with the following routes:");
     info!("  GET  /");
     info!("  GET  /hello");
+    info!("  GET  /users/:id");
     info!("  GET  /api/data");
     info!("  POST /api/data");
     info!("  POST /api/users");
@@ -59,7 +59,19 @@ async fn main() -> std::io::Result<()> {
                                 ParserError::InvalidMethod(method) => format!("Invalid HTTP method: {method}"),
                                 ParserError::InvalidVersion(version) => format!("Invalid HTTP version: {version}"),
                                 ParserError::MalformedRequestLine(line) => format!("Malformed request line: {line}"),
+                                ParserError::MalformedStatusLine(line) => format!("Malformed status line: {line}"),
+                                ParserError::AmbiguousBodyLength(reason) => format!("Ambiguous body length: {reason}"),
+                                ParserError::IncompleteBody(reason) => format!("Incomplete request body: {reason}"),
+                                ParserError::IncompleteHeaders(reason) => format!("Incomplete header section: {reason}"),
+                                ParserError::InvalidLineEnding => "Line ending is not CRLF".to_string(),
+                                ParserError::TooManyHeaders(limit) => format!("Too many headers (the limit is {limit})"),
+                                ParserError::HeadersTooLarge(limit) => format!("Header section too large (the limit is {limit} bytes)"),
                                 ParserError::EmptyRequest => "Empty request".to_string(),
+                                ParserError::InvalidChunkEncoding(line) => format!("Invalid chunk encoding: {line}"),
+                                ParserError::QueryError(e) => format!("Query string error: {e}"),
+                                ParserError::FormError(e) => format!("Form parsing error: {e}"),
+                                ParserError::InvalidMultipart(reason) => format!("Invalid multipart body: {reason}"),
+                                ParserError::MultipartTooLarge(limit) => format!("Multipart body exceeds {limit} bytes"),
                                 ParserError::JsonError(e) => format!("JSON parsing error: {e}"),
                             };
 
@@ -0,0 +1,21 @@
+//! Error types for the HTTP client.
+
+use thiserror::Error;
+
+use crate::parser::Error as ParserError;
+
+/// Errors that can occur while sending a request and reading the response.
+#[derive(Debug, Error)]
+pub enum Error {
+    /// I/O error.
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    /// The response is malformed.
+    #[error("Invalid response: {0}")]
+    ParseError(#[from] ParserError),
+
+    /// The server closed the connection before the whole response arrived.
+    #[error("Connection closed before the response was complete")]
+    IncompleteResponse,
+}
@@ -0,0 +1,13 @@
+//! A minimal HTTP/1.1 client over plain TCP.
+//!
+//! The client sends a single [`HttpRequest`](crate::parser::HttpRequest) per connection and
+//! reads the response into an [`HttpResponseParsed`](crate::parser::HttpResponseParsed), which
+//! is enough for round-trip tests and simple integrations with other servers.
+
+mod error;
+mod send;
+mod tests;
+
+// Re-export public items
+pub use error::Error;
+pub use send::send;
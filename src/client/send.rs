@@ -0,0 +1,108 @@
+//! Sending requests and reading their responses.
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, ToSocketAddrs};
+
+use crate::client::error::Error;
+use crate::parser::{Error as ParserError, HttpRequest, HttpResponseParsed, Method, ParseOptions, decode_chunked, find_header_end, parse_response_head};
+
+/// Send a request to a server over a new TCP connection and read the response.
+///
+/// The request is written as [`HttpRequest::to_bytes`] produces it, so an HTTP/1.1 request
+/// needs a `Host` header. Interim `1xx` responses other than `101 Switching Protocols` are
+/// skipped. The body of the response is read as its `Content-Length` or chunked encoding
+/// says, or until the server closes the connection if it gives neither; the connection is
+/// closed once the response has been read.
+///
+/// # Examples
+///
+/// ```no_run
+/// use microhttp_rs::{HeaderMap, HttpRequest, HttpVersion, Method, client};
+///
+/// # async fn run() -> Result<(), microhttp_rs::ClientError> {
+/// let mut request = HttpRequest::new(Method::GET, "/".to_string(), HttpVersion::Http11, HeaderMap::new());
+/// request.append_header("Host", "localhost");
+/// let response = client::send("127.0.0.1:8080", &request).await?;
+/// println!("{} {}", response.status, String::from_utf8_lossy(&response.body));
+/// # Ok(())
+/// # }
+/// ```
+pub async fn send(addr: impl ToSocketAddrs, request: &HttpRequest) -> Result<HttpResponseParsed, Error> {
+    let mut stream = TcpStream::connect(addr).await?;
+    stream.write_all(&request.to_bytes()).await?;
+    stream.flush().await?;
+    read_response(&mut stream, request.method == Method::HEAD).await
+}
+
+/// Read a response from a connection.
+///
+/// Responses to HEAD requests, and `204` and `304` responses, have no body whatever their
+/// headers say.
+pub(crate) async fn read_response(
+    stream: &mut (impl AsyncRead + Unpin),
+    is_head: bool,
+) -> Result<HttpResponseParsed, Error> {
+    let mut buf = Vec::new();
+    let mut chunk = vec![0; 8192];
+
+    loop {
+        // Read until the end of the header section
+        let header_end = loop {
+            if let Some(end) = find_header_end(&buf) {
+                break end;
+            }
+            let n = stream.read(&mut chunk).await?;
+            if n == 0 {
+                return Err(Error::IncompleteResponse);
+            }
+            buf.extend_from_slice(&chunk[..n]);
+        };
+        let mut response = parse_response_head(&buf[..header_end], ParseOptions::default())?;
+        buf.drain(..header_end);
+
+        // An interim response is followed by the final one on the same connection
+        if (100..200).contains(&response.status) && response.status != 101 {
+            continue;
+        }
+        if is_head || (100..200).contains(&response.status) || matches!(response.status, 204 | 304) {
+            return Ok(response);
+        }
+
+        if response.is_chunked() {
+            loop {
+                let chunked = decode_chunked(&buf, false)?;
+                if chunked.complete {
+                    response.body = chunked.data;
+                    let trailers = chunked.trailers.into_iter().map(|(name, value)| (name.to_string(), value.to_string()));
+                    response.headers.extend(trailers);
+                    return Ok(response);
+                }
+                let n = stream.read(&mut chunk).await?;
+                if n == 0 {
+                    return Err(Error::IncompleteResponse);
+                }
+                buf.extend_from_slice(&chunk[..n]);
+            }
+        }
+
+        match response.get_header("Content-Length") {
+            Some(value) => {
+                let content_length = value.parse::<usize>().map_err(|_| ParserError::InvalidHeaderFormat)?;
+                while buf.len() < content_length {
+                    let n = stream.read(&mut chunk).await?;
+                    if n == 0 {
+                        return Err(Error::IncompleteResponse);
+                    }
+                    buf.extend_from_slice(&chunk[..n]);
+                }
+                buf.truncate(content_length);
+            }
+            // Without a length, the body ends when the server closes the connection
+            None => {
+                stream.read_to_end(&mut buf).await?;
+            }
+        }
+        response.body = buf;
+        return Ok(response);
+    }
+}
@@ -0,0 +1,115 @@
+//! Tests for the HTTP client.
+
+#[cfg(test)]
+mod client_tests {
+    use std::net::SocketAddr;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    use crate::client::send::read_response;
+    use crate::client::{Error, send};
+    use crate::parser::{HeaderMap, HttpRequest, HttpVersion, Method};
+    use crate::server::{HttpResponse, HttpServer, ServerConfig, StatusCode};
+
+    fn request(method: Method, path: &str) -> HttpRequest {
+        let mut request = HttpRequest::new(method, path.to_string(), HttpVersion::Http11, HeaderMap::new());
+        request.append_header("Host", "localhost");
+        request
+    }
+
+    /// Serve a single connection with a canned response, returning the address to send to.
+    async fn canned_server(response: &'static [u8]) -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 1024];
+            let _ = socket.read(&mut buf).await;
+            socket.write_all(response).await.unwrap();
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_with_the_server() {
+        let config = ServerConfig {
+            addr: "127.0.0.1:0".parse().unwrap(),
+            ..ServerConfig::default()
+        };
+        let server = HttpServer::new(config).bind().await.unwrap();
+        server
+            .add_route("/echo", vec![Method::POST], |req| async move {
+                HttpResponse::new(StatusCode::Created)
+                    .with_header("X-Method", req.method.to_string())
+                    .with_body_bytes(req.body)
+            })
+            .await;
+        server.add_route("/hello", vec![Method::GET], |_req| async { "Hello" }).await;
+        let addr = server.local_addr();
+        tokio::spawn(server.serve());
+
+        let mut post = request(Method::POST, "/echo");
+        post.append_header("Content-Length", "5");
+        post.body = b"hello".to_vec();
+        let response = send(addr, &post).await.unwrap();
+        assert_eq!(response.version, HttpVersion::Http11);
+        assert_eq!(response.status, 201);
+        assert_eq!(response.reason, "Created");
+        assert_eq!(response.get_header("x-method"), Some("POST"));
+        assert_eq!(response.body, b"hello");
+
+        let response = send(addr, &request(Method::GET, "/hello")).await.unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"Hello");
+
+        // HEAD responses announce the length of a body they don't carry
+        let response = send(addr, &request(Method::HEAD, "/hello")).await.unwrap();
+        assert_eq!(response.get_header("Content-Length"), Some("5"));
+        assert!(response.body.is_empty());
+
+        let response = send(addr, &request(Method::GET, "/missing")).await.unwrap();
+        assert_eq!(response.status, 404);
+    }
+
+    #[tokio::test]
+    async fn test_response_bodies() {
+        // Chunked, with a trailer, after an interim response
+        let addr = canned_server(
+            b"HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n6\r\n world\r\n0\r\nX-Checksum: 42\r\n\r\n",
+        )
+        .await;
+        let response = send(addr, &request(Method::GET, "/")).await.unwrap();
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body, b"hello world");
+        assert_eq!(response.get_header("X-Checksum"), Some("42"));
+
+        // Delimited by closing the connection, with an unknown status code
+        let addr = canned_server(b"HTTP/1.0 299 Whatever It Is\r\n\r\nuntil the end").await;
+        let response = send(addr, &request(Method::GET, "/")).await.unwrap();
+        assert_eq!(response.version, HttpVersion::Http10);
+        assert_eq!(response.status, 299);
+        assert_eq!(response.reason, "Whatever It Is");
+        assert_eq!(response.body, b"until the end");
+
+        // Bytes after the announced length are left alone
+        let mut input: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nokHTTP/1.1";
+        let response = read_response(&mut input, false).await.unwrap();
+        assert_eq!(response.body, b"ok");
+    }
+
+    #[tokio::test]
+    async fn test_incomplete_and_malformed_responses() {
+        let mut input: &[u8] = b"HTTP/1.1 200 OK\r\nContent-Length: 10\r\n\r\nshort";
+        assert!(matches!(read_response(&mut input, false).await, Err(Error::IncompleteResponse)));
+
+        let mut input: &[u8] = b"HTTP/1.1 200 OK\r\nContent-";
+        assert!(matches!(read_response(&mut input, false).await, Err(Error::IncompleteResponse)));
+
+        let mut input: &[u8] = b"HTTP/1.1 2000 OK\r\n\r\n";
+        assert!(matches!(read_response(&mut input, false).await, Err(Error::ParseError(_))));
+
+        let mut input: &[u8] = b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhel";
+        assert!(matches!(read_response(&mut input, false).await, Err(Error::IncompleteResponse)));
+    }
+}
@@ -5,12 +5,17 @@
 //!
 //! # Features
 //!
-//! - Parse HTTP requests from byte slices
-//! - Support for common HTTP methods (GET, POST, PUT, DELETE, HEAD, OPTIONS, PATCH)
-//! - Support for HTTP versions 1.0, 1.1, and 2.0
+//! - Parse HTTP requests from byte slices, including their body: `Content-Length` bodies are
+//!   capped at the declared length and chunked bodies are decoded. The header section must be
+//!   valid UTF-8, while the body is kept as raw bytes.
+//! - Parse HTTP responses from byte slices
+//! - Support for common HTTP methods (GET, POST, PUT, DELETE, HEAD, OPTIONS, PATCH, TRACE,
+//!   CONNECT) and extension methods
+//! - Support for HTTP versions 0.9, 1.0, 1.1, and 2.0
 //! - JSON serialization and deserialization for request and response bodies
 //! - Proper error handling with descriptive error messages
 //! - Simple HTTP server implementation with routing and async handlers
+//! - A minimal HTTP/1.1 client for sending requests and reading the responses
 //!
 //! # Examples
 //!
@@ -39,13 +44,12 @@
 //! ```
 //! use microhttp_rs::{parse_request, ParserError};
 //!
-//! let invalid_request = b"INVALID /index.html HTTP/1.1\r\nHost: example.com\r\n\r\n";
+//! // Extension methods are accepted, but a method must be a token
+//! let invalid_request = b"INV@LID /index.html HTTP/1.1\r\nHost: example.com\r\n\r\n";
 //!
 //! match parse_request(invalid_request) {
-//!     Ok(_) => println!("Request parsed successfully"),
-//!     Err(ParserError::InvalidMethod(method)) => println!("Invalid method: {}", method),
-//!     Err(ParserError::MalformedRequestLine(line)) => println!("Malformed request line: {}", line),
-//!     Err(err) => println!("Other error: {}", err),
+//!     Err(ParserError::InvalidMethod(method)) => assert_eq!(method, "INV@LID"),
+//!     other => panic!("expected an invalid method, got {other:?}"),
 //! }
 //! ```
 //!
@@ -88,6 +92,25 @@ pub mod parser;
 // Export the server module
 pub mod server;
 
+// Export the client module
+pub mod client;
+
 // Re-export commonly used items for convenience
-pub use parser::{Error as ParserError, HttpRequest, HttpVersion, Method, parse_request};
-pub use server::{Error as ServerError, HttpResponse, HttpServer, ServerConfig, StatusCode};
+pub use parser::{
+    AuthScheme, BodyReader, Error as ParserError, HeaderMap, HttpRequest, HttpRequestBuilder, HttpRequestRef, HttpResponseParsed, HttpVersion, Method, Multipart, MultipartLimits,
+    ParseOptions, ParseStatus, Part, RequestParser, parse_request, remove_hop_by_hop_headers, parse_request_partial, parse_response,
+    parse_request_partial_with_options, parse_request_ref, parse_request_ref_with_options, parse_request_with_options,
+};
+pub use server::{
+    ACCESS_LOG_TARGET, AccessLogFormat, BodyStream, BoundServer, Cookie, CorsConfig, Error as ServerError, HttpResponse, HttpServer,
+    IntoHandlerResult, IntoResponse, MatchResult, Metrics, Next, Problem, Router, SameSite, ServerConfig, ServerConfigBuilder, ServerMetrics,
+    SseEvent, SseResponse, StatusCode, basic_auth, html_escape, mime_from_extension, proxy_to, rate_limit,
+};
+pub use client::Error as ClientError;
+#[cfg(feature = "tls")]
+pub use server::{load_tls_config, tls_config_from_pem};
+#[cfg(feature = "websocket")]
+pub use server::{WebSocket, WebSocketMessage, is_websocket_upgrade, websocket_upgrade};
+/// The rustls crate, for building custom TLS configurations.
+#[cfg(feature = "tls")]
+pub use tokio_rustls::rustls;
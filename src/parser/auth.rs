@@ -0,0 +1,67 @@
+//! Credentials from the Authorization header.
+
+use crate::parser::base64;
+
+/// Credentials sent in an `Authorization` header.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthScheme {
+    /// HTTP Basic authentication, with the base64-decoded user name and password.
+    Basic {
+        /// The user name
+        username: String,
+        /// The password
+        password: String,
+    },
+    /// A bearer token, as used by OAuth 2.0.
+    Bearer {
+        /// The token
+        token: String,
+    },
+    /// Any other scheme, with the credentials left as sent.
+    Other {
+        /// The scheme name as sent
+        scheme: String,
+        /// The credentials following the scheme name
+        value: String,
+    },
+}
+
+impl AuthScheme {
+    /// Parse the value of an `Authorization` header.
+    ///
+    /// The scheme name is matched case-insensitively.
+    ///
+    /// # Returns
+    ///
+    /// The credentials, or None if the header is empty or holds malformed Basic or Bearer
+    /// credentials
+    pub fn parse(header: &str) -> Option<AuthScheme> {
+        let header = header.trim();
+        let (scheme, value) = header.split_once(' ').unwrap_or((header, ""));
+        let value = value.trim();
+        if scheme.is_empty() {
+            return None;
+        }
+
+        if scheme.eq_ignore_ascii_case("Basic") {
+            let decoded = String::from_utf8(base64::decode(value)?).ok()?;
+            let (username, password) = decoded.split_once(':')?;
+            Some(AuthScheme::Basic {
+                username: username.to_string(),
+                password: password.to_string(),
+            })
+        } else if scheme.eq_ignore_ascii_case("Bearer") {
+            if value.is_empty() {
+                return None;
+            }
+            Some(AuthScheme::Bearer {
+                token: value.to_string(),
+            })
+        } else {
+            Some(AuthScheme::Other {
+                scheme: scheme.to_string(),
+                value: value.to_string(),
+            })
+        }
+    }
+}
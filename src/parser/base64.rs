@@ -0,0 +1,64 @@
+//! Standard base64 (RFC 4648) encoding and decoding.
+
+/// Decode standard base64 with `=` padding.
+///
+/// # Returns
+///
+/// The decoded bytes, or None if the input isn't valid base64
+pub(crate) fn decode(input: &str) -> Option<Vec<u8>> {
+    let input = input.as_bytes();
+    if !input.len().is_multiple_of(4) {
+        return None;
+    }
+
+    let mut out = Vec::with_capacity(input.len() / 4 * 3);
+    for (i, group) in input.chunks(4).enumerate() {
+        let is_last = i == input.len() / 4 - 1;
+        let padding = group.iter().rev().take_while(|&&c| c == b'=').count();
+        if padding > 2 || (padding > 0 && !is_last) {
+            return None;
+        }
+
+        let mut value = 0u32;
+        for &c in &group[..4 - padding] {
+            value = value << 6 | sextet(c)? as u32;
+        }
+        value <<= 6 * padding as u32;
+
+        let bytes = value.to_be_bytes();
+        out.extend_from_slice(&bytes[1..4 - padding]);
+    }
+    Some(out)
+}
+
+/// Encode bytes as standard base64 with `=` padding.
+#[cfg(feature = "websocket")]
+pub(crate) fn encode(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+    let mut out = String::with_capacity(input.len().div_ceil(3) * 4);
+    for group in input.chunks(3) {
+        let mut bytes = [0; 3];
+        bytes[..group.len()].copy_from_slice(group);
+        let value = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= group.len() {
+                out.push(ALPHABET[(value >> (18 - 6 * i) & 63) as usize] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+fn sextet(c: u8) -> Option<u8> {
+    match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
@@ -0,0 +1,147 @@
+//! Readers over request bodies, which the server may fill from the connection as the handler
+//! reads them.
+
+use std::fmt;
+use std::io;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll, ready};
+
+use tokio::io::{AsyncRead, ReadBuf};
+use tokio::sync::{mpsc, oneshot};
+
+/// A reader over a request body, from [`HttpRequest::body_reader`].
+///
+/// For routes added with [`HttpServer::add_streaming_route`], the body is read from the
+/// connection as the reader is polled, respecting its `Content-Length` or chunked framing, and
+/// the reader ends where the body does. A body cut short by the client, or with malformed
+/// chunks, fails the read with an error. For other routes the reader serves the body that was
+/// already received.
+///
+/// [`HttpRequest::body_reader`]: crate::parser::HttpRequest::body_reader
+/// [`HttpServer::add_streaming_route`]: crate::server::HttpServer::add_streaming_route
+pub struct BodyReader {
+    inner: Inner,
+}
+
+enum Inner {
+    Buffered {
+        body: Vec<u8>,
+        pos: usize,
+    },
+    Streamed {
+        /// Tells the connection to start reading the body, on the first read
+        start: Option<oneshot::Sender<()>>,
+        receiver: mpsc::Receiver<io::Result<Vec<u8>>>,
+        chunk: Vec<u8>,
+        pos: usize,
+        done: bool,
+    },
+}
+
+impl BodyReader {
+    /// Create a reader over a body that has already been received.
+    pub(crate) fn buffered(body: Vec<u8>) -> Self {
+        Self {
+            inner: Inner::Buffered { body, pos: 0 },
+        }
+    }
+
+    /// Create a reader over a body still on the connection.
+    ///
+    /// `start` is signalled on the first read, after which the body arrives through `receiver`
+    /// as chunks of data ending with an empty one, or as an error.
+    pub(crate) fn streamed(start: oneshot::Sender<()>, receiver: mpsc::Receiver<io::Result<Vec<u8>>>) -> Self {
+        Self {
+            inner: Inner::Streamed {
+                start: Some(start),
+                receiver,
+                chunk: Vec::new(),
+                pos: 0,
+                done: false,
+            },
+        }
+    }
+}
+
+impl AsyncRead for BodyReader {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        match &mut self.inner {
+            Inner::Buffered { body, pos } => {
+                let n = buf.remaining().min(body.len() - *pos);
+                buf.put_slice(&body[*pos..*pos + n]);
+                *pos += n;
+                Poll::Ready(Ok(()))
+            }
+            Inner::Streamed { start, receiver, chunk, pos, done } => {
+                if let Some(start) = start.take() {
+                    let _ = start.send(());
+                }
+                loop {
+                    if *pos < chunk.len() {
+                        let n = buf.remaining().min(chunk.len() - *pos);
+                        buf.put_slice(&chunk[*pos..*pos + n]);
+                        *pos += n;
+                        return Poll::Ready(Ok(()));
+                    }
+                    if *done {
+                        return Poll::Ready(Ok(()));
+                    }
+                    match ready!(receiver.poll_recv(cx)) {
+                        // An empty chunk marks the end of the body
+                        Some(Ok(data)) if data.is_empty() => *done = true,
+                        Some(Ok(data)) => {
+                            *chunk = data;
+                            *pos = 0;
+                        }
+                        Some(Err(e)) => return Poll::Ready(Err(e)),
+                        None => {
+                            return Poll::Ready(Err(io::Error::new(
+                                io::ErrorKind::UnexpectedEof,
+                                "the request body is no longer available",
+                            )));
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for BodyReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BodyReader").finish_non_exhaustive()
+    }
+}
+
+/// The slot of a request holding the reader of a streamed body until the handler takes it.
+///
+/// Clones of a request share the slot, so only one of them gets the reader.
+#[derive(Clone)]
+pub(crate) struct StreamedBody(Arc<Mutex<Option<BodyReader>>>);
+
+impl StreamedBody {
+    /// Create a slot holding `reader`.
+    pub(crate) fn new(reader: BodyReader) -> Self {
+        Self(Arc::new(Mutex::new(Some(reader))))
+    }
+
+    /// Take the reader, if no clone of the request took it first.
+    pub(crate) fn take(&self) -> Option<BodyReader> {
+        self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take()
+    }
+}
+
+impl PartialEq for StreamedBody {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for StreamedBody {}
+
+impl fmt::Debug for StreamedBody {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamedBody").finish_non_exhaustive()
+    }
+}
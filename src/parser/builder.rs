@@ -0,0 +1,109 @@
+//! A builder for requests made in code, such as in handler tests.
+
+use std::collections::HashMap;
+use std::net::SocketAddr;
+
+use serde::Serialize;
+
+use crate::parser::error::Error;
+use crate::parser::header_map::HeaderMap;
+use crate::parser::method::Method;
+use crate::parser::request::HttpRequest;
+use crate::parser::version::HttpVersion;
+
+/// A builder for [`HttpRequest`], created with [`HttpRequest::builder`].
+///
+/// Requests start as `GET / HTTP/1.1` without headers or a body.
+#[derive(Debug, Clone)]
+pub struct HttpRequestBuilder {
+    method: Method,
+    path: String,
+    version: HttpVersion,
+    headers: HeaderMap,
+    body: Vec<u8>,
+    path_params: HashMap<String, String>,
+    peer_addr: Option<SocketAddr>,
+}
+
+impl Default for HttpRequestBuilder {
+    fn default() -> Self {
+        Self {
+            method: Method::GET,
+            path: "/".to_string(),
+            version: HttpVersion::Http11,
+            headers: HeaderMap::new(),
+            body: Vec::new(),
+            path_params: HashMap::new(),
+            peer_addr: None,
+        }
+    }
+}
+
+impl HttpRequestBuilder {
+    /// Set the HTTP method.
+    pub fn method(mut self, method: Method) -> Self {
+        self.method = method;
+        self
+    }
+
+    /// Set the path, which may include a query string.
+    pub fn path(mut self, path: impl Into<String>) -> Self {
+        self.path = path.into();
+        self
+    }
+
+    /// Set the HTTP version.
+    pub fn version(mut self, version: HttpVersion) -> Self {
+        self.version = version;
+        self
+    }
+
+    /// Add a header; repeated headers keep every value, as when parsed.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    /// Set the request body.
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Set the request body to a value serialized as JSON, with a `Content-Type` of
+    /// `application/json` unless one was added.
+    pub fn json_body<T: Serialize>(mut self, value: &T) -> Result<Self, Error> {
+        self.body = serde_json::to_vec(value)?;
+        if !self.headers.contains("Content-Type") {
+            self.headers.append("Content-Type", "application/json");
+        }
+        Ok(self)
+    }
+
+    /// Set a path parameter, as captured by a route pattern such as `/users/:id`.
+    pub fn path_param(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.path_params.insert(name.into(), value.into());
+        self
+    }
+
+    /// Set the address of the client.
+    pub fn peer_addr(mut self, peer_addr: SocketAddr) -> Self {
+        self.peer_addr = Some(peer_addr);
+        self
+    }
+
+    /// Build the request.
+    ///
+    /// The query parameters are parsed from the path as for a received request, and a request
+    /// with a body gets a `Content-Length` header unless one was added.
+    pub fn build(self) -> HttpRequest {
+        let mut request = HttpRequest::new(self.method, self.path, self.version, self.headers);
+        if !self.body.is_empty() && !request.has_header("Content-Length") {
+            request.append_header("Content-Length", self.body.len().to_string());
+        }
+        request.body = self.body;
+        request.path_params = self.path_params;
+        request.peer_addr = self.peer_addr;
+        request
+    }
+}
@@ -0,0 +1,126 @@
+//! Incremental decoding of bodies sent with the chunked transfer encoding.
+
+use crate::parser::error::Error;
+use crate::parser::request::{next_line, parse_chunk_size};
+
+/// A decoder for a chunked body that arrives in pieces.
+///
+/// The decoder keeps its place between calls, so every byte of the body is decoded once no
+/// matter how many pieces it arrives in, unlike `decode_chunked`, which decodes a whole body
+/// at once.
+#[derive(Debug)]
+pub(crate) struct ChunkedDecoder {
+    state: State,
+    strict: bool,
+    trailers: Vec<(String, String)>,
+}
+
+/// A position in a chunked body.
+#[derive(Debug)]
+enum State {
+    /// Before a chunk size line
+    Size,
+    /// Within the data of a chunk, with the number of bytes left
+    Data(usize),
+    /// Before the line break after the data of a chunk
+    DataEnd,
+    /// Within the trailer section
+    Trailers,
+    /// After the end of the body
+    Done,
+}
+
+impl ChunkedDecoder {
+    /// Create a decoder positioned at the start of a body.
+    ///
+    /// # Arguments
+    ///
+    /// * `strict` - Whether the chunk size lines, chunk delimiters and trailer lines must end
+    ///   with `\r\n`
+    pub(crate) fn new(strict: bool) -> Self {
+        Self {
+            state: State::Size,
+            strict,
+            trailers: Vec::new(),
+        }
+    }
+
+    /// Decode the next piece of the body, appending the chunk data to `data`.
+    ///
+    /// Decoding stops at the end of the body or of the complete lines in `input`; the bytes
+    /// of a line cut short must be passed again, followed by the rest of the body.
+    ///
+    /// # Returns
+    ///
+    /// The number of bytes of `input` decoded, or an error if a chunk size line, chunk
+    /// delimiter or trailer line is malformed
+    pub(crate) fn decode(&mut self, input: &[u8], data: &mut Vec<u8>) -> Result<usize, Error> {
+        let mut pos = 0;
+        loop {
+            match &mut self.state {
+                State::Size => {
+                    let Some((line, next)) = next_line(input, pos, self.strict)? else {
+                        return Ok(pos);
+                    };
+                    let size = parse_chunk_size(line)?;
+                    pos = next;
+                    self.state = if size == 0 { State::Trailers } else { State::Data(size) };
+                }
+                State::Data(remaining) => {
+                    let n = (*remaining).min(input.len() - pos);
+                    if n == 0 {
+                        return Ok(pos);
+                    }
+                    data.extend_from_slice(&input[pos..pos + n]);
+                    pos += n;
+                    *remaining -= n;
+                    if *remaining == 0 {
+                        self.state = State::DataEnd;
+                    }
+                }
+                State::DataEnd => {
+                    // Each chunk must be followed by a line break
+                    let len = match &input[pos..] {
+                        [b'\r', b'\n', ..] => 2,
+                        [b'\n', ..] if !self.strict => 1,
+                        [] | [b'\r'] => return Ok(pos),
+                        [b'\n', ..] => return Err(Error::InvalidLineEnding),
+                        _ => return Err(Error::InvalidChunkEncoding("Missing line break after chunk data".to_string())),
+                    };
+                    pos += len;
+                    self.state = State::Size;
+                }
+                State::Trailers => {
+                    // The trailer section ends with an empty line
+                    let Some((line, next)) = next_line(input, pos, self.strict)? else {
+                        return Ok(pos);
+                    };
+                    pos = next;
+                    if line.is_empty() {
+                        self.state = State::Done;
+                        continue;
+                    }
+                    let line = std::str::from_utf8(line).map_err(|_| Error::InvalidHeaderFormat)?;
+                    let (name, value) = line.split_once(':').ok_or(Error::InvalidHeaderFormat)?;
+                    self.trailers.push((name.trim().to_string(), value.trim().to_string()));
+                }
+                State::Done => return Ok(pos),
+            }
+        }
+    }
+
+    /// Check whether the terminating chunk and trailer section were decoded.
+    pub(crate) fn is_done(&self) -> bool {
+        matches!(self.state, State::Done)
+    }
+
+    /// Get the trailer fields decoded so far.
+    pub(crate) fn trailers(&self) -> &[(String, String)] {
+        &self.trailers
+    }
+
+    /// Take the trailer fields decoded so far.
+    pub(crate) fn take_trailers(&mut self) -> Vec<(String, String)> {
+        std::mem::take(&mut self.trailers)
+    }
+}
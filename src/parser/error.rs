@@ -2,7 +2,7 @@
 
 use thiserror::Error;
 
-/// Errors that can occur during HTTP request parsing.
+/// Errors that can occur during HTTP request and response parsing.
 #[derive(Debug, Error)]
 pub enum Error {
     /// The HTTP method in the request is not supported.
@@ -17,8 +17,12 @@ pub enum Error {
     #[error("Malformed request line: {0}")]
     MalformedRequestLine(String),
 
+    /// The status line of a response is malformed.
+    #[error("Malformed status line: {0}")]
+    MalformedStatusLine(String),
+
     /// The HTTP version in the request is not supported.
-    #[error("Invalid HTTP version: {0}")]
+    #[error("Invalid HTTP version: {0} (expected HTTP/0.9, HTTP/1.0, HTTP/1.1 or HTTP/2)")]
     InvalidVersion(String),
 
     /// A required header is missing from the request.
@@ -29,10 +33,56 @@ pub enum Error {
     #[error("Invalid header format")]
     InvalidHeaderFormat,
 
+    /// The request has both `Content-Length` and `Transfer-Encoding` headers, or
+    /// `Content-Length` values that disagree, so its body length is ambiguous.
+    #[error("Ambiguous body length: {0}")]
+    AmbiguousBodyLength(String),
+
+    /// The connection closed before the whole body announced by `Content-Length`, or the
+    /// final chunk of a chunked body, arrived.
+    #[error("Incomplete request body: {0}")]
+    IncompleteBody(String),
+
+    /// The connection closed in the middle of a line of the header section.
+    #[error("Incomplete header section: {0}")]
+    IncompleteHeaders(String),
+
+    /// A line ends with a bare `\r` or `\n` instead of `\r\n`, in strict parsing.
+    #[error("Line ending is not CRLF")]
+    InvalidLineEnding,
+
+    /// The request has more header lines than the limit.
+    #[error("Too many headers (the limit is {0})")]
+    TooManyHeaders(usize),
+
+    /// The request line and header section are larger than the limit in bytes.
+    #[error("Header section too large (the limit is {0} bytes)")]
+    HeadersTooLarge(usize),
+
     /// The request is empty.
     #[error("Empty request")]
     EmptyRequest,
 
+    /// The chunked transfer encoding of the body is malformed.
+    #[error("Invalid chunk encoding: {0}")]
+    InvalidChunkEncoding(String),
+
+    /// The query string doesn't match the requested type.
+    #[error("Query string error: {0}")]
+    QueryError(serde_urlencoded::de::Error),
+
+    /// The form body doesn't match the requested type.
+    #[error("Form parsing error: {0}")]
+    FormError(serde_urlencoded::de::Error),
+
+    /// The multipart body is malformed.
+    #[error("Invalid multipart body: {0}")]
+    InvalidMultipart(String),
+
+    /// A part, or all parts together, exceed the multipart size limit.
+    #[error("Multipart body exceeds the size limit of {0} bytes")]
+    MultipartTooLarge(usize),
+
     /// Error parsing JSON.
     #[error("JSON parsing error: {0}")]
     JsonError(#[from] serde_json::Error),
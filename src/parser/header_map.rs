@@ -0,0 +1,169 @@
+//! An ordered, case-insensitive collection of header fields.
+
+use std::fmt;
+
+/// The header fields of a request or response.
+///
+/// Fields are kept in insertion order with their names spelled as given, and a name may
+/// appear more than once, as `Set-Cookie` or a repeated `Accept` does. Lookups match names
+/// case-insensitively.
+///
+/// # Examples
+///
+/// ```
+/// use microhttp_rs::HeaderMap;
+///
+/// let mut headers = HeaderMap::new();
+/// headers.append("Set-Cookie", "a=1");
+/// headers.append("set-cookie", "b=2");
+/// headers.insert("Content-Type", "text/plain");
+///
+/// assert_eq!(headers.get("SET-COOKIE").map(String::as_str), Some("b=2"));
+/// assert_eq!(headers.get_all("Set-Cookie"), vec!["a=1", "b=2"]);
+///
+/// headers.insert("Set-Cookie", "c=3");
+/// assert_eq!(headers.get_all("Set-Cookie"), vec!["c=3"]);
+/// assert_eq!(headers.remove("content-type").as_deref(), Some("text/plain"));
+/// assert_eq!(headers.len(), 1);
+/// ```
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct HeaderMap {
+    entries: Vec<(String, String)>,
+}
+
+impl HeaderMap {
+    /// Create an empty header map.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Get a header value; when a header is repeated, the last value is returned.
+    pub fn get(&self, name: &str) -> Option<&String> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v)
+    }
+
+    /// Get every value of a header, in insertion order; empty if it doesn't exist.
+    pub fn get_all(&self, name: &str) -> Vec<&String> {
+        self.entries
+            .iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v)
+            .collect()
+    }
+
+    /// Check if a header exists.
+    pub fn contains(&self, name: &str) -> bool {
+        self.entries.iter().any(|(k, _)| k.eq_ignore_ascii_case(name))
+    }
+
+    /// Set a header, replacing every existing value for the name in any casing.
+    ///
+    /// A replaced header keeps the position of its first occurrence, so the other headers
+    /// aren't reordered; a new header is added at the end.
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        let name = name.into();
+        let value = value.into();
+        match self.entries.iter().position(|(k, _)| k.eq_ignore_ascii_case(&name)) {
+            Some(first) => {
+                let mut rest = self.entries.split_off(first + 1);
+                rest.retain(|(k, _)| !k.eq_ignore_ascii_case(&name));
+                self.entries[first] = (name, value);
+                self.entries.append(&mut rest);
+            }
+            None => self.entries.push((name, value)),
+        }
+    }
+
+    /// Add a header value after any existing values for the name.
+    pub fn append(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.entries.push((name.into(), value.into()));
+    }
+
+    /// Remove every value of a header.
+    ///
+    /// # Returns
+    ///
+    /// The last value removed, or None if the header didn't exist
+    pub fn remove(&mut self, name: &str) -> Option<String> {
+        let mut removed = None;
+        self.entries.retain_mut(|(k, v)| {
+            if k.eq_ignore_ascii_case(name) {
+                removed = Some(std::mem::take(v));
+                false
+            } else {
+                true
+            }
+        });
+        removed
+    }
+
+    /// Keep only the headers for which `keep` returns true.
+    pub fn retain(&mut self, mut keep: impl FnMut(&str, &str) -> bool) {
+        self.entries.retain(|(k, v)| keep(k, v));
+    }
+
+    /// Iterate over every header in insertion order, once per value.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+
+    /// Get the number of header lines, counting each value of a repeated header.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if there are no headers.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Get the header lines, for code that works on plain name and value pairs.
+    pub(crate) fn entries_mut(&mut self) -> &mut Vec<(String, String)> {
+        &mut self.entries
+    }
+}
+
+impl fmt::Debug for HeaderMap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a> IntoIterator for &'a HeaderMap {
+    type Item = (&'a str, &'a str);
+    type IntoIter = std::iter::Map<std::slice::Iter<'a, (String, String)>, fn(&'a (String, String)) -> (&'a str, &'a str)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.iter().map(|(k, v)| (k.as_str(), v.as_str()))
+    }
+}
+
+impl IntoIterator for HeaderMap {
+    type Item = (String, String);
+    type IntoIter = std::vec::IntoIter<(String, String)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for HeaderMap {
+    /// Collect headers in order, keeping repeated names as separate values.
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut headers = Self::new();
+        headers.extend(iter);
+        headers
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> Extend<(K, V)> for HeaderMap {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (name, value) in iter {
+            self.append(name, value);
+        }
+    }
+}
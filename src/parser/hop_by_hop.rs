@@ -0,0 +1,57 @@
+//! Hop-by-hop headers, which describe a single connection rather than the message.
+
+/// The headers that are always hop-by-hop (RFC 9110 section 7.6.1), along with `Trailers`,
+/// the spelling RFC 2616 used for `Trailer`.
+const HOP_BY_HOP_HEADERS: [&str; 9] = [
+    "Connection",
+    "Keep-Alive",
+    "Proxy-Authenticate",
+    "Proxy-Authorization",
+    "TE",
+    "Trailer",
+    "Trailers",
+    "Transfer-Encoding",
+    "Upgrade",
+];
+
+/// Remove the hop-by-hop headers from a list of headers, such as
+/// [`HttpResponseParsed::headers`](crate::parser::HttpResponseParsed::headers): the standard
+/// ones such as `Connection` and `Transfer-Encoding`, and any header named in `Connection`.
+///
+/// A proxy removes these before forwarding a message, since they apply only to the
+/// connection it arrived on.
+///
+/// # Examples
+///
+/// ```
+/// use microhttp_rs::remove_hop_by_hop_headers;
+///
+/// let mut headers = vec![
+///     ("Connection".to_string(), "close, X-Foo".to_string()),
+///     ("X-Foo".to_string(), "1".to_string()),
+///     ("Content-Type".to_string(), "text/plain".to_string()),
+/// ];
+/// remove_hop_by_hop_headers(&mut headers);
+/// assert_eq!(headers, vec![("Content-Type".to_string(), "text/plain".to_string())]);
+/// ```
+pub fn remove_hop_by_hop_headers(headers: &mut Vec<(String, String)>) {
+    let listed = connection_listed(headers.iter().map(|(name, value)| (name.as_str(), value.as_str())));
+    headers.retain(|(name, _)| !is_hop_by_hop(name, &listed));
+}
+
+/// Get the header names listed in the `Connection` headers among a message's headers.
+fn connection_listed<'a>(headers: impl IntoIterator<Item = (&'a str, &'a str)>) -> Vec<String> {
+    headers
+        .into_iter()
+        .filter(|(name, _)| name.eq_ignore_ascii_case("Connection"))
+        .flat_map(|(_, value)| value.split(','))
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .collect()
+}
+
+/// Check whether a header is hop-by-hop, given the names listed in `Connection`.
+fn is_hop_by_hop(name: &str, connection_listed: &[String]) -> bool {
+    HOP_BY_HOP_HEADERS.iter().any(|h| h.eq_ignore_ascii_case(name))
+        || connection_listed.iter().any(|h| h.eq_ignore_ascii_case(name))
+}
@@ -0,0 +1,13 @@
+//! Media types of Content-Type headers.
+
+/// Get the media type of a Content-Type value, lowercased and without parameters, so
+/// `Application/JSON; charset=utf-8` gives `application/json`.
+pub(crate) fn media_type(content_type: &str) -> String {
+    content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase()
+}
+
+/// Check whether a media type, as returned by [`media_type`], is JSON: `application/json` or
+/// a type with the `+json` suffix, such as `application/problem+json`.
+pub(crate) fn is_json_media_type(media_type: &str) -> bool {
+    media_type == "application/json" || media_type.split_once('/').is_some_and(|(_, subtype)| subtype.ends_with("+json"))
+}
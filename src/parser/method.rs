@@ -3,10 +3,12 @@
 use std::fmt;
 use std::str::FromStr;
 
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 use crate::parser::error::Error;
 
 /// HTTP request methods as defined in RFC 7231 and common extensions.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum Method {
     /// GET method: Requests a representation of the specified resource.
     GET,
@@ -22,6 +24,13 @@ pub enum Method {
     OPTIONS,
     /// PATCH method: Applies partial modifications to a resource.
     PATCH,
+    /// TRACE method: Performs a message loop-back test along the path to the target resource.
+    TRACE,
+    /// CONNECT method: Establishes a tunnel to the server identified by the target, which is
+    /// given in authority form (`host:port`) rather than as a path.
+    CONNECT,
+    /// Any other method, such as the WebDAV `PROPFIND` or `MKCOL`, as sent by the client.
+    Other(String),
 }
 
 // Implement FromStr for Method
@@ -37,13 +46,59 @@ impl FromStr for Method {
             "HEAD" => Ok(Method::HEAD),
             "OPTIONS" => Ok(Method::OPTIONS),
             "PATCH" => Ok(Method::PATCH),
+            "TRACE" => Ok(Method::TRACE),
+            "CONNECT" => Ok(Method::CONNECT),
+            _ if is_token(s) => Ok(Method::Other(s.to_string())),
             _ => Err(Error::InvalidMethod(s.to_string())),
         }
     }
 }
 
+impl Method {
+    /// Check whether the method is safe, meaning it is read-only and doesn't change server state
+    /// (RFC 9110 section 9.2.1): GET, HEAD, OPTIONS and TRACE.
+    ///
+    /// Extension methods are not known to be safe.
+    pub fn is_safe(&self) -> bool {
+        matches!(self, Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE)
+    }
+
+    /// Check whether the method is idempotent, meaning repeating a request has the same effect
+    /// as sending it once (RFC 9110 section 9.2.2): the safe methods plus PUT and DELETE.
+    ///
+    /// Extension methods are not known to be idempotent.
+    pub fn is_idempotent(&self) -> bool {
+        self.is_safe() || matches!(self, Method::PUT | Method::DELETE)
+    }
+}
+
+/// Methods are serialized as their names, such as `"GET"` or `"PROPFIND"`.
+impl Serialize for Method {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Methods are deserialized from their names, which must be spelled as sent on the wire.
+impl<'de> Deserialize<'de> for Method {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        name.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 impl fmt::Display for Method {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{self:?}")
+        match self {
+            Method::Other(method) => write!(f, "{method}"),
+            _ => write!(f, "{self:?}"),
+        }
     }
 }
+
+/// Check whether a string is a valid token (RFC 7230 section 3.2.6), as methods and header
+/// names must be.
+pub(crate) fn is_token(s: &str) -> bool {
+    !s.is_empty()
+        && s.bytes().all(|b| b.is_ascii_alphanumeric() || b"!#$%&'*+-.^_`|~".contains(&b))
+}
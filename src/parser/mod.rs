@@ -4,15 +4,58 @@
 //! correctness, and performance.
 
 mod request;
+mod builder;
+mod request_parser;
+mod request_ref;
+mod response;
+mod auth;
+mod chunked;
+mod body_reader;
+mod base64;
+mod header_map;
+mod hop_by_hop;
+mod media_type;
+mod multipart;
+mod negotiation;
+mod options;
+mod path;
 mod method;
 mod version;
 mod error;
+mod tests;
 
 // Re-export public items
 pub use request::HttpRequest;
+pub use builder::HttpRequestBuilder;
+pub use request_parser::{ParseStatus, RequestParser};
+pub use request_ref::HttpRequestRef;
+pub use response::HttpResponseParsed;
+pub use auth::AuthScheme;
+pub use body_reader::BodyReader;
+pub use header_map::HeaderMap;
+pub use hop_by_hop::remove_hop_by_hop_headers;
+pub use multipart::{Multipart, MultipartLimits, Part};
+pub use options::ParseOptions;
 pub use method::Method;
 pub use version::HttpVersion;
 pub use error::Error;
 
-// Re-export the parse_request function
-pub use request::parse_request;
+// Re-export the parse functions
+pub use response::parse_response;
+pub use request::{
+    parse_request, parse_request_partial, parse_request_partial_with_options, parse_request_ref,
+    parse_request_ref_with_options, parse_request_with_options,
+};
+
+// Helpers shared with the server
+pub(crate) use chunked::ChunkedDecoder;
+pub(crate) use body_reader::StreamedBody;
+pub(crate) use request::{check_body_length, decode_chunked, find_header_end, find_simple_request_end};
+pub(crate) use response::parse_response_head;
+#[cfg(feature = "compression")]
+pub(crate) use media_type::media_type;
+pub(crate) use method::is_token;
+#[cfg(feature = "websocket")]
+pub(crate) use base64::{decode as base64_decode, encode as base64_encode};
+#[cfg(feature = "compression")]
+pub(crate) use negotiation::{encoding_quality, parse_quality_list};
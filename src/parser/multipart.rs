@@ -0,0 +1,218 @@
+//! Parsing of `multipart/form-data` bodies, as sent by HTML forms with file uploads.
+
+use crate::parser::error::Error;
+use crate::parser::media_type::media_type;
+
+/// Size limits applied while parsing a multipart body.
+///
+/// The defaults impose no limits beyond the size of the body itself, which the server
+/// already caps with `ServerConfig::max_body_size`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MultipartLimits {
+    /// The maximum size of a single part's data in bytes
+    pub max_part_size: usize,
+    /// The maximum combined size of all parts' data in bytes
+    pub max_total_size: usize,
+}
+
+impl Default for MultipartLimits {
+    fn default() -> Self {
+        Self {
+            max_part_size: usize::MAX,
+            max_total_size: usize::MAX,
+        }
+    }
+}
+
+impl MultipartLimits {
+    /// Set the maximum size of a single part's data.
+    pub fn with_max_part_size(mut self, max_part_size: usize) -> Self {
+        self.max_part_size = max_part_size;
+        self
+    }
+
+    /// Set the maximum combined size of all parts' data.
+    pub fn with_max_total_size(mut self, max_total_size: usize) -> Self {
+        self.max_total_size = max_total_size;
+        self
+    }
+}
+
+/// A single part of a multipart body: a form field or an uploaded file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Part {
+    /// The headers of the part, in the order received
+    pub headers: Vec<(String, String)>,
+    /// The form field name from the Content-Disposition header
+    pub name: String,
+    /// The file name from the Content-Disposition header, for file uploads
+    pub filename: Option<String>,
+    /// The part data
+    pub data: Vec<u8>,
+}
+
+impl Part {
+    /// Get the value of a part header.
+    ///
+    /// The lookup is case-insensitive.
+    pub fn get_header(&self, name: &str) -> Option<&String> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v)
+    }
+
+    /// Get the content type of the part, if it has one.
+    pub fn content_type(&self) -> Option<&String> {
+        self.get_header("Content-Type")
+    }
+
+    /// Check if the part is an uploaded file rather than a plain field.
+    pub fn is_file(&self) -> bool {
+        self.filename.is_some()
+    }
+
+    /// Get the part data as text, if it is valid UTF-8.
+    pub fn text(&self) -> Option<&str> {
+        std::str::from_utf8(&self.data).ok()
+    }
+}
+
+/// The parts of a `multipart/form-data` body, in the order received.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Multipart {
+    parts: Vec<Part>,
+}
+
+impl Multipart {
+    /// Get every part.
+    pub fn parts(&self) -> &[Part] {
+        &self.parts
+    }
+
+    /// Get the first part with the given field name.
+    pub fn get(&self, name: &str) -> Option<&Part> {
+        self.parts.iter().find(|part| part.name == name)
+    }
+
+    /// Parse a multipart body delimited by the given boundary.
+    pub fn parse(body: &[u8], boundary: &str, limits: MultipartLimits) -> Result<Multipart, Error> {
+        let delimiter = format!("--{boundary}").into_bytes();
+        let invalid = |reason: &str| Error::InvalidMultipart(reason.to_string());
+
+        // Skip the preamble before the first boundary
+        let mut pos = find(body, &delimiter, 0).ok_or_else(|| invalid("missing boundary"))? + delimiter.len();
+        let mut parts = Vec::new();
+        let mut total_size = 0usize;
+
+        loop {
+            // The final boundary is followed by `--`
+            if body[pos..].starts_with(b"--") {
+                return Ok(Multipart { parts });
+            }
+            pos = skip_line_end(body, pos).ok_or_else(|| invalid("boundary not followed by a line break"))?;
+
+            // Read the part headers up to the blank line
+            let mut headers = Vec::new();
+            loop {
+                let line_end = find(body, b"\n", pos).ok_or_else(|| invalid("unterminated part headers"))?;
+                let line = &body[pos..line_end];
+                let line = line.strip_suffix(b"\r").unwrap_or(line);
+                pos = line_end + 1;
+                if line.is_empty() {
+                    break;
+                }
+
+                let line = std::str::from_utf8(line).map_err(|_| invalid("part header is not valid UTF-8"))?;
+                let (name, value) = line.split_once(':').ok_or_else(|| invalid("malformed part header"))?;
+                headers.push((name.trim().to_string(), value.trim().to_string()));
+            }
+
+            // The data runs until the line break before the next boundary
+            let mut next_delimiter = b"\r\n".to_vec();
+            next_delimiter.extend_from_slice(&delimiter);
+            let data_end = find(body, &next_delimiter, pos).ok_or_else(|| invalid("missing closing boundary"))?;
+            let data = &body[pos..data_end];
+            pos = data_end + next_delimiter.len();
+
+            if data.len() > limits.max_part_size {
+                return Err(Error::MultipartTooLarge(limits.max_part_size));
+            }
+            total_size += data.len();
+            if total_size > limits.max_total_size {
+                return Err(Error::MultipartTooLarge(limits.max_total_size));
+            }
+
+            let disposition = headers
+                .iter()
+                .find(|(k, _)| k.eq_ignore_ascii_case("Content-Disposition"))
+                .map(|(_, v)| v.as_str())
+                .ok_or_else(|| invalid("part without a Content-Disposition header"))?;
+            let name = disposition_param(disposition, "name").ok_or_else(|| invalid("part without a name"))?;
+            let filename = disposition_param(disposition, "filename");
+
+            parts.push(Part {
+                headers,
+                name,
+                filename,
+                data: data.to_vec(),
+            });
+        }
+    }
+}
+
+impl IntoIterator for Multipart {
+    type Item = Part;
+    type IntoIter = std::vec::IntoIter<Part>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.parts.into_iter()
+    }
+}
+
+/// Get the boundary parameter of a `multipart/form-data` content type.
+pub(crate) fn boundary(content_type: &str) -> Option<String> {
+    if media_type(content_type) != "multipart/form-data" {
+        return None;
+    }
+    content_type.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_once('=')?;
+        name.trim().eq_ignore_ascii_case("boundary").then(|| unquote(value.trim()).to_string())
+    })
+}
+
+/// Get a parameter of a Content-Disposition header value, such as `name` or `filename`.
+fn disposition_param(disposition: &str, param: &str) -> Option<String> {
+    disposition.split(';').skip(1).find_map(|p| {
+        let (name, value) = p.split_once('=')?;
+        name.trim().eq_ignore_ascii_case(param).then(|| unquote(value.trim()).to_string())
+    })
+}
+
+fn unquote(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .unwrap_or(value)
+}
+
+/// Skip past the line break at `pos`, allowing trailing whitespace before it.
+fn skip_line_end(body: &[u8], mut pos: usize) -> Option<usize> {
+    while matches!(body.get(pos), Some(b' ' | b'\t')) {
+        pos += 1;
+    }
+    match body.get(pos..pos + 2) {
+        Some(b"\r\n") => Some(pos + 2),
+        _ if body.get(pos) == Some(&b'\n') => Some(pos + 1),
+        _ => None,
+    }
+}
+
+/// Find the first occurrence of `needle` in `haystack` at or after `from`.
+fn find(haystack: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    haystack
+        .get(from..)?
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .map(|i| i + from)
+}
@@ -0,0 +1,68 @@
+//! Content negotiation with the Accept and Accept-Encoding headers.
+
+/// Split a header value listing weighted items, such as `text/html, application/json;q=0.8`,
+/// into each item with its quality.
+///
+/// Items without a `q` parameter have a quality of 1, and items with a malformed or out of
+/// range `q` are left out. Parameters other than `q` are dropped from the items.
+pub(crate) fn parse_quality_list<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<(&'a str, f32)> {
+    values
+        .into_iter()
+        .flat_map(|value| value.split(','))
+        .filter_map(|item| {
+            let mut params = item.split(';');
+            let name = params.next().unwrap_or("").trim();
+            if name.is_empty() {
+                return None;
+            }
+            let quality = match params.find_map(|param| {
+                let (key, value) = param.split_once('=')?;
+                key.trim().eq_ignore_ascii_case("q").then(|| value.trim())
+            }) {
+                Some(q) => q.parse::<f32>().ok().filter(|q| (0.0..=1.0).contains(q))?,
+                None => 1.0,
+            };
+            Some((name, quality))
+        })
+        .collect()
+}
+
+/// Get the quality a list of media ranges gives a media type.
+///
+/// The most specific matching range decides: `text/html` over `text/*` over `*/*`. Any
+/// parameters of the media type are ignored, and a type no range matches has a quality of 0.
+pub(crate) fn media_type_quality(ranges: &[(&str, f32)], media_type: &str) -> f32 {
+    let media_type = media_type.split(';').next().unwrap_or("").trim();
+    let (main_type, _) = media_type.split_once('/').unwrap_or((media_type, ""));
+
+    ranges
+        .iter()
+        .filter_map(|&(range, quality)| {
+            let specificity = if range == "*/*" {
+                0
+            } else if range.strip_suffix("/*").is_some_and(|t| t.eq_ignore_ascii_case(main_type)) {
+                1
+            } else if range.eq_ignore_ascii_case(media_type) {
+                2
+            } else {
+                return None;
+            };
+            Some((specificity, quality))
+        })
+        .max_by_key(|&(specificity, _)| specificity)
+        .map_or(0.0, |(_, quality)| quality)
+}
+
+/// Get the quality a list of content codings gives a coding.
+///
+/// An entry for the coding itself takes precedence over `*`. The `identity` coding is
+/// acceptable unless it is listed with a quality of 0, or `*;q=0` is listed without it.
+#[cfg(feature = "compression")]
+pub(crate) fn encoding_quality(codings: &[(&str, f32)], coding: &str) -> f32 {
+    let quality = |name: &str| codings.iter().rev().find(|(c, _)| c.eq_ignore_ascii_case(name)).map(|&(_, q)| q);
+    match (quality(coding), quality("*")) {
+        (Some(q), _) | (None, Some(q)) => q,
+        (None, None) if coding.eq_ignore_ascii_case("identity") => 1.0,
+        (None, None) => 0.0,
+    }
+}
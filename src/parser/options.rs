@@ -0,0 +1,63 @@
+//! Options that control how strictly requests are parsed.
+
+/// Options applied while parsing a request.
+///
+/// The defaults match [`parse_request`](crate::parser::parse_request).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// The maximum number of header lines, including any chunked trailers
+    pub max_headers: usize,
+    /// The maximum size in bytes of the request line and header section that a
+    /// [`RequestParser`] buffers before rejecting the request as HeadersTooLarge
+    ///
+    /// [`RequestParser`]: crate::parser::RequestParser
+    pub max_head_size: usize,
+    /// Whether every line of the header section and of the chunked body framing must end with
+    /// `\r\n`, rejecting bare `\r` and `\n`, as RFC 9112 requires. Lenient parsing also
+    /// accepts bare `\n`, which helps with hand-written requests but lets a proxy and this
+    /// server disagree about where a request ends.
+    pub strict_line_endings: bool,
+    /// Whether the path is normalized as it is parsed, as [`HttpRequest::normalized_path`]
+    /// does, so requests with a `..` segment climbing above the root are rejected as
+    /// InvalidPath. The host named by a CONNECT request is left as it is.
+    ///
+    /// [`HttpRequest::normalized_path`]: crate::parser::HttpRequest::normalized_path
+    pub normalize_path: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            max_headers: 100,
+            max_head_size: 64 * 1024,
+            strict_line_endings: false,
+            normalize_path: false,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Set the maximum number of header lines.
+    pub fn with_max_headers(mut self, max_headers: usize) -> Self {
+        self.max_headers = max_headers;
+        self
+    }
+
+    /// Set the maximum size of the request line and header section.
+    pub fn with_max_head_size(mut self, max_head_size: usize) -> Self {
+        self.max_head_size = max_head_size;
+        self
+    }
+
+    /// Set whether lines must end with `\r\n`.
+    pub fn with_strict_line_endings(mut self, strict_line_endings: bool) -> Self {
+        self.strict_line_endings = strict_line_endings;
+        self
+    }
+
+    /// Set whether paths are normalized as they are parsed.
+    pub fn with_normalize_path(mut self, normalize_path: bool) -> Self {
+        self.normalize_path = normalize_path;
+        self
+    }
+}
@@ -0,0 +1,84 @@
+//! Normalization of request paths.
+
+use crate::parser::error::Error;
+
+/// Normalize a request path, keeping its query string.
+///
+/// Empty segments from repeated slashes and `.` segments are removed, and `..` segments
+/// remove the segment before them. Dot segments are recognized when percent-encoded too
+/// (`%2e%2E`), while other percent-encoded bytes, such as an encoded `/`, are left as they
+/// are. The asterisk-form path `*` is returned unchanged.
+///
+/// # Returns
+///
+/// The normalized path, or an InvalidPath error if a `..` segment would climb above the root,
+/// the path doesn't start with `/`, or it holds a malformed percent-encoding
+pub(crate) fn normalize_path(path: &str) -> Result<String, Error> {
+    if path == "*" {
+        return Ok(path.to_string());
+    }
+    let (path, query) = match path.split_once('?') {
+        Some((path, query)) => (path, Some(query)),
+        None => (path, None),
+    };
+    let Some(path) = path.strip_prefix('/') else {
+        return Err(Error::InvalidPath);
+    };
+
+    let mut segments: Vec<&str> = Vec::new();
+    // A path ending in a directory, such as `/a/` or `/a/..`, keeps its trailing slash
+    let mut trailing_slash = false;
+    for segment in path.split('/') {
+        trailing_slash = true;
+        match decoded_dots(segment)? {
+            Some(0 | 1) => {}
+            Some(2) => {
+                if segments.pop().is_none() {
+                    return Err(Error::InvalidPath);
+                }
+            }
+            _ => {
+                segments.push(segment);
+                trailing_slash = false;
+            }
+        }
+    }
+
+    let mut normalized = format!("/{}", segments.join("/"));
+    if trailing_slash && !segments.is_empty() {
+        normalized.push('/');
+    }
+    if let Some(query) = query {
+        normalized.push('?');
+        normalized.push_str(query);
+    }
+    Ok(normalized)
+}
+
+/// Count the dots of a segment made only of dots, decoding `%2e`.
+///
+/// # Returns
+///
+/// The number of dots if the segment is empty or only dots, None for any other segment, or
+/// an InvalidPath error if it holds a malformed percent-encoding
+fn decoded_dots(segment: &str) -> Result<Option<usize>, Error> {
+    let bytes = segment.as_bytes();
+    let mut dots = 0;
+    let mut only_dots = true;
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = bytes.get(i + 1..i + 3).ok_or(Error::InvalidPath)?;
+            if !hex.iter().all(u8::is_ascii_hexdigit) {
+                return Err(Error::InvalidPath);
+            }
+            only_dots &= hex.eq_ignore_ascii_case(b"2e");
+            i += 3;
+        } else {
+            only_dots &= bytes[i] == b'.';
+            i += 1;
+        }
+        dots += 1;
+    }
+    Ok(only_dots.then_some(dots))
+}
@@ -1,15 +1,28 @@
 //! HTTP request parsing and representation.
 
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::net::SocketAddr;
 use std::str::FromStr;
 use serde::de::DeserializeOwned;
 
+use crate::parser::auth::AuthScheme;
+use crate::parser::body_reader::{BodyReader, StreamedBody};
 use crate::parser::error::Error;
+use crate::parser::header_map::HeaderMap;
+use crate::parser::hop_by_hop::remove_hop_by_hop_headers;
+use crate::parser::multipart::{self, Multipart, MultipartLimits};
+use crate::parser::negotiation::{media_type_quality, parse_quality_list};
 use crate::parser::method::Method;
+use crate::parser::builder::HttpRequestBuilder;
+use crate::parser::media_type::{is_json_media_type, media_type};
+use crate::parser::options::ParseOptions;
+use crate::parser::path::normalize_path;
+use crate::parser::request_ref::HttpRequestRef;
 use crate::parser::version::HttpVersion;
 
 /// Represents an HTTP request.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct HttpRequest {
     /// The HTTP method (GET, POST, etc.)
     pub method: Method,
@@ -17,12 +30,27 @@ pub struct HttpRequest {
     pub path: String,
     /// The HTTP version
     pub version: HttpVersion,
-    /// The HTTP headers
-    pub headers: HashMap<String, String>,
+    /// The HTTP headers in the order received, with names spelled as received and repeated
+    /// headers kept once per value
+    pub headers: HeaderMap,
     /// The request body
     pub body: Vec<u8>,
-    /// Query parameters parsed from the path
+    /// Query parameters parsed from the path; when a parameter is repeated, the first value is kept
     pub query_params: HashMap<String, String>,
+    /// Every query parameter in the order it appears in the path, including repeated parameters
+    pub all_query_params: Vec<(String, String)>,
+    /// Path parameters captured by the matched route pattern
+    pub path_params: HashMap<String, String>,
+    /// The address of the client that sent the request, if known; the server sets it for
+    /// every request it receives
+    pub peer_addr: Option<SocketAddr>,
+    /// The scheme of an absolute-form request target, such as `http` in
+    /// `GET http://example.com/path HTTP/1.1` as sent to proxies
+    pub scheme: Option<String>,
+    /// The authority of an absolute-form request target, such as `example.com`
+    pub authority: Option<String>,
+    /// The body left on the connection for the handler of a streaming route
+    pub(crate) streamed_body: Option<StreamedBody>,
 }
 
 impl HttpRequest {
@@ -38,9 +66,9 @@ impl HttpRequest {
     /// # Returns
     ///
     /// A new HTTP request with an empty body
-    pub fn new(method: Method, path: String, version: HttpVersion, headers: HashMap<String, String>) -> Self {
+    pub fn new(method: Method, path: String, version: HttpVersion, headers: HeaderMap) -> Self {
         // Parse query parameters from the path
-        let query_params: HashMap<String, String> = path
+        let all_query_params: Vec<(String, String)> = path
             .split_once('?')
             .map(|(_, query)| query
                 .split('&')
@@ -55,6 +83,12 @@ impl HttpRequest {
                 .collect())
             .unwrap_or_default();
 
+        // Keep the first value of repeated query parameters in the map
+        let mut query_params = HashMap::new();
+        for (k, v) in &all_query_params {
+            query_params.entry(k.clone()).or_insert_with(|| v.clone());
+        }
+
         Self {
             method,
             path,
@@ -62,6 +96,12 @@ impl HttpRequest {
             headers,
             body: Vec::new(),
             query_params,
+            all_query_params,
+            path_params: HashMap::new(),
+            peer_addr: None,
+            scheme: None,
+            authority: None,
+            streamed_body: None,
         }
     }
 
@@ -78,12 +118,34 @@ impl HttpRequest {
     /// # Returns
     ///
     /// A new HTTP request with the specified body
-    pub fn with_body(method: Method, path: String, version: HttpVersion, headers: HashMap<String, String>, body: Vec<u8>) -> Self {
+    pub fn with_body(method: Method, path: String, version: HttpVersion, headers: HeaderMap, body: Vec<u8>) -> Self {
         let mut request = Self::new(method, path, version, headers);
         request.body = body;
         request
     }
 
+    /// Start building a request, for handler tests and other requests made in code.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use microhttp_rs::{HttpRequest, Method};
+    ///
+    /// let request = HttpRequest::builder()
+    ///     .method(Method::POST)
+    ///     .path("/users?notify=true")
+    ///     .header("Authorization", "Bearer token")
+    ///     .json_body(&serde_json::json!({"name": "Ada"}))
+    ///     .unwrap()
+    ///     .build();
+    /// assert_eq!(request.get_query_param("notify").unwrap(), "true");
+    /// assert_eq!(request.get_header("Content-Type").unwrap(), "application/json");
+    /// assert_eq!(request.get_header("Content-Length").unwrap(), "14");
+    /// ```
+    pub fn builder() -> HttpRequestBuilder {
+        HttpRequestBuilder::default()
+    }
+
     /// Get a header value.
     ///
     /// # Arguments
@@ -92,16 +154,41 @@ impl HttpRequest {
     ///
     /// # Returns
     ///
-    /// The header value, if it exists
+    /// The header value matched case-insensitively, if it exists; when a header is repeated,
+    /// the last value
     pub fn get_header(&self, name: &str) -> Option<&String> {
-        // Headers are case-insensitive, so we need to do a case-insensitive lookup
-        self.headers.iter().find_map(|(k, v)| {
-            if k.eq_ignore_ascii_case(name) {
-                Some(v)
-            } else {
-                None
-            }
-        })
+        self.headers.get(name)
+    }
+
+    /// Get all values of a header, in the order they were received.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The header name
+    ///
+    /// # Returns
+    ///
+    /// Every value of the header, matched case-insensitively; empty if it doesn't exist
+    pub fn get_all_headers(&self, name: &str) -> Vec<&String> {
+        self.headers.get_all(name)
+    }
+
+    /// Iterate over every header in the order received, with names spelled as received.
+    ///
+    /// Repeated headers are yielded once per occurrence, so the iterator reproduces the header
+    /// section exactly, as needed to forward a request or check a signature over its headers.
+    pub fn headers_in_order(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.headers.iter()
+    }
+
+    /// Add a header value, keeping any existing values.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The header name
+    /// * `value` - The header value
+    pub fn append_header(&mut self, name: impl Into<String>, value: impl Into<String>) {
+        self.headers.append(name, value);
     }
 
     /// Check if a header exists.
@@ -117,6 +204,39 @@ impl HttpRequest {
         self.get_header(name).is_some()
     }
 
+    /// Get the path with repeated slashes and `.` and `..` segments resolved, keeping the
+    /// query string.
+    ///
+    /// Percent-encoded dot segments (`%2e%2e`) are resolved too, while other percent-encoded
+    /// bytes are kept as they are, so an encoded `/` doesn't split a segment.
+    ///
+    /// # Returns
+    ///
+    /// The normalized path, or an InvalidPath error if a `..` segment would climb above the
+    /// root or the path holds a malformed percent-encoding
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use microhttp_rs::parse_request;
+    ///
+    /// let request = parse_request(b"GET /static//css/../js/./app.js?v=2 HTTP/1.0\r\n\r\n").unwrap();
+    /// assert_eq!(request.normalized_path().unwrap(), "/static/js/app.js?v=2");
+    ///
+    /// let request = parse_request(b"GET /static/../../etc/passwd HTTP/1.0\r\n\r\n").unwrap();
+    /// assert!(request.normalized_path().is_err());
+    /// ```
+    pub fn normalized_path(&self) -> Result<String, Error> {
+        normalize_path(&self.path)
+    }
+
+    /// Remove the hop-by-hop headers, including any named in `Connection`.
+    ///
+    /// See [`remove_hop_by_hop_headers`](crate::parser::remove_hop_by_hop_headers).
+    pub fn remove_hop_by_hop_headers(&mut self) {
+        remove_hop_by_hop_headers(self.headers.entries_mut());
+    }
+
     /// Parse the request body as JSON.
     ///
     /// # Returns
@@ -133,19 +253,292 @@ impl HttpRequest {
         Ok(json)
     }
 
+    /// Take a reader over the request body.
+    ///
+    /// For routes added with [`HttpServer::add_streaming_route`], the reader receives the body
+    /// from the connection as it is read, and only the first call gets it; later calls, and
+    /// calls on clones of the request, get an empty reader. For other requests the reader
+    /// serves [`body`](Self::body), which is left empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use microhttp_rs::{HttpRequest, Method};
+    /// use tokio::io::AsyncReadExt;
+    ///
+    /// # async fn example() {
+    /// let mut request = HttpRequest::builder().method(Method::POST).body("upload").build();
+    /// let mut body = String::new();
+    /// request.body_reader().read_to_string(&mut body).await.unwrap();
+    /// assert_eq!(body, "upload");
+    /// # }
+    /// ```
+    ///
+    /// [`HttpServer::add_streaming_route`]: crate::server::HttpServer::add_streaming_route
+    pub fn body_reader(&mut self) -> BodyReader {
+        match self.streamed_body.as_ref().and_then(StreamedBody::take) {
+            Some(reader) => reader,
+            None => BodyReader::buffered(std::mem::take(&mut self.body)),
+        }
+    }
+
+    /// Deserialize the query string into a value.
+    ///
+    /// The query string is decoded as `application/x-www-form-urlencoded`, so `?page=2&limit=10`
+    /// maps to a struct with `page` and `limit` fields. Fields that may be missing should be
+    /// `Option`s.
+    ///
+    /// # Returns
+    ///
+    /// The deserialized value, or an error if the query string doesn't match the type
+    pub fn query<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        let query = self.path.split_once('?').map_or("", |(_, query)| query);
+        serde_urlencoded::from_str(query).map_err(Error::QueryError)
+    }
+
+    /// Get the media type of the Content-Type header, lowercased and without parameters.
+    ///
+    /// # Returns
+    ///
+    /// The media type, such as `application/json` for `Application/JSON; charset=utf-8`, or
+    /// None if the header is absent
+    pub fn media_type(&self) -> Option<String> {
+        self.get_header("Content-Type").map(|content_type| media_type(content_type))
+    }
+
     /// Check if the request has a JSON body.
     ///
     /// # Returns
     ///
-    /// true if the Content-Type header is application/json, false otherwise
+    /// true if the media type is application/json or has the `+json` suffix, such as
+    /// application/problem+json, false otherwise
     pub fn is_json(&self) -> bool {
-        if let Some(content_type) = self.get_header("Content-Type") {
-            content_type.starts_with("application/json")
+        self.media_type().is_some_and(|media_type| is_json_media_type(&media_type))
+    }
+
+    /// Parse the request body as an `application/x-www-form-urlencoded` form.
+    ///
+    /// Names and values are percent-decoded, with `+` decoded as a space.
+    ///
+    /// # Returns
+    ///
+    /// The deserialized form, or an error if the body is not form-encoded or doesn't match
+    /// the type
+    pub fn form<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        // Check if the Content-Type header is application/x-www-form-urlencoded
+        if !self.is_form() {
+            return Err(Error::MissingHeader("Content-Type: application/x-www-form-urlencoded".to_string()));
+        }
+
+        serde_urlencoded::from_bytes(&self.body).map_err(Error::FormError)
+    }
+
+    /// Parse the request body as an `application/x-www-form-urlencoded` form of strings.
+    ///
+    /// # Returns
+    ///
+    /// A map from field names to values, where the last value of a repeated field is kept,
+    /// or an error if the body is not form-encoded
+    pub fn form_params(&self) -> Result<HashMap<String, String>, Error> {
+        self.form()
+    }
+
+    /// Check if the request has a form-encoded body.
+    ///
+    /// # Returns
+    ///
+    /// true if the Content-Type header is application/x-www-form-urlencoded, false otherwise
+    pub fn is_form(&self) -> bool {
+        self.media_type().is_some_and(|media_type| media_type == "application/x-www-form-urlencoded")
+    }
+
+    /// Parse the request body as `multipart/form-data`, without size limits on the parts.
+    ///
+    /// # Returns
+    ///
+    /// The parts of the body, or an error if the body is not multipart or is malformed
+    pub fn multipart(&self) -> Result<Multipart, Error> {
+        self.multipart_with_limits(MultipartLimits::default())
+    }
+
+    /// Parse the request body as `multipart/form-data`, rejecting parts larger than the limits.
+    ///
+    /// # Returns
+    ///
+    /// The parts of the body, or an error if the body is not multipart, is malformed or
+    /// exceeds the limits
+    pub fn multipart_with_limits(&self, limits: MultipartLimits) -> Result<Multipart, Error> {
+        let content_type = self
+            .get_header("Content-Type")
+            .ok_or_else(|| Error::MissingHeader("Content-Type: multipart/form-data".to_string()))?;
+        let boundary = multipart::boundary(content_type)
+            .ok_or_else(|| Error::InvalidMultipart(format!("no boundary in content type {content_type}")))?;
+        Multipart::parse(&self.body, &boundary, limits)
+    }
+
+    /// Get the cookies sent in the Cookie header.
+    ///
+    /// # Returns
+    ///
+    /// A map from cookie names to values, empty if the header is absent
+    pub fn cookies(&self) -> HashMap<String, String> {
+        self.get_header("Cookie")
+            .map(|header| {
+                header
+                    .split(';')
+                    .filter_map(|pair| pair.split_once('='))
+                    .map(|(name, value)| {
+                        let value = value.trim();
+                        let value = value
+                            .strip_prefix('"')
+                            .and_then(|v| v.strip_suffix('"'))
+                            .unwrap_or(value);
+                        (name.trim().to_string(), value.to_string())
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// Get the host name and port from the Host header.
+    ///
+    /// IPv6 literals such as `[::1]:8080` are returned without the brackets.
+    ///
+    /// # Returns
+    ///
+    /// The host name and the port, if one is given, or None if the header is absent, empty
+    /// or malformed
+    pub fn host(&self) -> Option<(&str, Option<u16>)> {
+        let host = self.get_header("Host")?.trim();
+
+        let (name, port) = if let Some(rest) = host.strip_prefix('[') {
+            // IPv6 literal, optionally followed by a port
+            let (name, rest) = rest.split_once(']')?;
+            match rest {
+                "" => (name, None),
+                _ => (name, Some(rest.strip_prefix(':')?)),
+            }
         } else {
-            false
+            match host.split_once(':') {
+                Some((name, port)) => (name, Some(port)),
+                None => (host, None),
+            }
+        };
+
+        if name.is_empty() {
+            return None;
+        }
+        match port {
+            Some(port) => Some((name, Some(port.parse().ok()?))),
+            None => Some((name, None)),
         }
     }
 
+    /// Get the credentials sent in the Authorization header.
+    ///
+    /// # Returns
+    ///
+    /// The credentials, or None if the header is absent or malformed
+    pub fn authorization(&self) -> Option<AuthScheme> {
+        self.get_header("Authorization").and_then(|header| AuthScheme::parse(header))
+    }
+
+    /// Check if the client accepts a content type, according to the Accept header.
+    ///
+    /// The most specific media range matching the type decides, so `text/html;q=0` rejects
+    /// HTML even alongside `*/*`. Every type is accepted when the header is absent.
+    ///
+    /// # Arguments
+    ///
+    /// * `content_type` - The content type, such as `application/json`
+    ///
+    /// # Returns
+    ///
+    /// true if the type matches a media range with a non-zero quality, false otherwise
+    pub fn accepts(&self, content_type: &str) -> bool {
+        if !self.has_header("Accept") {
+            return true;
+        }
+        let ranges = parse_quality_list(self.get_all_headers("Accept").into_iter().map(String::as_str));
+        media_type_quality(&ranges, content_type) > 0.0
+    }
+
+    /// Pick the content type the client prefers among those offered, according to the
+    /// Accept header and its quality factors.
+    ///
+    /// # Arguments
+    ///
+    /// * `offered` - The content types the handler can produce, in the handler's order of
+    ///   preference
+    ///
+    /// # Returns
+    ///
+    /// The offered type with the highest quality, the earliest one on a tie, the first
+    /// offered type if the header is absent, or None if the client accepts none of them
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use microhttp_rs::parse_request;
+    ///
+    /// let request = parse_request(b"GET / HTTP/1.1\r\nHost: example.com\r\nAccept: text/html;q=0.9, application/json\r\n\r\n").unwrap();
+    /// assert_eq!(request.preferred(&["text/html", "application/json"]), Some("application/json"));
+    /// ```
+    pub fn preferred<'a>(&self, offered: &[&'a str]) -> Option<&'a str> {
+        if !self.has_header("Accept") {
+            return offered.first().copied();
+        }
+        let ranges = parse_quality_list(self.get_all_headers("Accept").into_iter().map(String::as_str));
+        let mut best: Option<(&'a str, f32)> = None;
+        for &content_type in offered {
+            let quality = media_type_quality(&ranges, content_type);
+            if quality > 0.0 && best.is_none_or(|(_, best_quality)| quality > best_quality) {
+                best = Some((content_type, quality));
+            }
+        }
+        best.map(|(content_type, _)| content_type)
+    }
+
+    /// Get the content codings listed in the Accept-Encoding header, with their quality
+    /// factors, most preferred first.
+    ///
+    /// Codings are lowercased and keep the header's order among equal qualities. A quality of
+    /// 0 means the coding, or with `*` every unlisted coding, is not acceptable; such entries
+    /// are kept, last. The `identity` coding is acceptable even when unlisted, unless `*;q=0`
+    /// excludes it.
+    ///
+    /// # Returns
+    ///
+    /// The codings and their qualities, empty if the header is absent
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use microhttp_rs::parse_request;
+    ///
+    /// let request = parse_request(b"GET / HTTP/1.1\r\nHost: example.com\r\nAccept-Encoding: deflate;q=0.5, gzip\r\n\r\n").unwrap();
+    /// assert_eq!(request.accepted_encodings(), vec![("gzip".to_string(), 1.0), ("deflate".to_string(), 0.5)]);
+    /// ```
+    pub fn accepted_encodings(&self) -> Vec<(String, f32)> {
+        let mut codings: Vec<(String, f32)> =
+            parse_quality_list(self.get_all_headers("Accept-Encoding").into_iter().map(String::as_str))
+                .into_iter()
+                .map(|(coding, quality)| (coding.to_ascii_lowercase(), quality))
+                .collect();
+        codings.sort_by(|(_, a), (_, b)| b.total_cmp(a));
+        codings
+    }
+
+    /// Check if the request body uses chunked transfer encoding.
+    ///
+    /// # Returns
+    ///
+    /// true if the Transfer-Encoding headers, joined into one list, end with chunked, false
+    /// otherwise
+    pub fn is_chunked(&self) -> bool {
+        is_chunked_encoding(self.get_all_headers("Transfer-Encoding").into_iter().map(String::as_str))
+    }
+
     /// Get a query parameter value.
     ///
     /// # Arguments
@@ -154,11 +547,28 @@ impl HttpRequest {
     ///
     /// # Returns
     ///
-    /// The query parameter value, if it exists
+    /// The first value of the query parameter, if it exists
     pub fn get_query_param(&self, name: &str) -> Option<&String> {
         self.query_params.get(name)
     }
 
+    /// Get all values of a query parameter, in the order they appear in the path.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The query parameter name
+    ///
+    /// # Returns
+    ///
+    /// Every value of the query parameter; empty if it doesn't exist
+    pub fn get_query_params(&self, name: &str) -> Vec<&String> {
+        self.all_query_params
+            .iter()
+            .filter(|(k, _)| k == name)
+            .map(|(_, v)| v)
+            .collect()
+    }
+
     /// Check if a query parameter exists.
     ///
     /// # Arguments
@@ -171,6 +581,59 @@ impl HttpRequest {
     pub fn has_query_param(&self, name: &str) -> bool {
         self.query_params.contains_key(name)
     }
+
+    /// Get a path parameter value.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The path parameter name, without the leading `:`
+    ///
+    /// # Returns
+    ///
+    /// The path parameter value, if it was captured by the matched route
+    pub fn get_path_param(&self, name: &str) -> Option<&String> {
+        self.path_params.get(name)
+    }
+
+    /// Serialize the request back into its wire format: the request line, the headers in
+    /// `headers`, a blank line and the body.
+    ///
+    /// An absolute-form target is written back in absolute form. A chunked body, which is
+    /// decoded when parsed, is encoded again as a single chunk; trailers received with it are
+    /// among the headers, so they are written in the header section. An HTTP/0.9 request is
+    /// only its request line.
+    ///
+    /// # Returns
+    ///
+    /// The bytes of the request, which parse back into an equivalent request
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let target = match (&self.scheme, &self.authority) {
+            (Some(scheme), Some(authority)) => format!("{scheme}://{authority}{}", self.path),
+            _ => self.path.clone(),
+        };
+        if self.version == HttpVersion::Http09 {
+            return format!("{} {target}\r\n", self.method).into_bytes();
+        }
+
+        let mut bytes = format!("{} {target} {}\r\n", self.method, self.version).into_bytes();
+        for (name, value) in &self.headers {
+            bytes.extend_from_slice(format!("{name}: {value}\r\n").as_bytes());
+        }
+        bytes.extend_from_slice(b"\r\n");
+
+        if self.is_chunked() {
+            if !self.body.is_empty() {
+                bytes.extend_from_slice(format!("{:x}\r\n", self.body.len()).as_bytes());
+                bytes.extend_from_slice(&self.body);
+                bytes.extend_from_slice(b"\r\n");
+            }
+            bytes.extend_from_slice(b"0\r\n\r\n");
+        } else {
+            bytes.extend_from_slice(&self.body);
+        }
+
+        bytes
+    }
 }
 
 /// Parse an HTTP request from a byte slice.
@@ -182,9 +645,92 @@ impl HttpRequest {
 /// # Returns
 ///
 /// The parsed HTTP request, or an error if the request is invalid
+///
+/// The body is taken from the bytes following the blank line, capped at `Content-Length` bytes.
+/// If fewer bytes are present, the available bytes are used; without a `Content-Length`
+/// header the body is empty. A body sent with `Transfer-Encoding: chunked` is decoded instead,
+/// and any trailers after the final chunk are added to the headers.
+///
+/// Only the header section has to be valid UTF-8; the body is kept as raw bytes, so binary
+/// bodies come through unchanged.
 pub fn parse_request(input: &[u8]) -> Result<HttpRequest, Error> {
-    // Convert the input to a string
-    let input_str = match std::str::from_utf8(input) {
+    parse_request_with_options(input, ParseOptions::default())
+}
+
+/// Parse an HTTP request from a byte slice, with the given options.
+///
+/// The request is parsed like [`parse_request`], except that the limits and checks of
+/// `options` are applied instead of the default ones.
+pub fn parse_request_with_options(input: &[u8], options: ParseOptions) -> Result<HttpRequest, Error> {
+    parse_request_partial_with_options(input, options).map(|(request, _)| request)
+}
+
+/// Parse an HTTP request from the start of a byte slice, reporting where it ends.
+///
+/// The request is parsed like [`parse_request`]; any bytes after its body, such as a
+/// pipelined request, are left alone.
+///
+/// # Arguments
+///
+/// * `input` - A byte slice starting with the HTTP request to parse
+///
+/// # Returns
+///
+/// The parsed HTTP request and the number of bytes it took up, including the headers and
+/// the body, or an error if the request is invalid
+pub fn parse_request_partial(input: &[u8]) -> Result<(HttpRequest, usize), Error> {
+    parse_request_partial_with_options(input, ParseOptions::default())
+}
+
+/// Parse an HTTP request from the start of a byte slice, with the given options, reporting
+/// where it ends.
+///
+/// The request is parsed like [`parse_request_partial`], except that the limits and checks
+/// of `options` are applied instead of the default ones.
+pub fn parse_request_partial_with_options(input: &[u8], options: ParseOptions) -> Result<(HttpRequest, usize), Error> {
+    parse_request_ref_partial(input, options).map(|(request, consumed)| (request.to_owned(), consumed))
+}
+
+/// Parse an HTTP request from a byte slice into a request that borrows from it.
+///
+/// The request is parsed like [`parse_request`], but its path, headers and body are slices
+/// of `input` rather than copies.
+///
+/// # Examples
+///
+/// ```
+/// use microhttp_rs::parse_request_ref;
+///
+/// let input = b"GET /index.html HTTP/1.1\r\nHost: example.com\r\n\r\n";
+/// let request = parse_request_ref(input).unwrap();
+/// assert_eq!(request.path, "/index.html");
+/// assert_eq!(request.get_header("host"), Some("example.com"));
+/// ```
+pub fn parse_request_ref(input: &[u8]) -> Result<HttpRequestRef<'_>, Error> {
+    parse_request_ref_with_options(input, ParseOptions::default())
+}
+
+/// Parse an HTTP request from a byte slice into a request that borrows from it, with the
+/// given options.
+pub fn parse_request_ref_with_options(input: &[u8], options: ParseOptions) -> Result<HttpRequestRef<'_>, Error> {
+    parse_request_ref_partial(input, options).map(|(request, _)| request)
+}
+
+/// Parse a request that borrows from the input, reporting where it ends; every other parse
+/// function builds on this one.
+fn parse_request_ref_partial(input: &[u8], options: ParseOptions) -> Result<(HttpRequestRef<'_>, usize), Error> {
+    // Split the input into the header section and the body
+    let (head, body) = match find_header_end(input) {
+        Some(end) => (&input[..end], &input[end..]),
+        None => (input, &input[input.len()..]),
+    };
+
+    if options.strict_line_endings {
+        check_line_endings(head)?;
+    }
+
+    // Convert the header section to a string; the body stays as bytes
+    let input_str = match std::str::from_utf8(head) {
         Ok(s) => s,
         Err(_) => return Err(Error::MalformedRequestLine("Invalid UTF-8".to_string())),
     };
@@ -198,51 +744,362 @@ pub fn parse_request(input: &[u8]) -> Result<HttpRequest, Error> {
         None => return Err(Error::EmptyRequest),
     };
 
-    // Split the request line into method, path, and version
-    let parts: Vec<&str> = request_line.split_whitespace().collect();
-    if parts.len() != 3 {
-        return Err(Error::MalformedRequestLine(request_line.to_string()));
-    }
+    // Split the request line into method, path, and version; an HTTP/0.9 simple request
+    // has no version
+    let mut parts = request_line.split_whitespace();
+    let (method, target, version) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(method), Some(target), Some(version), None) => (method, target, Some(version)),
+        (Some(method), Some(target), None, None) if is_simple_request_line(request_line) => (method, target, None),
+        _ => return Err(Error::MalformedRequestLine(request_line.to_string())),
+    };
 
     // Parse the method
-    let method = Method::from_str(parts[0])?;
+    let method = Method::from_str(method)?;
 
-    // Parse the path
-    let path = parts[1].to_string();
+    // Parse the path, splitting the scheme and authority off an absolute-form target
+    let (scheme, authority, path) = match split_absolute_form(target)? {
+        Some((scheme, authority, path)) => (Some(scheme), Some(authority), path),
+        None => (None, None, Cow::Borrowed(target)),
+    };
     if path.is_empty() {
         return Err(Error::InvalidPath);
     }
+    let path = match path {
+        // A CONNECT target names a host in authority form, which has no path to normalize
+        path if options.normalize_path && method != Method::CONNECT => match normalize_path(&path)? {
+            normalized if normalized == path => path,
+            normalized => Cow::Owned(normalized),
+        },
+        path => path,
+    };
+    // The asterisk-form target refers to the whole server and only makes sense for OPTIONS
+    if path == "*" && method != Method::OPTIONS {
+        return Err(Error::InvalidPath);
+    }
 
     // Parse the version
-    let version = HttpVersion::from_str(parts[2])?;
+    let version = match version {
+        Some(version) => HttpVersion::from_str(version)?,
+        None => HttpVersion::Http09,
+    };
+
+    let headers = parse_header_lines(lines, options.max_headers)?;
+
+    let mut request = HttpRequestRef {
+        method,
+        path,
+        version,
+        headers,
+        body: Cow::Borrowed(&[]),
+        scheme,
+        authority,
+    };
 
-    // Parse the headers
-    let mut headers = HashMap::new();
+    // An absolute-form target names the host, which stands in for a missing Host header
+    if let Some(authority) = authority {
+        if request.get_header("Host").is_none() {
+            request.headers.push(("Host", authority));
+        }
+    }
+
+    // Check for required headers
+    if version == HttpVersion::Http11 && request.get_header("Host").is_none() {
+        return Err(Error::MissingHeader("Host".to_string()));
+    }
+
+    let content_length = check_body_length(request.headers.iter().copied())?;
+
+    let body_len = if request.is_chunked() {
+        // Decode the chunked body and merge any trailers into the headers
+        let chunked = decode_chunked(body, options.strict_line_endings)?;
+        if request.headers.len() + chunked.trailers.len() > options.max_headers {
+            return Err(Error::TooManyHeaders(options.max_headers));
+        }
+        request.headers.extend(chunked.trailers);
+        request.body = Cow::Owned(chunked.data);
+        chunked.consumed
+    } else {
+        // Read the body based on the Content-Length header, keeping whatever is available
+        let body_len = body.len().min(content_length.unwrap_or(0));
+        request.body = Cow::Borrowed(&body[..body_len]);
+        body_len
+    };
+
+    Ok((request, head.len() + body_len))
+}
+
+/// Parse the header lines of a request or response, keeping every occurrence in arrival
+/// order, up to the empty line that ends the header section.
+pub(crate) fn parse_header_lines<'a>(
+    lines: impl Iterator<Item = &'a str>,
+    max_headers: usize,
+) -> Result<Vec<(&'a str, &'a str)>, Error> {
+    let mut headers = Vec::with_capacity(16);
     for line in lines {
         // Empty line indicates the end of headers
         if line.is_empty() {
             break;
         }
 
-        // Split the line into name and value
-        let parts: Vec<&str> = line.splitn(2, ':').collect();
-        if parts.len() != 2 {
+        // Split the line into name and value, trimming whitespace from both
+        let Some((name, value)) = line.split_once(':') else {
             return Err(Error::InvalidHeaderFormat);
+        };
+
+        if headers.len() == max_headers {
+            return Err(Error::TooManyHeaders(max_headers));
         }
+        headers.push((name.trim(), value.trim()));
+    }
+    Ok(headers)
+}
 
-        // Trim whitespace from the name and value
-        let name = parts[0].trim().to_string();
-        let value = parts[1].trim().to_string();
+/// The scheme, authority and path with query of an absolute-form request target.
+type AbsoluteForm<'a> = (&'a str, &'a str, Cow<'a, str>);
 
-        // Add the header to the map
-        headers.insert(name, value);
+/// Split an absolute-form request target such as `http://example.com/path?q=1` into its
+/// scheme, authority and path with query.
+///
+/// # Returns
+///
+/// The parts of the target, None if the target is not in absolute form (such as `/path` or
+/// `*`), or an InvalidPath error if it has no authority
+fn split_absolute_form(target: &str) -> Result<Option<AbsoluteForm<'_>>, Error> {
+    let Some((scheme, rest)) = target.split_once("://") else {
+        return Ok(None);
+    };
+    let is_scheme = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c));
+    if !is_scheme {
+        return Ok(None);
     }
 
-    // Check for required headers
-    if version == HttpVersion::Http11 && !headers.iter().any(|(k, _)| k.eq_ignore_ascii_case("Host")) {
-        return Err(Error::MissingHeader("Host".to_string()));
+    let authority_end = rest.find(['/', '?']).unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(authority_end);
+    if authority.is_empty() {
+        return Err(Error::InvalidPath);
+    }
+
+    // The path is empty for targets such as `http://example.com` or `http://example.com?q=1`
+    let path = if path.starts_with('/') { Cow::Borrowed(path) } else { Cow::Owned(format!("/{path}")) };
+    Ok(Some((scheme, authority, path)))
+}
+
+/// Check whether a request line is an HTTP/0.9 simple request, which is `GET` followed by a
+/// path and nothing else.
+pub(crate) fn is_simple_request_line(line: &str) -> bool {
+    let mut parts = line.split_whitespace();
+    parts.next() == Some("GET") && parts.next().is_some_and(|path| path.starts_with('/')) && parts.next().is_none()
+}
+
+/// Find the end of an HTTP/0.9 simple request, which has no header section and ends with
+/// its request line.
+pub(crate) fn find_simple_request_end(buf: &[u8]) -> Option<usize> {
+    let line_end = buf.iter().position(|&b| b == b'\n')?;
+    let line = std::str::from_utf8(&buf[..line_end]).ok()?;
+    is_simple_request_line(line).then_some(line_end + 1)
+}
+
+/// Find the end of the header section in a buffer.
+///
+/// Returns the index of the first byte after the blank line that terminates the headers,
+/// accepting both `\r\n\r\n` and bare `\n\n` terminators.
+pub(crate) fn find_header_end(buf: &[u8]) -> Option<usize> {
+    // Jump from one line break to the next rather than looking at every byte pair
+    let mut pos = 0;
+    while let Some(offset) = buf[pos..].iter().position(|&b| b == b'\n') {
+        let i = pos + offset;
+        match buf.get(i + 1) {
+            Some(b'\n') => return Some(i + 2),
+            Some(b'\r') if buf.get(i + 2) == Some(&b'\n') => return Some(i + 3),
+            _ => pos = i + 1,
+        }
+    }
+    None
+}
+
+/// Check that the headers give a single body length, since a request with both
+/// `Content-Length` and `Transfer-Encoding`, with several different `Content-Length`
+/// values, or with a final transfer coding other than chunked may be framed differently by
+/// other servers on the way (RFC 9112 section 6.3).
+///
+/// Repeating the same `Content-Length` value is allowed, in separate headers or in a list.
+///
+/// # Returns
+///
+/// The length given by `Content-Length`, None if the body is chunked or has no length, or an
+/// error if the length is ambiguous or not a number
+pub(crate) fn check_body_length<'a>(headers: impl IntoIterator<Item = (&'a str, &'a str)>) -> Result<Option<usize>, Error> {
+    let mut content_length = None;
+    let mut transfer_codings = Vec::new();
+    for (name, value) in headers {
+        if name.eq_ignore_ascii_case("Content-Length") {
+            for length in value.split(',').map(str::trim) {
+                // Only digits, so a sign isn't taken as part of the length
+                if length.is_empty() || !length.bytes().all(|b| b.is_ascii_digit()) {
+                    return Err(Error::InvalidHeaderFormat);
+                }
+                let length = length.parse::<usize>().map_err(|_| Error::InvalidHeaderFormat)?;
+                if content_length.is_some_and(|first| first != length) {
+                    return Err(Error::AmbiguousBodyLength("conflicting Content-Length values".to_string()));
+                }
+                content_length = Some(length);
+            }
+        } else if name.eq_ignore_ascii_case("Transfer-Encoding") {
+            transfer_codings.push(value);
+        }
+    }
+
+    if transfer_codings.is_empty() {
+        return Ok(content_length);
+    }
+    if content_length.is_some() {
+        return Err(Error::AmbiguousBodyLength("both Content-Length and Transfer-Encoding are present".to_string()));
+    }
+    if !is_chunked_encoding(transfer_codings) {
+        return Err(Error::AmbiguousBodyLength("the final transfer coding is not chunked".to_string()));
+    }
+    Ok(None)
+}
+
+/// Check that every line ending is `\r\n`, with no bare `\r` or `\n`.
+fn check_line_endings(bytes: &[u8]) -> Result<(), Error> {
+    let mut bytes = bytes.iter().peekable();
+    while let Some(&b) = bytes.next() {
+        match b {
+            b'\r' if bytes.next_if_eq(&&b'\n').is_some() => {}
+            // A trailing `\r` may be followed by a `\n` that hasn't arrived yet
+            b'\r' if bytes.peek().is_none() => {}
+            b'\r' | b'\n' => return Err(Error::InvalidLineEnding),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Check if the Transfer-Encoding header values, taken together as one list, have chunked as
+/// their final coding.
+pub(crate) fn is_chunked_encoding<'a>(values: impl IntoIterator<Item = &'a str>) -> bool {
+    values
+        .into_iter()
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .filter(|coding| !coding.is_empty())
+        .last()
+        .is_some_and(|coding| coding.eq_ignore_ascii_case("chunked"))
+}
+
+/// A body decoded from the chunked transfer encoding.
+pub(crate) struct ChunkedBody<'a> {
+    /// The concatenated chunk data
+    pub data: Vec<u8>,
+    /// The trailer headers sent after the final chunk
+    pub trailers: Vec<(&'a str, &'a str)>,
+    /// Whether the terminating chunk and trailer section were seen
+    pub complete: bool,
+    /// The number of input bytes that belong to the body, including the trailer section
+    pub consumed: usize,
+}
+
+/// Decode a body sent with the chunked transfer encoding.
+///
+/// Decoding stops at the terminating zero-length chunk. If the input ends before that,
+/// the data decoded so far is returned with `complete` set to false.
+///
+/// # Arguments
+///
+/// * `input` - The bytes following the header section
+/// * `strict` - Whether the chunk size lines, chunk delimiters and trailer lines must end
+///   with `\r\n`
+///
+/// # Returns
+///
+/// The decoded body, or an error if a chunk size line or chunk delimiter is malformed
+pub(crate) fn decode_chunked(input: &[u8], strict: bool) -> Result<ChunkedBody<'_>, Error> {
+    let mut chunked = ChunkedBody {
+        data: Vec::new(),
+        trailers: Vec::new(),
+        complete: false,
+        consumed: input.len(),
+    };
+    let mut pos = 0;
+
+    loop {
+        // Read the chunk size line
+        let Some((line, next)) = next_line(input, pos, strict)? else {
+            return Ok(chunked);
+        };
+        let size = parse_chunk_size(line)?;
+        pos = next;
+
+        if size == 0 {
+            break;
+        }
+
+        // Read the chunk data
+        let available = &input[pos..];
+        if available.len() < size {
+            chunked.data.extend_from_slice(available);
+            return Ok(chunked);
+        }
+        chunked.data.extend_from_slice(&available[..size]);
+        pos += size;
+
+        // Each chunk must be followed by a line break
+        match &input[pos..] {
+            [b'\r', b'\n', ..] => pos += 2,
+            [b'\n', ..] if !strict => pos += 1,
+            [] | [b'\r'] => return Ok(chunked),
+            [b'\n', ..] => return Err(Error::InvalidLineEnding),
+            _ => return Err(Error::InvalidChunkEncoding("Missing line break after chunk data".to_string())),
+        }
+    }
+
+    // Read the trailer section, which ends with an empty line
+    while let Some((line, next)) = next_line(input, pos, strict)? {
+        pos = next;
+        if line.is_empty() {
+            chunked.complete = true;
+            chunked.consumed = pos;
+            break;
+        }
+
+        let line = std::str::from_utf8(line).map_err(|_| Error::InvalidHeaderFormat)?;
+        let (name, value) = line.split_once(':').ok_or(Error::InvalidHeaderFormat)?;
+        chunked.trailers.push((name.trim(), value.trim()));
     }
 
-    // Create the request
-    Ok(HttpRequest::new(method, path, version, headers))
+    Ok(chunked)
+}
+
+/// Parse a chunk size line, ignoring any chunk extensions after the size.
+///
+/// The size must be hexadecimal digits only; `from_str_radix` alone would also take a sign.
+pub(crate) fn parse_chunk_size(line: &[u8]) -> Result<usize, Error> {
+    let line = std::str::from_utf8(line).map_err(|_| Error::InvalidChunkEncoding("Invalid UTF-8".to_string()))?;
+    let size_str = line.split(';').next().unwrap_or_default().trim();
+    if size_str.is_empty() || !size_str.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(Error::InvalidChunkEncoding(line.to_string()));
+    }
+    usize::from_str_radix(size_str, 16).map_err(|_| Error::InvalidChunkEncoding(line.to_string()))
+}
+
+/// Get the next line starting at `pos`, without its line ending, and the position after it.
+///
+/// Returns None if the line isn't complete yet, or an error in strict mode if it doesn't end
+/// with `\r\n` or holds a bare `\r`.
+pub(crate) fn next_line(input: &[u8], pos: usize, strict: bool) -> Result<Option<(&[u8], usize)>, Error> {
+    let Some(len) = input[pos..].iter().position(|&b| b == b'\n') else {
+        return Ok(None);
+    };
+    let line = &input[pos..pos + len];
+    let line = match line.strip_suffix(b"\r") {
+        Some(line) => line,
+        None if strict => return Err(Error::InvalidLineEnding),
+        None => line,
+    };
+    if strict && line.contains(&b'\r') {
+        return Err(Error::InvalidLineEnding);
+    }
+    Ok(Some((line, pos + len + 1)))
 }
@@ -0,0 +1,148 @@
+//! Incremental parsing of requests that arrive in pieces.
+
+use crate::parser::chunked::ChunkedDecoder;
+use crate::parser::error::Error;
+use crate::parser::options::ParseOptions;
+use crate::parser::request::{HttpRequest, check_body_length, find_header_end, parse_request_with_options};
+
+/// The outcome of feeding bytes to a [`RequestParser`].
+// The request is returned once per call, so boxing it would only add an allocation
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum ParseStatus {
+    /// The request isn't complete yet; feed more bytes.
+    NeedMore,
+    /// A complete request, including its body.
+    Complete(HttpRequest),
+}
+
+/// A push-based parser that is fed bytes as they arrive and reports when a request is complete.
+///
+/// Bytes that follow a complete request are kept for the next one, so pipelined requests are
+/// framed correctly: after a [`ParseStatus::Complete`], call [`RequestParser::feed`] with an
+/// empty slice to parse any request that is already buffered. After an error the buffered
+/// bytes are in an unknown state and the parser should be discarded.
+///
+/// # Examples
+///
+/// ```
+/// use microhttp_rs::{ParseStatus, RequestParser};
+///
+/// let mut parser = RequestParser::new();
+/// assert!(matches!(parser.feed(b"POST /submit HTTP/1.1\r\nHost: example.com\r\n").unwrap(), ParseStatus::NeedMore));
+/// assert!(matches!(parser.feed(b"Content-Length: 5\r\n\r\nhel").unwrap(), ParseStatus::NeedMore));
+///
+/// match parser.feed(b"loGET / HTTP/1.1\r\n").unwrap() {
+///     ParseStatus::Complete(request) => assert_eq!(request.body, b"hello"),
+///     ParseStatus::NeedMore => unreachable!(),
+/// }
+/// // The start of the next request stays buffered
+/// assert_eq!(parser.buffered(), b"GET / HTTP/1.1\r\n");
+/// ```
+#[derive(Debug, Default)]
+pub struct RequestParser {
+    /// The bytes received but not yet consumed by a complete request
+    buf: Vec<u8>,
+    /// The request line and headers of the current request, once they are complete
+    head: Option<Head>,
+    /// The number of bytes the last complete request took up
+    consumed: usize,
+    /// The options every request is parsed with
+    options: ParseOptions,
+}
+
+/// The parsed head of a request whose body is still arriving.
+#[derive(Debug)]
+struct Head {
+    request: HttpRequest,
+    /// The offset of the body in the buffer
+    header_end: usize,
+    /// The decoder of a chunked body, with the number of body bytes it has decoded
+    chunked: Option<(ChunkedDecoder, usize)>,
+}
+
+impl RequestParser {
+    /// Create a parser with an empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a parser with an empty buffer that parses requests with the given options.
+    pub fn with_options(options: ParseOptions) -> Self {
+        Self {
+            options,
+            ..Self::default()
+        }
+    }
+
+    /// Add bytes to the buffer and try to complete the current request.
+    ///
+    /// # Returns
+    ///
+    /// [`ParseStatus::Complete`] with the request once its headers and body have arrived,
+    /// [`ParseStatus::NeedMore`] otherwise, or an error if the request is invalid
+    pub fn feed(&mut self, bytes: &[u8]) -> Result<ParseStatus, Error> {
+        self.buf.extend_from_slice(bytes);
+
+        // Parse the request line and headers once the header section is complete, refusing
+        // to buffer a header section larger than the limit
+        if self.head.is_none() {
+            let header_end = find_header_end(&self.buf);
+            if header_end.unwrap_or(self.buf.len()) > self.options.max_head_size {
+                return Err(Error::HeadersTooLarge(self.options.max_head_size));
+            }
+            let Some(header_end) = header_end else {
+                return Ok(ParseStatus::NeedMore);
+            };
+            let request = parse_request_with_options(&self.buf[..header_end], self.options)?;
+            let chunked = request.is_chunked().then(|| (ChunkedDecoder::new(self.options.strict_line_endings), 0));
+            self.head = Some(Head { request, header_end, chunked });
+        }
+        let Some(Head { request, header_end, chunked }) = &mut self.head else {
+            return Ok(ParseStatus::NeedMore);
+        };
+        let header_end = *header_end;
+
+        // Wait for the whole body, either as chunks or based on the Content-Length header
+        let request_end = if let Some((decoder, decoded)) = chunked {
+            // Only the bytes that arrived since the last call are decoded
+            *decoded += decoder.decode(&self.buf[header_end + *decoded..], &mut request.body)?;
+            if request.headers.len() + decoder.trailers().len() > self.options.max_headers {
+                return Err(Error::TooManyHeaders(self.options.max_headers));
+            }
+            if !decoder.is_done() {
+                return Ok(ParseStatus::NeedMore);
+            }
+            for (name, value) in decoder.take_trailers() {
+                request.append_header(name, value);
+            }
+            header_end + *decoded
+        } else {
+            let content_length = check_body_length(request.headers_in_order())?.unwrap_or(0);
+            // A length too large to address can never be buffered
+            let body_end = header_end.checked_add(content_length).ok_or(Error::InvalidHeaderFormat)?;
+            if self.buf.len() < body_end {
+                return Ok(ParseStatus::NeedMore);
+            }
+            request.body = self.buf[header_end..body_end].to_vec();
+            body_end
+        };
+
+        // Keep any bytes that belong to the next request
+        self.buf.drain(..request_end);
+        self.consumed = request_end;
+        let head = self.head.take().expect("the head was parsed above");
+        Ok(ParseStatus::Complete(head.request))
+    }
+
+    /// Get the number of bytes the last complete request took up, including its headers
+    /// and body.
+    pub fn consumed(&self) -> usize {
+        self.consumed
+    }
+
+    /// Get the bytes received but not yet part of a complete request.
+    pub fn buffered(&self) -> &[u8] {
+        &self.buf
+    }
+}
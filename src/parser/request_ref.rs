@@ -0,0 +1,90 @@
+//! A request that borrows from the buffer it was parsed from.
+
+use std::borrow::Cow;
+
+use crate::parser::media_type::{is_json_media_type, media_type};
+use crate::parser::method::Method;
+use crate::parser::request::{HttpRequest, is_chunked_encoding};
+use crate::parser::version::HttpVersion;
+
+/// An HTTP request borrowing its path, headers and body from the parsed input.
+///
+/// Parsing into an [`HttpRequestRef`] with [`parse_request_ref`] avoids allocating a string
+/// per header, which pays off when the input buffer outlives the request and most requests
+/// are only inspected. Use [`HttpRequestRef::to_owned`] to get an [`HttpRequest`].
+///
+/// Only parts that had to be rewritten are owned: the path of an absolute-form target
+/// without one, and a chunked body, which is decoded.
+///
+/// [`parse_request_ref`]: crate::parser::parse_request_ref
+#[derive(Debug, Clone)]
+pub struct HttpRequestRef<'a> {
+    /// The HTTP method (GET, POST, etc.)
+    pub method: Method,
+    /// The request path, including any query string
+    pub path: Cow<'a, str>,
+    /// The HTTP version
+    pub version: HttpVersion,
+    /// Every HTTP header in the order received, with its name spelled as received, including
+    /// repeated headers and chunked trailers
+    pub headers: Vec<(&'a str, &'a str)>,
+    /// The request body
+    pub body: Cow<'a, [u8]>,
+    /// The scheme of an absolute-form request target
+    pub scheme: Option<&'a str>,
+    /// The authority of an absolute-form request target
+    pub authority: Option<&'a str>,
+}
+
+impl<'a> HttpRequestRef<'a> {
+    /// Get a header value; when a header is repeated, the last value is returned.
+    ///
+    /// The lookup is case-insensitive.
+    pub fn get_header(&self, name: &str) -> Option<&'a str> {
+        self.headers
+            .iter()
+            .rev()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| *v)
+    }
+
+    /// Get all values of a header, in the order they were received.
+    pub fn get_all_headers(&self, name: &str) -> Vec<&'a str> {
+        self.headers
+            .iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| *v)
+            .collect()
+    }
+
+    /// Check if the Transfer-Encoding headers, joined into one list, end with chunked.
+    pub fn is_chunked(&self) -> bool {
+        is_chunked_encoding(self.get_all_headers("Transfer-Encoding"))
+    }
+
+    /// Get the media type of the Content-Type header, lowercased and without parameters.
+    pub fn media_type(&self) -> Option<String> {
+        self.get_header("Content-Type").map(media_type)
+    }
+
+    /// Check if the media type is application/json or has the `+json` suffix, as
+    /// [`HttpRequest::is_json`] does.
+    pub fn is_json(&self) -> bool {
+        self.media_type().is_some_and(|media_type| is_json_media_type(&media_type))
+    }
+
+    /// Copy the request into an owned [`HttpRequest`].
+    pub fn to_owned(&self) -> HttpRequest {
+        let headers = self.headers.iter().map(|&(name, value)| (name, value)).collect();
+        let mut request = HttpRequest::with_body(
+            self.method.clone(),
+            self.path.clone().into_owned(),
+            self.version,
+            headers,
+            self.body.clone().into_owned(),
+        );
+        request.scheme = self.scheme.map(str::to_string);
+        request.authority = self.authority.map(str::to_string);
+        request
+    }
+}
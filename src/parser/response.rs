@@ -0,0 +1,140 @@
+//! HTTP response parsing, for clients of other servers.
+
+use serde::de::DeserializeOwned;
+
+use crate::parser::error::Error;
+use crate::parser::options::ParseOptions;
+use crate::parser::request::{decode_chunked, find_header_end, is_chunked_encoding, parse_header_lines};
+use crate::parser::version::HttpVersion;
+
+/// An HTTP response received from a server.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponseParsed {
+    /// The HTTP version of the status line
+    pub version: HttpVersion,
+    /// The numeric status code, which may be one the server's `StatusCode` doesn't know
+    pub status: u16,
+    /// The reason phrase, which may be empty
+    pub reason: String,
+    /// Every header in the order received, with its name spelled as received
+    pub headers: Vec<(String, String)>,
+    /// The response body, with any chunked transfer encoding removed
+    pub body: Vec<u8>,
+}
+
+impl HttpResponseParsed {
+    /// Get a header value; when a header is repeated, the last value is returned.
+    ///
+    /// The lookup is case-insensitive.
+    pub fn get_header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .rev()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Get all values of a header, in the order they were received.
+    pub fn get_all_headers(&self, name: &str) -> Vec<&str> {
+        self.headers
+            .iter()
+            .filter(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+            .collect()
+    }
+
+    /// Check if the Transfer-Encoding headers, joined into one list, end with chunked.
+    pub fn is_chunked(&self) -> bool {
+        is_chunked_encoding(self.get_all_headers("Transfer-Encoding"))
+    }
+
+    /// Parse the body as JSON.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, Error> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+}
+
+/// Parse an HTTP response from a byte slice.
+///
+/// The status code is kept as a number, so codes outside the server's `StatusCode` are
+/// parsed too. The body is framed like a request body: a chunked body is decoded, with any
+/// trailers added to the headers, and otherwise the body is capped at `Content-Length` bytes.
+/// Without either header the body is the rest of the input, as a server ends such a body by
+/// closing the connection. `1xx`, `204` and `304` responses have no body.
+///
+/// # Examples
+///
+/// ```
+/// use microhttp_rs::parse_response;
+///
+/// let response = parse_response(b"HTTP/1.1 200 OK\r\nContent-Length: 2\r\n\r\nok").unwrap();
+/// assert_eq!(response.status, 200);
+/// assert_eq!(response.reason, "OK");
+/// assert_eq!(response.body, b"ok");
+/// ```
+pub fn parse_response(input: &[u8]) -> Result<HttpResponseParsed, Error> {
+    let options = ParseOptions::default();
+    let (head, body) = match find_header_end(input) {
+        Some(end) => (&input[..end], &input[end..]),
+        None => (input, &input[input.len()..]),
+    };
+    let mut response = parse_response_head(head, options)?;
+
+    if (100..200).contains(&response.status) || matches!(response.status, 204 | 304) {
+        return Ok(response);
+    }
+    if response.is_chunked() {
+        let chunked = decode_chunked(body, options.strict_line_endings)?;
+        if response.headers.len() + chunked.trailers.len() > options.max_headers {
+            return Err(Error::TooManyHeaders(options.max_headers));
+        }
+        response.body = chunked.data;
+        let trailers = chunked.trailers.into_iter().map(|(name, value)| (name.to_string(), value.to_string()));
+        response.headers.extend(trailers);
+        return Ok(response);
+    }
+    response.body = match response.get_header("Content-Length") {
+        Some(v) => {
+            let content_length = v.parse::<usize>().map_err(|_| Error::InvalidHeaderFormat)?;
+            body[..body.len().min(content_length)].to_vec()
+        }
+        None => body.to_vec(),
+    };
+    Ok(response)
+}
+
+/// Parse the status line and headers of a response, leaving the body empty.
+///
+/// # Arguments
+///
+/// * `head` - The header section, up to and including the empty line that ends it
+pub(crate) fn parse_response_head(head: &[u8], options: ParseOptions) -> Result<HttpResponseParsed, Error> {
+    let head = std::str::from_utf8(head).map_err(|_| Error::MalformedStatusLine("Invalid UTF-8".to_string()))?;
+    let mut lines = head.lines();
+    let status_line = lines.next().unwrap_or("");
+
+    // The reason phrase may be empty or contain spaces
+    let mut parts = status_line.splitn(3, ' ');
+    let (version, status, reason) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(version), Some(status), reason) => (version, status, reason.unwrap_or("")),
+        _ => return Err(Error::MalformedStatusLine(status_line.to_string())),
+    };
+    let version: HttpVersion = version.parse()?;
+    let status = match status.parse::<u16>() {
+        Ok(code) if status.len() == 3 && code >= 100 => code,
+        _ => return Err(Error::MalformedStatusLine(status_line.to_string())),
+    };
+
+    let headers = parse_header_lines(lines, options.max_headers)?
+        .into_iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+
+    Ok(HttpResponseParsed {
+        version,
+        status,
+        reason: reason.trim().to_string(),
+        headers,
+        body: Vec::new(),
+    })
+}
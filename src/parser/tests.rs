@@ -1,11 +1,15 @@
 //! Tests for the HTTP parser.
 
 #[cfg(test)]
+#[allow(clippy::module_inception)]
 mod tests {
-    use std::collections::HashMap;
+    use std::borrow::Cow;
     use serde::{Deserialize, Serialize};
 
-    use crate::parser::{HttpRequest, Method, HttpVersion, Error, parse_request};
+    use crate::parser::{
+        AuthScheme, HeaderMap, HttpRequest, Method, HttpVersion, Error, MultipartLimits, ParseOptions, ParseStatus, RequestParser,
+        parse_request, parse_request_partial, parse_request_ref, parse_request_ref_with_options, parse_request_with_options,
+    };
 
     #[test]
     fn test_parse_simple_get_request() {
@@ -47,9 +51,28 @@ mod tests {
 
     #[test]
     fn test_invalid_method() {
-        let request = b"INVALID /index.html HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        let request = b"INV@LID /index.html HTTP/1.1\r\nHost: example.com\r\n\r\n";
         let result = parse_request(request);
-        assert!(matches!(result, Err(Error::InvalidMethod(ref m)) if m == "INVALID"));
+        assert!(matches!(result, Err(Error::InvalidMethod(ref m)) if m == "INV@LID"));
+
+        for method in ["GE(T", "P\"UT", "GET,POST", "{}"] {
+            assert!(matches!(method.parse::<Method>(), Err(Error::InvalidMethod(_))), "method {method:?}");
+        }
+    }
+
+    #[test]
+    fn test_extension_methods() {
+        let request = b"PROPFIND /files HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        let result = parse_request(request).unwrap();
+        assert_eq!(result.method, Method::Other("PROPFIND".to_string()));
+
+        // Methods are case-sensitive, and extension methods keep their original spelling
+        for method in ["MKCOL", "get", "X-VENDOR.Verb"] {
+            let parsed: Method = method.parse().unwrap();
+            assert_eq!(parsed, Method::Other(method.to_string()));
+            assert_eq!(parsed.to_string(), method);
+        }
+        assert_eq!("GET".parse::<Method>().unwrap(), Method::GET);
     }
 
     #[test]
@@ -100,6 +123,7 @@ mod tests {
             (b"HEAD /index.html HTTP/1.1\r\nHost: example.com\r\n\r\n".to_vec(), Method::HEAD),
             (b"OPTIONS /index.html HTTP/1.1\r\nHost: example.com\r\n\r\n".to_vec(), Method::OPTIONS),
             (b"PATCH /index.html HTTP/1.1\r\nHost: example.com\r\n\r\n".to_vec(), Method::PATCH),
+            (b"TRACE /index.html HTTP/1.1\r\nHost: example.com\r\n\r\n".to_vec(), Method::TRACE),
         ];
 
         for (request, expected_method) in methods {
@@ -108,6 +132,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_connect_method() {
+        // CONNECT requests name the tunnel target in authority form
+        let request = b"CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n";
+        let result = parse_request(request).unwrap();
+        assert_eq!(result.method, Method::CONNECT);
+        assert_eq!(result.path, "example.com:443");
+
+        // The target is left alone when paths are normalized, as is the asterisk form
+        let options = ParseOptions::default().with_normalize_path(true);
+        let result = parse_request_with_options(request, options).unwrap();
+        assert_eq!(result.path, "example.com:443");
+        let result = parse_request_ref_with_options(request, options).unwrap();
+        assert_eq!(result.path, "example.com:443");
+        let result = parse_request_with_options(b"OPTIONS * HTTP/1.1\r\nHost: example.com\r\n\r\n", options).unwrap();
+        assert_eq!(result.path, "*");
+    }
+
     #[test]
     fn test_headers_with_multiple_colons() {
         let request = b"GET /index.html HTTP/1.1\r\nHost: example.com\r\nX-Test: value:with:colons\r\n\r\n";
@@ -133,6 +175,36 @@ mod tests {
         assert!(result.headers.is_empty());
     }
 
+    #[test]
+    fn test_absolute_form_target() {
+        let request = parse_request(b"GET http://example.com:8080/users/42?full=1 HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(request.path, "/users/42?full=1");
+        assert_eq!(request.scheme.as_deref(), Some("http"));
+        assert_eq!(request.authority.as_deref(), Some("example.com:8080"));
+        assert_eq!(request.get_query_param("full").unwrap(), "1");
+
+        // The authority stands in for a missing Host header, but doesn't replace one
+        assert_eq!(request.host(), Some(("example.com", Some(8080))));
+        let request = parse_request(b"GET https://example.com?q=1 HTTP/1.1\r\nHost: other.org\r\n\r\n").unwrap();
+        assert_eq!(request.path, "/?q=1");
+        assert_eq!(request.get_header("Host").unwrap(), "other.org");
+        assert_eq!(request.get_all_headers("Host").len(), 1);
+
+        // Origin-form and asterisk-form targets are left alone
+        let request = parse_request(b"GET /users HTTP/1.1\r\nHost: example.com\r\n\r\n").unwrap();
+        assert_eq!(request.path, "/users");
+        assert_eq!(request.scheme, None);
+        assert_eq!(request.authority, None);
+        let request = parse_request(b"OPTIONS * HTTP/1.1\r\nHost: example.com\r\n\r\n").unwrap();
+        assert_eq!(request.path, "*");
+        assert_eq!(request.authority, None);
+        let result = parse_request(b"GET * HTTP/1.1\r\nHost: example.com\r\n\r\n");
+        assert!(matches!(result, Err(Error::InvalidPath)));
+
+        let result = parse_request(b"GET http:///users HTTP/1.1\r\nHost: example.com\r\n\r\n");
+        assert!(matches!(result, Err(Error::InvalidPath)));
+    }
+
     #[test]
     fn test_method_display() {
         assert_eq!(Method::GET.to_string(), "GET");
@@ -142,10 +214,33 @@ mod tests {
         assert_eq!(Method::HEAD.to_string(), "HEAD");
         assert_eq!(Method::OPTIONS.to_string(), "OPTIONS");
         assert_eq!(Method::PATCH.to_string(), "PATCH");
+        assert_eq!(Method::TRACE.to_string(), "TRACE");
+        assert_eq!(Method::CONNECT.to_string(), "CONNECT");
+    }
+
+    #[test]
+    fn test_method_semantics() {
+        let cases = [
+            (Method::GET, true, true),
+            (Method::HEAD, true, true),
+            (Method::OPTIONS, true, true),
+            (Method::TRACE, true, true),
+            (Method::PUT, false, true),
+            (Method::DELETE, false, true),
+            (Method::POST, false, false),
+            (Method::PATCH, false, false),
+            (Method::CONNECT, false, false),
+            (Method::Other("PROPFIND".to_string()), false, false),
+        ];
+        for (method, safe, idempotent) in cases {
+            assert_eq!(method.is_safe(), safe, "{method} safe");
+            assert_eq!(method.is_idempotent(), idempotent, "{method} idempotent");
+        }
     }
 
     #[test]
     fn test_http_version_display() {
+        assert_eq!(HttpVersion::Http09.to_string(), "HTTP/0.9");
         assert_eq!(HttpVersion::Http10.to_string(), "HTTP/1.0");
         assert_eq!(HttpVersion::Http11.to_string(), "HTTP/1.1");
         assert_eq!(HttpVersion::Http20.to_string(), "HTTP/2");
@@ -179,6 +274,30 @@ mod tests {
         assert_eq!(result.version, HttpVersion::Http11);
     }
 
+    #[test]
+    fn test_http09_simple_request() {
+        let result = parse_request(b"GET /index.html\r\n").unwrap();
+        assert_eq!(result.method, Method::GET);
+        assert_eq!(result.path, "/index.html");
+        assert_eq!(result.version, HttpVersion::Http09);
+        assert_eq!("HTTP/0.9".parse::<HttpVersion>().unwrap(), HttpVersion::Http09);
+
+        // Only GET with a path makes a simple request; other two-part lines stay malformed
+        for request in [&b"POST /index.html\r\n"[..], b"GET index.html\r\n", b"/index.html GET\r\n"] {
+            let result = parse_request(request);
+            assert!(matches!(result, Err(Error::MalformedRequestLine(_))), "request {request:?}");
+        }
+    }
+
+    #[test]
+    fn test_invalid_version_message() {
+        let error = "HTTP/3.5".parse::<HttpVersion>().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid HTTP version: HTTP/3.5 (expected HTTP/0.9, HTTP/1.0, HTTP/1.1 or HTTP/2)"
+        );
+    }
+
     #[test]
     fn test_empty_path() {
         let request = b"GET  HTTP/1.1\r\nHost: example.com\r\n\r\n";
@@ -216,6 +335,16 @@ mod tests {
         assert_eq!(result.query_params.get("empty").unwrap(), "");
     }
 
+    #[test]
+    fn test_repeated_query_parameters() {
+        let request = b"GET /search?tag=a&flag&tag=b&tag= HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        let result = parse_request(request).unwrap();
+        assert_eq!(result.get_query_params("tag"), vec!["a", "b", ""]);
+        assert_eq!(result.get_query_param("tag").unwrap(), "a");
+        assert_eq!(result.get_query_params("flag"), vec![""]);
+        assert!(result.get_query_params("missing").is_empty());
+    }
+
     #[test]
     fn test_malformed_utf8_in_request() {
         let request = b"GET /index.html HTTP/1.1\r\nHost: example.com\r\nX-Test: \xFF\xFF\xFF\r\n\r\n";
@@ -236,11 +365,27 @@ mod tests {
         let result = parse_request(request).unwrap();
         // The second value should overwrite the first
         assert_eq!(result.headers.get("X-Test").unwrap(), "value2");
+        // Every value is kept in the list
+        assert_eq!(result.get_all_headers("X-Test"), vec!["value1", "value2"]);
+    }
+
+    #[test]
+    fn test_duplicate_headers_case_insensitive() {
+        let request = b"GET /index.html HTTP/1.1\r\nHost: example.com\r\nVia: 1.0 first\r\nvia: 1.1 second\r\nVIA: 2 third\r\n\r\n";
+        let result = parse_request(request).unwrap();
+        assert_eq!(result.get_all_headers("Via"), vec!["1.0 first", "1.1 second", "2 third"]);
+        assert_eq!(result.get_all_headers("via").len(), 3);
+        assert!(result.get_all_headers("X-Missing").is_empty());
+
+        // Trailers are appended to the list as well
+        let request = b"POST / HTTP/1.1\r\nHost: example.com\r\nTransfer-Encoding: chunked\r\nX-Sum: a\r\n\r\n0\r\nX-Sum: b\r\n\r\n";
+        let result = parse_request(request).unwrap();
+        assert_eq!(result.get_all_headers("X-Sum"), vec!["a", "b"]);
     }
 
     #[test]
     fn test_http_request_methods() {
-        let mut headers = HashMap::new();
+        let mut headers = HeaderMap::new();
         headers.insert("Host".to_string(), "example.com".to_string());
         headers.insert("Content-Type".to_string(), "application/json".to_string());
 
@@ -278,7 +423,7 @@ mod tests {
 
     #[test]
     fn test_json_parsing() {
-        let mut headers = HashMap::new();
+        let mut headers = HeaderMap::new();
         headers.insert("Host".to_string(), "example.com".to_string());
         headers.insert("Content-Type".to_string(), "application/json".to_string());
 
@@ -304,13 +449,157 @@ mod tests {
         assert!(matches!(result, Err(Error::JsonError(_))));
     }
 
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Pagination {
+        page: u32,
+        limit: Option<u32>,
+        sort: Option<String>,
+    }
+
+    #[test]
+    fn test_query_deserialization() {
+        let request = parse_request(b"GET /items?page=2&limit=10&sort=name+desc%21 HTTP/1.1\r\nHost: example.com\r\n\r\n").unwrap();
+        let pagination: Pagination = request.query().unwrap();
+        assert_eq!(
+            pagination,
+            Pagination {
+                page: 2,
+                limit: Some(10),
+                sort: Some("name desc!".to_string()),
+            }
+        );
+
+        // Missing optional fields are None
+        let request = parse_request(b"GET /items?page=1 HTTP/1.1\r\nHost: example.com\r\n\r\n").unwrap();
+        let pagination: Pagination = request.query().unwrap();
+        assert_eq!(pagination.limit, None);
+
+        // Missing required fields and values of the wrong type are errors
+        for path in ["/items", "/items?limit=5", "/items?page=two"] {
+            let request = format!("GET {path} HTTP/1.1\r\nHost: example.com\r\n\r\n");
+            let result = parse_request(request.as_bytes()).unwrap().query::<Pagination>();
+            assert!(matches!(result, Err(Error::QueryError(_))), "path {path:?}");
+        }
+    }
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Login {
+        username: String,
+        password: String,
+        remember: Option<bool>,
+    }
+
+    #[test]
+    fn test_form_parsing() {
+        let body = b"username=jane+doe&password=p%40ss%26word&remember=true".to_vec();
+        let request = format!(
+            "POST /login HTTP/1.1\r\nHost: example.com\r\nContent-Type: application/x-www-form-urlencoded; charset=utf-8\r\nContent-Length: {}\r\n\r\n",
+            body.len()
+        );
+        let request = parse_request(&[request.into_bytes(), body].concat()).unwrap();
+        assert!(request.is_form());
+        assert!(!request.is_json());
+
+        let login: Login = request.form().unwrap();
+        assert_eq!(
+            login,
+            Login {
+                username: "jane doe".to_string(),
+                password: "p@ss&word".to_string(),
+                remember: Some(true),
+            }
+        );
+
+        let params = request.form_params().unwrap();
+        assert_eq!(params.get("username").unwrap(), "jane doe");
+        assert_eq!(params.len(), 3);
+
+        // A body that doesn't match the type is an error
+        let result = request.form::<Pagination>();
+        assert!(matches!(result, Err(Error::FormError(_))));
+    }
+
+    #[test]
+    fn test_form_requires_content_type() {
+        let request = b"POST /login HTTP/1.1\r\nHost: example.com\r\nContent-Type: application/json\r\nContent-Length: 3\r\n\r\na=b";
+        let request = parse_request(request).unwrap();
+        assert!(!request.is_form());
+        assert!(matches!(request.form::<Login>(), Err(Error::MissingHeader(_))));
+        assert!(matches!(request.form_params(), Err(Error::MissingHeader(_))));
+    }
+
+    fn multipart_request(content_type: &str, body: &[u8]) -> Vec<u8> {
+        let head = format!(
+            "POST /upload HTTP/1.1\r\nHost: example.com\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\n\r\n",
+            body.len()
+        );
+        [head.into_bytes(), body.to_vec()].concat()
+    }
+
+    #[test]
+    fn test_multipart_parsing() {
+        let body = b"preamble\r\n\
+            --XyZ\r\n\
+            Content-Disposition: form-data; name=\"title\"\r\n\
+            \r\n\
+            Holiday\r\n\
+            --XyZ\r\n\
+            Content-Disposition: form-data; name=\"photo\"; filename=\"beach.png\"\r\n\
+            Content-Type: image/png\r\n\
+            \r\n\
+            \x89PNG\r\n--not-a-boundary\r\n\
+            --XyZ--\r\n\
+            epilogue";
+        let request = parse_request(&multipart_request("multipart/form-data; boundary=\"XyZ\"", body)).unwrap();
+        let multipart = request.multipart().unwrap();
+        assert_eq!(multipart.parts().len(), 2);
+
+        // Plain fields have no filename
+        let title = multipart.get("title").unwrap();
+        assert_eq!(title.filename, None);
+        assert!(!title.is_file());
+        assert_eq!(title.text(), Some("Holiday"));
+        assert_eq!(title.content_type(), None);
+
+        // File data keeps line breaks and near-boundaries intact
+        let photo = multipart.get("photo").unwrap();
+        assert_eq!(photo.filename.as_deref(), Some("beach.png"));
+        assert_eq!(photo.content_type().unwrap(), "image/png");
+        assert_eq!(photo.data, b"\x89PNG\r\n--not-a-boundary");
+        assert_eq!(photo.headers.len(), 2);
+    }
+
+    #[test]
+    fn test_multipart_errors() {
+        let part = b"--b\r\nContent-Disposition: form-data; name=\"a\"\r\n\r\n12345\r\n";
+
+        // A body without the closing boundary is malformed
+        let request = parse_request(&multipart_request("multipart/form-data; boundary=b", part)).unwrap();
+        assert!(matches!(request.multipart(), Err(Error::InvalidMultipart(_))));
+
+        // Parts beyond the per-part or total limit are rejected
+        let body = [part.as_slice(), part.as_slice(), b"--b--"].concat();
+        let request = parse_request(&multipart_request("multipart/form-data; boundary=b", &body)).unwrap();
+        assert_eq!(request.multipart().unwrap().parts().len(), 2);
+        let limits = MultipartLimits::default().with_max_part_size(4);
+        assert!(matches!(request.multipart_with_limits(limits), Err(Error::MultipartTooLarge(4))));
+        let limits = MultipartLimits::default().with_max_part_size(5).with_max_total_size(8);
+        assert!(matches!(request.multipart_with_limits(limits), Err(Error::MultipartTooLarge(8))));
+
+        // The content type must be multipart with a boundary
+        let request = parse_request(&multipart_request("multipart/form-data", &body)).unwrap();
+        assert!(matches!(request.multipart(), Err(Error::InvalidMultipart(_))));
+        let request = parse_request(b"POST / HTTP/1.1\r\nHost: a\r\n\r\n").unwrap();
+        assert!(matches!(request.multipart(), Err(Error::MissingHeader(_))));
+    }
+
     #[test]
     fn test_complex_request() {
         let request = b"POST /api/users?role=admin HTTP/1.1\r\n\
             Host: example.com\r\n\
             User-Agent: test-client/1.0\r\n\
             Content-Type: application/json\r\n\
-            Content-Length: 43\r\n\
+            Content-Length: 46\r\n\
             X-API-Key: secret-key\r\n\
             \r\n\
             {\"name\":\"John Doe\",\"email\":\"john@example.com\"}";
@@ -322,12 +611,879 @@ mod tests {
         assert_eq!(result.headers.get("Host").unwrap(), "example.com");
         assert_eq!(result.headers.get("User-Agent").unwrap(), "test-client/1.0");
         assert_eq!(result.headers.get("Content-Type").unwrap(), "application/json");
-        assert_eq!(result.headers.get("Content-Length").unwrap(), "43");
+        assert_eq!(result.headers.get("Content-Length").unwrap(), "46");
         assert_eq!(result.headers.get("X-API-Key").unwrap(), "secret-key");
         assert_eq!(result.query_params.get("role").unwrap(), "admin");
 
-        // Note: The body is not parsed in the current implementation of parse_request
-        // This would require additional logic to read the body based on Content-Length
-        // or Transfer-Encoding headers
+        // The body is read based on the Content-Length header
+        let user: TestUser = result.json().unwrap();
+        assert_eq!(user.name, "John Doe");
+        assert_eq!(user.email, "john@example.com");
+    }
+
+    #[test]
+    fn test_body_capped_at_content_length() {
+        let request = b"POST /api HTTP/1.1\r\nHost: example.com\r\nContent-Length: 5\r\n\r\nhello world";
+        let result = parse_request(request).unwrap();
+        assert_eq!(result.body, b"hello");
+    }
+
+    #[test]
+    fn test_body_shorter_than_content_length() {
+        let request = b"POST /api HTTP/1.1\r\nHost: example.com\r\nContent-Length: 100\r\n\r\npartial";
+        let result = parse_request(request).unwrap();
+        assert_eq!(result.body, b"partial");
+    }
+
+    #[test]
+    fn test_body_without_content_length() {
+        let request = b"POST /api HTTP/1.1\r\nHost: example.com\r\n\r\nignored";
+        let result = parse_request(request).unwrap();
+        assert!(result.body.is_empty());
+    }
+
+    #[test]
+    fn test_invalid_content_length() {
+        let request = b"POST /api HTTP/1.1\r\nHost: example.com\r\nContent-Length: abc\r\n\r\n";
+        let result = parse_request(request);
+        assert!(matches!(result, Err(Error::InvalidHeaderFormat)));
+    }
+
+    #[test]
+    fn test_chunked_body() {
+        let request = b"POST /upload HTTP/1.1\r\nHost: example.com\r\nTransfer-Encoding: chunked\r\n\r\n\
+            5\r\nhello\r\n\
+            6;ext=1\r\n world\r\n\
+            0\r\n\r\n";
+        let result = parse_request(request).unwrap();
+        assert!(result.is_chunked());
+        assert_eq!(result.body, b"hello world");
+    }
+
+    #[test]
+    fn test_chunked_body_with_trailers() {
+        let request = b"POST /upload HTTP/1.1\r\nHost: example.com\r\nTransfer-Encoding: chunked\r\n\r\n\
+            a\r\n0123456789\r\n\
+            0\r\n\
+            X-Checksum: abc123\r\n\
+            \r\n";
+        let result = parse_request(request).unwrap();
+        assert_eq!(result.body, b"0123456789");
+        assert_eq!(result.get_header("X-Checksum").unwrap(), "abc123");
+    }
+
+    #[test]
+    fn test_ambiguous_body_length() {
+        // Transfer-Encoding alongside Content-Length, in either order
+        let request = b"POST /upload HTTP/1.1\r\nHost: example.com\r\nTransfer-Encoding: gzip, chunked\r\nContent-Length: 2\r\n\r\n\
+            3\r\nabc\r\n0\r\n\r\n";
+        assert!(matches!(parse_request(request), Err(Error::AmbiguousBodyLength(_))));
+        let request = b"POST /upload HTTP/1.1\r\nHost: example.com\r\nContent-Length: 3\r\nTransfer-Encoding: chunked\r\n\r\nabc";
+        assert!(matches!(parse_request(request), Err(Error::AmbiguousBodyLength(_))));
+
+        // Conflicting Content-Length values, in separate headers or in a list
+        let request = b"POST /upload HTTP/1.1\r\nHost: example.com\r\nContent-Length: 3\r\nContent-Length: 4\r\n\r\nabcd";
+        assert!(matches!(parse_request(request), Err(Error::AmbiguousBodyLength(_))));
+        let request = b"POST /upload HTTP/1.1\r\nHost: example.com\r\nContent-Length: 3, 4\r\n\r\nabcd";
+        assert!(matches!(parse_request(request), Err(Error::AmbiguousBodyLength(_))));
+
+        // The header section alone is enough to reject the request
+        let mut parser = RequestParser::new();
+        let result = parser.feed(b"POST /upload HTTP/1.1\r\nHost: example.com\r\nContent-Length: 3\r\ncontent-length: 5\r\n\r\n");
+        assert!(matches!(result, Err(Error::AmbiguousBodyLength(_))));
+
+        // Repeating the same value is not ambiguous
+        let request = b"POST /upload HTTP/1.1\r\nHost: example.com\r\nContent-Length: 3\r\nContent-Length: 3\r\n\r\nabc";
+        assert_eq!(parse_request(request).unwrap().body, b"abc");
+        let request = b"POST /upload HTTP/1.1\r\nHost: example.com\r\nContent-Length: 5, 5\r\n\r\nhello";
+        assert_eq!(parse_request(request).unwrap().body, b"hello");
+        let mut parser = RequestParser::new();
+        match parser.feed(request).unwrap() {
+            ParseStatus::Complete(request) => assert_eq!(request.body, b"hello"),
+            ParseStatus::NeedMore => panic!("expected a complete request"),
+        }
+
+        // A signed length isn't a length
+        let request = b"POST /upload HTTP/1.1\r\nHost: example.com\r\nContent-Length: +3\r\n\r\nabc";
+        assert!(matches!(parse_request(request), Err(Error::InvalidHeaderFormat)));
+    }
+
+    #[test]
+    fn test_final_transfer_coding() {
+        // Transfer-Encoding headers are joined into one list, so chunked must come last overall
+        let request = b"POST /upload HTTP/1.1\r\nHost: example.com\r\nTransfer-Encoding: gzip\r\nTransfer-Encoding: chunked\r\n\r\n\
+            3\r\nabc\r\n0\r\n\r\n";
+        let result = parse_request(request).unwrap();
+        assert!(result.is_chunked());
+        assert_eq!(result.body, b"abc");
+
+        let request = b"POST /upload HTTP/1.1\r\nHost: example.com\r\nTransfer-Encoding: chunked\r\nTransfer-Encoding: gzip\r\n\r\n\
+            3\r\nabc\r\n0\r\n\r\n";
+        assert!(matches!(parse_request(request), Err(Error::AmbiguousBodyLength(_))));
+        assert!(matches!(parse_request_ref(request), Err(Error::AmbiguousBodyLength(_))));
+
+        // A request body whose length can't be told from its final coding is rejected
+        let request = b"POST /upload HTTP/1.1\r\nHost: example.com\r\nTransfer-Encoding: gzip\r\n\r\nabc";
+        assert!(matches!(parse_request(request), Err(Error::AmbiguousBodyLength(_))));
+    }
+
+    #[test]
+    fn test_invalid_chunk_size() {
+        let request = b"POST /upload HTTP/1.1\r\nHost: example.com\r\nTransfer-Encoding: chunked\r\n\r\n\
+            zz\r\nhello\r\n0\r\n\r\n";
+        let result = parse_request(request);
+        assert!(matches!(result, Err(Error::InvalidChunkEncoding(ref line)) if line == "zz"));
+
+        // A sign or any other non-hex character isn't part of a chunk size
+        for size in ["+5", "-5", "0x5", "5g", ""] {
+            let request = format!(
+                "POST /upload HTTP/1.1\r\nHost: example.com\r\nTransfer-Encoding: chunked\r\n\r\n{size}\r\nhello\r\n0\r\n\r\n"
+            );
+            let result = parse_request(request.as_bytes());
+            assert!(matches!(result, Err(Error::InvalidChunkEncoding(_))), "{size:?}");
+        }
+    }
+
+    #[test]
+    fn test_chunk_data_longer_than_declared_size() {
+        let request = b"POST /upload HTTP/1.1\r\nHost: example.com\r\nTransfer-Encoding: chunked\r\n\r\n\
+            3\r\nhello\r\n0\r\n\r\n";
+        let result = parse_request(request);
+        assert!(matches!(result, Err(Error::InvalidChunkEncoding(_))));
+    }
+
+    #[test]
+    fn test_cookies() {
+        let request = b"GET / HTTP/1.1\r\nHost: example.com\r\nCookie: session=abc123; theme=\"dark\";empty=\r\n\r\n";
+        let result = parse_request(request).unwrap();
+        let cookies = result.cookies();
+        assert_eq!(cookies.len(), 3);
+        assert_eq!(cookies.get("session").unwrap(), "abc123");
+        assert_eq!(cookies.get("theme").unwrap(), "dark");
+        assert_eq!(cookies.get("empty").unwrap(), "");
+
+        // Without a Cookie header there are no cookies
+        let request = b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        assert!(parse_request(request).unwrap().cookies().is_empty());
+    }
+
+    #[test]
+    fn test_host() {
+        let host_of = |host: &str| {
+            let request = format!("GET / HTTP/1.1\r\nHost: {host}\r\n\r\n");
+            let request = parse_request(request.as_bytes()).unwrap();
+            request.host().map(|(name, port)| (name.to_string(), port))
+        };
+
+        assert_eq!(host_of("example.com"), Some(("example.com".to_string(), None)));
+        assert_eq!(host_of("localhost:8080"), Some(("localhost".to_string(), Some(8080))));
+        assert_eq!(host_of("[::1]:8080"), Some(("::1".to_string(), Some(8080))));
+        assert_eq!(host_of("[2001:db8::1]"), Some(("2001:db8::1".to_string(), None)));
+
+        // Malformed hosts
+        assert_eq!(host_of("localhost:http"), None);
+        assert_eq!(host_of("localhost:99999"), None);
+        assert_eq!(host_of("[::1"), None);
+        assert_eq!(host_of("[::1]8080"), None);
+        assert_eq!(host_of(":8080"), None);
+
+        // Without a Host header there is no host
+        let request = parse_request(b"GET / HTTP/1.0\r\n\r\n").unwrap();
+        assert_eq!(request.host(), None);
+    }
+
+    #[test]
+    fn test_authorization() {
+        let auth_of = |value: &str| {
+            let request = format!("GET / HTTP/1.1\r\nHost: localhost\r\nAuthorization: {value}\r\n\r\n");
+            parse_request(request.as_bytes()).unwrap().authorization()
+        };
+
+        // "Aladdin:open sesame"
+        assert_eq!(
+            auth_of("Basic QWxhZGRpbjpvcGVuIHNlc2FtZQ=="),
+            Some(AuthScheme::Basic {
+                username: "Aladdin".to_string(),
+                password: "open sesame".to_string(),
+            })
+        );
+        // "user:pa:ss" keeps everything after the first colon in the password
+        assert_eq!(
+            auth_of("basic dXNlcjpwYTpzcw=="),
+            Some(AuthScheme::Basic {
+                username: "user".to_string(),
+                password: "pa:ss".to_string(),
+            })
+        );
+        assert_eq!(
+            auth_of("BEARER abc.def.ghi"),
+            Some(AuthScheme::Bearer {
+                token: "abc.def.ghi".to_string(),
+            })
+        );
+        assert_eq!(
+            auth_of("Digest username=\"a\""),
+            Some(AuthScheme::Other {
+                scheme: "Digest".to_string(),
+                value: "username=\"a\"".to_string(),
+            })
+        );
+
+        // Malformed credentials
+        assert_eq!(auth_of("Basic not*base64"), None);
+        assert_eq!(auth_of("Basic QWxhZGRpbg"), None);
+        assert_eq!(auth_of("Basic QWxhZGRpbg=="), None); // No colon
+        assert_eq!(auth_of("Basic //79"), None); // Not UTF-8
+        assert_eq!(auth_of("Bearer"), None);
+
+        // Without an Authorization header there are no credentials
+        let request = parse_request(b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+        assert_eq!(request.authorization(), None);
+    }
+
+    #[test]
+    fn test_request_parser_byte_by_byte() {
+        let request = b"POST /submit HTTP/1.1\r\nHost: example.com\r\nContent-Length: 5\r\n\r\nhello";
+        let mut parser = RequestParser::new();
+
+        // Every prefix of the request needs more bytes
+        for &byte in &request[..request.len() - 1] {
+            assert!(matches!(parser.feed(&[byte]).unwrap(), ParseStatus::NeedMore));
+        }
+        let ParseStatus::Complete(result) = parser.feed(&request[request.len() - 1..]).unwrap() else {
+            panic!("request should be complete");
+        };
+        assert_eq!(result.method, Method::POST);
+        assert_eq!(result.body, b"hello");
+        assert_eq!(parser.consumed(), request.len());
+        assert!(parser.buffered().is_empty());
+    }
+
+    #[test]
+    fn test_request_parser_pipelined_requests() {
+        let mut parser = RequestParser::new();
+        let input = b"POST /first HTTP/1.1\r\nHost: example.com\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n0\r\nX-Trailer: yes\r\n\r\n\
+            GET /second HTTP/1.1\r\nHost: example.com\r\n\r\nGET /thi";
+
+        // Both complete requests are framed from a single feed
+        let ParseStatus::Complete(first) = parser.feed(input).unwrap() else {
+            panic!("first request should be complete");
+        };
+        assert_eq!(first.path, "/first");
+        assert_eq!(first.body, b"abc");
+        assert_eq!(first.get_header("X-Trailer").unwrap(), "yes");
+
+        let ParseStatus::Complete(second) = parser.feed(&[]).unwrap() else {
+            panic!("second request should be complete");
+        };
+        assert_eq!(second.path, "/second");
+        assert_eq!(parser.consumed(), b"GET /second HTTP/1.1\r\nHost: example.com\r\n\r\n".len());
+
+        // The partial third request waits for the rest of its bytes
+        assert!(matches!(parser.feed(&[]).unwrap(), ParseStatus::NeedMore));
+        assert_eq!(parser.buffered(), b"GET /thi");
+        let ParseStatus::Complete(third) = parser.feed(b"rd HTTP/1.0\r\n\r\n").unwrap() else {
+            panic!("third request should be complete");
+        };
+        assert_eq!(third.path, "/third");
+    }
+
+    #[test]
+    fn test_request_parser_invalid_request() {
+        let mut parser = RequestParser::new();
+        assert!(matches!(parser.feed(b"GET /index.html HTTP/1.1\r\n").unwrap(), ParseStatus::NeedMore));
+        let result = parser.feed(b"\r\n");
+        assert!(matches!(result, Err(Error::MissingHeader(ref h)) if h == "Host"));
+    }
+
+    #[test]
+    fn test_request_parser_limits() {
+        // A length too large to address is rejected instead of overflowing
+        let mut parser = RequestParser::new();
+        let result = parser.feed(b"POST / HTTP/1.1\r\nHost: example.com\r\nContent-Length: 18446744073709551615\r\n\r\n");
+        assert!(matches!(result, Err(Error::InvalidHeaderFormat)));
+
+        // A header section past the size limit is rejected before it is complete
+        let options = ParseOptions::default().with_max_head_size(64);
+        assert_eq!(ParseOptions::default().max_head_size, 64 * 1024);
+        let mut parser = RequestParser::with_options(options);
+        assert!(matches!(parser.feed(b"GET / HTTP/1.1\r\nHost: example.com\r\n").unwrap(), ParseStatus::NeedMore));
+        let result = parser.feed(&[b'x'; 64]);
+        assert!(matches!(result, Err(Error::HeadersTooLarge(64))));
+
+        // And once it is complete
+        let mut parser = RequestParser::with_options(options);
+        let result = parser.feed(format!("GET / HTTP/1.1\r\nHost: example.com\r\nX-Padding: {}\r\n\r\n", "x".repeat(30)).as_bytes());
+        assert!(matches!(result, Err(Error::HeadersTooLarge(64))));
+    }
+
+    #[test]
+    fn test_request_parser_chunked_pieces() {
+        // A chunked body fed a byte at a time is decoded as it arrives
+        let data = "abcdefghij".repeat(6_000);
+        let mut request = b"POST /upload HTTP/1.1\r\nHost: example.com\r\nTransfer-Encoding: chunked\r\n\r\n".to_vec();
+        for chunk in data.as_bytes().chunks(1000) {
+            request.extend_from_slice(format!("{:x}\r\n", chunk.len()).as_bytes());
+            request.extend_from_slice(chunk);
+            request.extend_from_slice(b"\r\n");
+        }
+        request.extend_from_slice(b"0\r\nX-Checksum: 42\r\n\r\n");
+
+        let mut parser = RequestParser::new();
+        for &byte in &request[..request.len() - 1] {
+            assert!(matches!(parser.feed(&[byte]).unwrap(), ParseStatus::NeedMore));
+        }
+        let ParseStatus::Complete(result) = parser.feed(&request[request.len() - 1..]).unwrap() else {
+            panic!("request should be complete");
+        };
+        assert_eq!(result.body, data.as_bytes());
+        assert_eq!(result.get_header("X-Checksum").unwrap(), "42");
+        assert_eq!(parser.consumed(), request.len());
+        assert!(parser.buffered().is_empty());
+    }
+
+    #[test]
+    fn test_parse_request_partial() {
+        let first = b"POST /first HTTP/1.1\r\nHost: example.com\r\nContent-Length: 3\r\n\r\nabc".to_vec();
+        let second = b"POST /second HTTP/1.1\r\nHost: example.com\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nde\r\n0\r\n\r\n".to_vec();
+        let third = b"GET /third HTTP/1.1\r\nHost: example.com\r\n\r\n".to_vec();
+        let input = [first.clone(), second.clone(), third.clone()].concat();
+
+        // Slice the buffer after each request to reach the next pipelined one
+        let (request, consumed) = parse_request_partial(&input).unwrap();
+        assert_eq!(request.path, "/first");
+        assert_eq!(request.body, b"abc");
+        assert_eq!(consumed, first.len());
+
+        let (request, consumed2) = parse_request_partial(&input[consumed..]).unwrap();
+        assert_eq!(request.path, "/second");
+        assert_eq!(request.body, b"de");
+        assert_eq!(consumed2, second.len());
+
+        let (request, consumed3) = parse_request_partial(&input[consumed + consumed2..]).unwrap();
+        assert_eq!(request.path, "/third");
+        assert_eq!(consumed3, third.len());
+
+        // A body shorter than its Content-Length takes up only the available bytes
+        let (_, consumed) = parse_request_partial(b"POST / HTTP/1.1\r\nHost: a\r\nContent-Length: 10\r\n\r\nabc").unwrap();
+        assert_eq!(consumed, 51);
+    }
+
+    #[test]
+    fn test_max_headers() {
+        let headers: String = (0..101).map(|i| format!("X-Header-{i}: {i}\r\n")).collect();
+        let request = format!("GET / HTTP/1.1\r\nHost: example.com\r\n{headers}\r\n");
+
+        // The default limit is 100 header lines
+        assert!(matches!(parse_request(request.as_bytes()), Err(Error::TooManyHeaders(100))));
+        let options = ParseOptions::default().with_max_headers(102);
+        assert_eq!(parse_request_with_options(request.as_bytes(), options).unwrap().headers.len(), 102);
+        let options = ParseOptions::default().with_max_headers(101);
+        assert!(matches!(parse_request_with_options(request.as_bytes(), options), Err(Error::TooManyHeaders(101))));
+
+        // Trailers count toward the limit
+        let request = b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n0\r\nX-Trailer: 1\r\n\r\n";
+        let options = ParseOptions::default().with_max_headers(2);
+        assert!(matches!(parse_request_with_options(request, options), Err(Error::TooManyHeaders(2))));
+
+        let mut parser = RequestParser::with_options(ParseOptions::default().with_max_headers(2));
+        assert!(matches!(parser.feed(request), Err(Error::TooManyHeaders(2))));
+        let mut parser = RequestParser::with_options(ParseOptions::default().with_max_headers(3));
+        assert!(matches!(parser.feed(request).unwrap(), ParseStatus::Complete(_)));
+    }
+
+    #[test]
+    fn test_strict_line_endings() {
+        let strict = ParseOptions::default().with_strict_line_endings(true);
+        let request = b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        assert!(parse_request_with_options(request, strict).is_ok());
+
+        // Bare line endings are accepted by default and rejected in strict mode
+        for request in [
+            &b"GET / HTTP/1.1\nHost: example.com\r\n\r\n"[..],
+            b"GET / HTTP/1.1\r\nHost: example.com\n\n",
+            b"GET / HTTP/1.1\r\nHost: example.com\rX-Other: 1\r\n\r\n",
+        ] {
+            assert!(parse_request(request).is_ok());
+            assert!(matches!(parse_request_with_options(request, strict), Err(Error::InvalidLineEnding)));
+        }
+
+        // The chunked body framing is checked too, but not the chunk data
+        let request = b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n3\r\na\nb\r\n0\r\n\r\n";
+        assert_eq!(parse_request_with_options(request, strict).unwrap().body, b"a\nb");
+        for request in [
+            &b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n1\na\r\n0\r\n\r\n"[..],
+            b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n1\r\na\n0\r\n\r\n",
+            b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n0\r\n\n",
+        ] {
+            assert!(parse_request(request).is_ok());
+            assert!(matches!(parse_request_with_options(request, strict), Err(Error::InvalidLineEnding)));
+        }
+
+        let mut parser = RequestParser::with_options(strict);
+        assert!(matches!(parser.feed(b"GET / HTTP/1.1\r\nHost: a\n\r\n"), Err(Error::InvalidLineEnding)));
+    }
+
+    #[test]
+    fn test_request_to_bytes() {
+        let request = b"POST /submit?x=1 HTTP/1.1\r\nHost: example.com\r\nContent-Length: 5\r\nX-Tag: a\r\nX-Tag: b\r\n\r\nhello";
+        let parsed = parse_request(request).unwrap();
+        assert_eq!(parsed.to_bytes(), request);
+
+        // Absolute-form targets and HTTP/0.9 requests keep their form
+        let request = b"GET http://example.com/a HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        assert_eq!(parse_request(request).unwrap().to_bytes(), request);
+        assert_eq!(parse_request(b"GET /old\r\n").unwrap().to_bytes(), b"GET /old\r\n");
+
+        // A chunked body is sent as one chunk and parses back to the same request
+        let request = b"POST /upload HTTP/1.1\r\nHost: example.com\r\nTransfer-Encoding: chunked\r\n\r\n\
+            3\r\nabc\r\n2\r\nde\r\n0\r\nX-Checksum: 1\r\n\r\n";
+        let parsed = parse_request(request).unwrap();
+        let bytes = parsed.to_bytes();
+        assert!(bytes.ends_with(b"\r\nX-Checksum: 1\r\n\r\n5\r\nabcde\r\n0\r\n\r\n"));
+        let reparsed = parse_request(&bytes).unwrap();
+        assert_eq!(reparsed.body, b"abcde");
+        assert_eq!(reparsed.headers, parsed.headers);
+    }
+
+    #[test]
+    fn test_header_order_and_casing() {
+        let request = b"GET / HTTP/1.1\r\nhost: example.com\r\nX-Amz-Date: 20240101T000000Z\r\nACCEPT: */*\r\nx-amz-date: 20240202T000000Z\r\n\r\n";
+        let parsed = parse_request(request).unwrap();
+
+        // The headers come back in arrival order with their original spelling
+        let headers: Vec<(&str, &str)> = parsed.headers_in_order().collect();
+        assert_eq!(
+            headers,
+            vec![
+                ("host", "example.com"),
+                ("X-Amz-Date", "20240101T000000Z"),
+                ("ACCEPT", "*/*"),
+                ("x-amz-date", "20240202T000000Z"),
+            ]
+        );
+        assert_eq!(parsed.to_bytes(), request);
+
+        // Lookups are case-insensitive, and a name repeated in another casing keeps both values
+        assert_eq!(parsed.get_header("Host").unwrap(), "example.com");
+        assert_eq!(parsed.get_header("X-AMZ-DATE").unwrap(), "20240202T000000Z");
+        assert_eq!(parsed.get_all_headers("X-Amz-Date"), vec!["20240101T000000Z", "20240202T000000Z"]);
+        assert_eq!(parsed.headers.len(), 4);
+    }
+
+    #[test]
+    fn test_parse_request_ref() {
+        let input = b"POST /submit?x=1 HTTP/1.1\r\nHost: example.com\r\nContent-Length: 5\r\nX-Tag: a\r\nx-tag: b\r\n\r\nhello";
+        let request = parse_request_ref(input).unwrap();
+        assert_eq!(request.method, Method::POST);
+        assert_eq!(request.version, HttpVersion::Http11);
+        assert_eq!(request.get_header("X-TAG"), Some("b"));
+        assert_eq!(request.get_all_headers("x-tag"), vec!["a", "b"]);
+
+        // The path, headers and body point into the input
+        let input_range = input.as_ptr_range();
+        assert!(matches!(request.path, Cow::Borrowed("/submit?x=1")));
+        assert!(matches!(request.body, Cow::Borrowed(b"hello")));
+        assert!(input_range.contains(&request.body.as_ptr()));
+        assert!(request.headers.iter().all(|(name, value)| {
+            input_range.contains(&name.as_ptr()) && input_range.contains(&value.as_ptr())
+        }));
+
+        // Converting gives the same request as owned parsing
+        let owned = request.to_owned();
+        let parsed = parse_request(input).unwrap();
+        assert_eq!(owned.path, parsed.path);
+        assert_eq!(owned.headers, parsed.headers);
+        assert_eq!(owned.headers, parsed.headers);
+        assert_eq!(owned.query_params, parsed.query_params);
+        assert_eq!(owned.body, parsed.body);
+
+        // A chunked body is decoded into an owned buffer, while trailers still borrow
+        let input = b"POST / HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n2\r\nab\r\n1\r\nc\r\n0\r\nX-Sum: 3\r\n\r\n";
+        let request = parse_request_ref(input).unwrap();
+        assert!(matches!(request.body, Cow::Owned(ref body) if body == b"abc"));
+        assert_eq!(request.get_header("X-Sum"), Some("3"));
+
+        // Absolute-form targets without a path get an owned `/`
+        let request = parse_request_ref(b"GET http://example.com?q=1 HTTP/1.1\r\n\r\n").unwrap();
+        assert_eq!(request.path, "/?q=1");
+        assert_eq!((request.scheme, request.authority), (Some("http"), Some("example.com")));
+        assert_eq!(request.get_header("Host"), Some("example.com"));
+
+        assert!(matches!(parse_request_ref(b"GET / HTTP/1.1\r\n\r\n"), Err(Error::MissingHeader(_))));
+    }
+
+    #[test]
+    fn test_binary_body() {
+        // A body that is not valid UTF-8, with a blank line and NUL bytes in it
+        let body: &[u8] = b"\x89PNG\r\n\r\n\x00\xff\xfe\x00";
+        let mut input = format!("POST /upload HTTP/1.1\r\nHost: example.com\r\nContent-Length: {}\r\n\r\n", body.len())
+            .into_bytes();
+        input.extend_from_slice(body);
+
+        let request = parse_request(&input).unwrap();
+        assert_eq!(request.body, body);
+        assert_eq!(request.headers.len(), 2);
+        assert!(std::str::from_utf8(&request.body).is_err());
+
+        let request_ref = parse_request_ref(&input).unwrap();
+        assert!(matches!(request_ref.body, Cow::Borrowed(b) if b == body));
+
+        // A chunked binary body is decoded byte for byte
+        let mut input = b"POST /upload HTTP/1.1\r\nHost: example.com\r\nTransfer-Encoding: chunked\r\n\r\n".to_vec();
+        input.extend_from_slice(format!("{:x}\r\n", body.len()).as_bytes());
+        input.extend_from_slice(body);
+        input.extend_from_slice(b"\r\n0\r\n\r\n");
+        assert_eq!(parse_request(&input).unwrap().body, body);
+    }
+
+    #[test]
+    fn test_accepts() {
+        let with_accept = |accept: &str| {
+            parse_request(format!("GET / HTTP/1.1\r\nHost: example.com\r\nAccept: {accept}\r\n\r\n").as_bytes()).unwrap()
+        };
+
+        let request = with_accept("text/html, application/*;q=0.5, */*;q=0.1");
+        assert!(request.accepts("text/html"));
+        assert!(request.accepts("application/json"));
+        assert!(request.accepts("image/png"));
+
+        // The most specific range decides, even when a wildcard would accept the type
+        let request = with_accept("*/*, text/html;q=0, application/*;q=0");
+        assert!(!request.accepts("text/html"));
+        assert!(!request.accepts("application/json"));
+        assert!(request.accepts("text/plain"));
+        assert!(request.accepts("TEXT/PLAIN; charset=utf-8"));
+
+        let request = with_accept("application/json");
+        assert!(request.accepts("Application/JSON"));
+        assert!(!request.accepts("text/html"));
+
+        // Without the header every type is accepted
+        let request = parse_request(b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n").unwrap();
+        assert!(request.accepts("text/html"));
+    }
+
+    #[test]
+    fn test_preferred() {
+        let with_accept = |accept: &str| {
+            parse_request(format!("GET / HTTP/1.1\r\nHost: example.com\r\nAccept: {accept}\r\n\r\n").as_bytes()).unwrap()
+        };
+        let offered = ["application/json", "text/html"];
+
+        // Quality factors decide, then the order of the offers
+        assert_eq!(with_accept("text/html, application/json;q=0.8").preferred(&offered), Some("text/html"));
+        assert_eq!(with_accept("text/html;q=0.5, application/json;q=0.5").preferred(&offered), Some("application/json"));
+        assert_eq!(with_accept("*/*").preferred(&offered), Some("application/json"));
+        assert_eq!(with_accept("text/*;q=0.9, */*;q=0.2").preferred(&offered), Some("text/html"));
+        assert_eq!(with_accept("text/html;level=1;q=0.7, application/json;q=0.6").preferred(&offered), Some("text/html"));
+
+        // Nothing acceptable
+        assert_eq!(with_accept("image/png").preferred(&offered), None);
+        assert_eq!(with_accept("*/*;q=0").preferred(&offered), None);
+        assert_eq!(with_accept("text/html").preferred(&[]), None);
+
+        // Malformed quality factors leave the range out
+        assert_eq!(with_accept("text/html;q=2, application/json;q=abc, */*;q=0.1").preferred(&offered), Some("application/json"));
+
+        // Without the header the first offer wins
+        let request = parse_request(b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n").unwrap();
+        assert_eq!(request.preferred(&offered), Some("application/json"));
+    }
+
+    #[test]
+    fn test_accepted_encodings() {
+        let with_accept_encoding = |accept_encoding: &str| {
+            let request = format!("GET / HTTP/1.1\r\nHost: example.com\r\nAccept-Encoding: {accept_encoding}\r\n\r\n");
+            parse_request(request.as_bytes()).unwrap().accepted_encodings()
+        };
+        let owned = |codings: &[(&str, f32)]| -> Vec<(String, f32)> {
+            codings.iter().map(|&(coding, q)| (coding.to_string(), q)).collect()
+        };
+
+        assert_eq!(
+            with_accept_encoding("gzip, deflate;q=0.5, *;q=0"),
+            owned(&[("gzip", 1.0), ("deflate", 0.5), ("*", 0.0)])
+        );
+        assert_eq!(
+            with_accept_encoding("identity;q=0.1, GZIP;q=0.8, br"),
+            owned(&[("br", 1.0), ("gzip", 0.8), ("identity", 0.1)])
+        );
+        // Equal qualities keep the header's order, and malformed entries are left out
+        assert_eq!(with_accept_encoding("br, gzip, deflate;q=x, ,"), owned(&[("br", 1.0), ("gzip", 1.0)]));
+        assert_eq!(with_accept_encoding("gzip;q=0"), owned(&[("gzip", 0.0)]));
+
+        let request = parse_request(b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n").unwrap();
+        assert!(request.accepted_encodings().is_empty());
+    }
+
+    #[test]
+    fn test_parse_response() {
+        use crate::parser::parse_response;
+
+        let response = parse_response(b"HTTP/1.1 404 Not Found\r\nContent-Type: text/plain\r\nSet-Cookie: a=1\r\nSet-Cookie: b=2\r\nContent-Length: 9\r\n\r\nNot found").unwrap();
+        assert_eq!(response.version, HttpVersion::Http11);
+        assert_eq!(response.status, 404);
+        assert_eq!(response.reason, "Not Found");
+        assert_eq!(response.get_header("content-type"), Some("text/plain"));
+        assert_eq!(response.get_all_headers("Set-Cookie"), vec!["a=1", "b=2"]);
+        assert_eq!(response.body, b"Not found");
+
+        // Status codes outside the known set, and empty reason phrases
+        let response = parse_response(b"HTTP/1.1 599 Network Connect Timeout Error\r\n\r\n").unwrap();
+        assert_eq!(response.status, 599);
+        assert_eq!(response.reason, "Network Connect Timeout Error");
+        let response = parse_response(b"HTTP/1.1 200\r\nContent-Length: 0\r\n\r\n").unwrap();
+        assert_eq!(response.reason, "");
+
+        // Chunked bodies are decoded, with their trailers added to the headers
+        let response = parse_response(b"HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n3\r\nabc\r\n0\r\nX-Done: yes\r\n\r\n").unwrap();
+        assert_eq!(response.body, b"abc");
+        assert_eq!(response.get_header("X-Done"), Some("yes"));
+
+        // Without a length the body runs to the end of the input, except for bodiless statuses
+        let response = parse_response(b"HTTP/1.0 200 OK\r\n\r\n<html></html>").unwrap();
+        assert_eq!(response.body, b"<html></html>");
+        let response = parse_response(b"HTTP/1.1 304 Not Modified\r\nContent-Length: 5\r\n\r\n").unwrap();
+        assert!(response.body.is_empty());
+    }
+
+    #[test]
+    fn test_parse_response_errors() {
+        use crate::parser::parse_response;
+
+        assert!(matches!(parse_response(b""), Err(Error::MalformedStatusLine(_))));
+        assert!(matches!(parse_response(b"HTTP/1.1\r\n\r\n"), Err(Error::MalformedStatusLine(_))));
+        assert!(matches!(parse_response(b"HTTP/1.1 20 OK\r\n\r\n"), Err(Error::MalformedStatusLine(_))));
+        assert!(matches!(parse_response(b"HTTP/1.1 abc OK\r\n\r\n"), Err(Error::MalformedStatusLine(_))));
+        assert!(matches!(parse_response(b"HTTP/3 200 OK\r\n\r\n"), Err(Error::InvalidVersion(_))));
+        assert!(matches!(parse_response(b"HTTP/1.1 200 OK\r\nNo colon\r\n\r\n"), Err(Error::InvalidHeaderFormat)));
+        assert!(matches!(parse_response(b"HTTP/1.1 200 OK\r\nContent-Length: x\r\n\r\n"), Err(Error::InvalidHeaderFormat)));
+    }
+
+    #[test]
+    fn test_remove_hop_by_hop_headers() {
+        use crate::parser::remove_hop_by_hop_headers;
+
+        let mut request = parse_request(
+            b"GET / HTTP/1.1\r\nHost: example.com\r\nConnection: keep-alive, X-Foo\r\nX-Foo: internal\r\nKeep-Alive: timeout=5\r\nTE: trailers\r\nTrailers: X-Sum\r\nUpgrade: h2c\r\nProxy-Authorization: Basic abc\r\nX-Bar: kept\r\n\r\n",
+        )
+        .unwrap();
+        request.remove_hop_by_hop_headers();
+        assert_eq!(
+            request.headers_in_order().collect::<Vec<_>>(),
+            vec![("Host", "example.com"), ("X-Bar", "kept")]
+        );
+        assert_eq!(request.headers.len(), 2);
+        assert!(request.get_header("X-Foo").is_none());
+
+        // Header names are matched whatever their casing
+        let mut headers = vec![
+            ("connection".to_string(), "X-FOO".to_string()),
+            ("x-foo".to_string(), "1".to_string()),
+            ("TRANSFER-ENCODING".to_string(), "chunked".to_string()),
+            ("X-Other".to_string(), "2".to_string()),
+        ];
+        remove_hop_by_hop_headers(&mut headers);
+        assert_eq!(headers, vec![("X-Other".to_string(), "2".to_string())]);
+    }
+
+    #[test]
+    fn test_normalized_path() {
+        let normalized = |path: &str| {
+            let request = format!("GET {path} HTTP/1.0\r\n\r\n");
+            parse_request(request.as_bytes()).unwrap().normalized_path()
+        };
+        assert_eq!(normalized("/").unwrap(), "/");
+        assert_eq!(normalized("/a//b///c").unwrap(), "/a/b/c");
+        assert_eq!(normalized("/a/./b/../c").unwrap(), "/a/c");
+        assert_eq!(normalized("/a/b/..").unwrap(), "/a/");
+        assert_eq!(normalized("/a/b/").unwrap(), "/a/b/");
+        assert_eq!(normalized("/a/..").unwrap(), "/");
+        assert_eq!(normalized("/a/...").unwrap(), "/a/...");
+
+        // The query string is kept as it is, dot segments and all
+        assert_eq!(normalized("/a/../b?next=/x/../y&c=1").unwrap(), "/b?next=/x/../y&c=1");
+
+        // Percent-encoded dots are dot segments, but an encoded slash doesn't separate segments
+        assert_eq!(normalized("/a/%2e%2E/b").unwrap(), "/b");
+        assert_eq!(normalized("/a/.%2e/b").unwrap(), "/b");
+        assert_eq!(normalized("/a%2F..%2Fb/c").unwrap(), "/a%2F..%2Fb/c");
+        assert_eq!(normalized("/caf%C3%A9/./x").unwrap(), "/caf%C3%A9/x");
+
+        // Climbing above the root and malformed escapes are rejected
+        assert!(matches!(normalized("/.."), Err(Error::InvalidPath)));
+        assert!(matches!(normalized("/a/../../b"), Err(Error::InvalidPath)));
+        assert!(matches!(normalized("/%2e%2e/etc/passwd"), Err(Error::InvalidPath)));
+        assert!(matches!(normalized("/a%2"), Err(Error::InvalidPath)));
+        assert!(matches!(normalized("/a%zz"), Err(Error::InvalidPath)));
+
+        let request = parse_request(b"OPTIONS * HTTP/1.0\r\n\r\n").unwrap();
+        assert_eq!(request.normalized_path().unwrap(), "*");
+    }
+
+    #[test]
+    fn test_normalize_path_option() {
+        let request = b"GET /static//css/../app.js?v=1 HTTP/1.0\r\n\r\n";
+        assert_eq!(parse_request(request).unwrap().path, "/static//css/../app.js?v=1");
+
+        let options = ParseOptions::default().with_normalize_path(true);
+        let parsed = parse_request_with_options(request, options).unwrap();
+        assert_eq!(parsed.path, "/static/app.js?v=1");
+        assert_eq!(parsed.query_params.get("v").map(String::as_str), Some("1"));
+
+        let parsed = parse_request_with_options(b"GET http://example.com/a/./b HTTP/1.1\r\n\r\n", options).unwrap();
+        assert_eq!(parsed.path, "/a/b");
+
+        assert!(matches!(
+            parse_request_with_options(b"GET /../secret HTTP/1.0\r\n\r\n", options),
+            Err(Error::InvalidPath)
+        ));
+    }
+
+    #[test]
+    fn test_request_equality() {
+        let input = b"GET /a?x=1 HTTP/1.1\r\nHost: example.com\r\n\r\n";
+        let request = parse_request(input).unwrap();
+        assert_eq!(request, parse_request(input).unwrap());
+        assert_eq!(request, request.clone());
+        assert_ne!(request, parse_request(b"GET /a?x=2 HTTP/1.1\r\nHost: example.com\r\n\r\n").unwrap());
+        assert_ne!(request, parse_request(b"GET /a?x=1 HTTP/1.1\r\nHost: example.org\r\n\r\n").unwrap());
+
+        let mut with_body = request.clone();
+        with_body.body = b"data".to_vec();
+        assert_ne!(request, with_body);
+    }
+
+    #[test]
+    fn test_request_builder() {
+        let request = HttpRequest::builder().build();
+        assert_eq!(request.method, Method::GET);
+        assert_eq!(request.path, "/");
+        assert_eq!(request.version, HttpVersion::Http11);
+        assert!(request.headers.is_empty());
+        assert!(request.body.is_empty());
+
+        let request = HttpRequest::builder()
+            .method(Method::PUT)
+            .path("/items/7?tag=a&tag=b&draft")
+            .header("Host", "example.com")
+            .header("Accept", "text/html")
+            .header("Accept", "application/json")
+            .json_body(&serde_json::json!({"id": 7}))
+            .unwrap()
+            .path_param("id", "7")
+            .peer_addr("10.0.0.1:4000".parse().unwrap())
+            .build();
+        assert_eq!(request.method, Method::PUT);
+        assert_eq!(request.get_query_param("tag").unwrap(), "a");
+        assert_eq!(request.get_query_params("tag"), vec!["a", "b"]);
+        assert!(request.has_query_param("draft"));
+        assert_eq!(request.get_all_headers("Accept"), vec!["text/html", "application/json"]);
+        assert_eq!(request.get_header("Content-Type").unwrap(), "application/json");
+        assert_eq!(request.get_header("Content-Length").unwrap(), "8");
+        assert_eq!(request.body, br#"{"id":7}"#);
+        assert_eq!(request.get_path_param("id").unwrap(), "7");
+        assert_eq!(request.peer_addr, Some("10.0.0.1:4000".parse().unwrap()));
+
+        // Headers given explicitly are kept
+        let request = HttpRequest::builder()
+            .header("Content-Type", "application/vnd.api+json")
+            .header("Content-Length", "2")
+            .json_body(&1)
+            .unwrap()
+            .body("{}")
+            .build();
+        assert_eq!(request.get_all_headers("Content-Type"), vec!["application/vnd.api+json"]);
+        assert_eq!(request.get_all_headers("Content-Length"), vec!["2"]);
+
+        // A built request reads back the same after a round trip through the parser
+        let request = HttpRequest::builder()
+            .method(Method::POST)
+            .path("/submit?x=1")
+            .header("Host", "localhost")
+            .body("a=1")
+            .build();
+        assert_eq!(parse_request(&request.to_bytes()).unwrap(), request);
+    }
+
+    #[test]
+    fn test_media_type() {
+        let with_content_type = |content_type: &str| {
+            let request = format!("POST / HTTP/1.1\r\nHost: example.com\r\nContent-Type: {content_type}\r\n\r\n");
+            parse_request(request.as_bytes()).unwrap()
+        };
+
+        for json in ["application/json", "application/json;charset=utf-8", "application/json ; charset=utf-8", "Application/JSON", "application/problem+json", "application/vnd.api+json"] {
+            let request = with_content_type(json);
+            assert!(request.is_json(), "{json}");
+            assert!(parse_request_ref(&request.to_bytes()).unwrap().is_json(), "{json}");
+        }
+        for other in ["text/plain", "application/jsonp", "application/json-seq", "text/json+other"] {
+            assert!(!with_content_type(other).is_json(), "{other}");
+        }
+
+        let request = with_content_type(" Text/HTML; charset=UTF-8");
+        assert_eq!(request.media_type().as_deref(), Some("text/html"));
+        assert_eq!(parse_request(b"GET / HTTP/1.1\r\nHost: example.com\r\n\r\n").unwrap().media_type(), None);
+
+        assert!(with_content_type("Application/X-WWW-Form-Urlencoded; charset=utf-8").is_form());
+        assert!(!with_content_type("application/x-www-form-urlencoded-extra").is_form());
+    }
+
+    #[test]
+    fn test_header_map() {
+        let mut headers: HeaderMap = [("Host", "example.com"), ("Accept", "text/html"), ("X-Tag", "a"), ("accept", "*/*")]
+            .into_iter()
+            .collect();
+        assert_eq!(headers.len(), 4);
+        assert_eq!(headers.get("ACCEPT").unwrap(), "*/*");
+        assert_eq!(headers.get_all("Accept"), vec!["text/html", "*/*"]);
+        assert!(headers.contains("x-tag"));
+
+        // Replacing a repeated header keeps the position of its first occurrence
+        headers.insert("ACCEPT", "application/json");
+        let lines: Vec<(&str, &str)> = headers.iter().collect();
+        assert_eq!(lines, vec![("Host", "example.com"), ("ACCEPT", "application/json"), ("X-Tag", "a")]);
+
+        // New and appended headers go at the end
+        headers.append("x-tag", "b");
+        headers.insert("Content-Type", "text/plain");
+        assert_eq!(headers.get_all("X-Tag"), vec!["a", "b"]);
+        assert_eq!(headers.iter().last(), Some(("Content-Type", "text/plain")));
+
+        assert_eq!(headers.remove("X-TAG").as_deref(), Some("b"));
+        assert_eq!(headers.remove("X-Tag"), None);
+        headers.retain(|name, _| !name.eq_ignore_ascii_case("host"));
+        let names: Vec<&str> = headers.iter().map(|(name, _)| name).collect();
+        assert_eq!(names, vec!["ACCEPT", "Content-Type"]);
+    }
+
+    #[test]
+    fn test_method_and_version_serde() {
+        assert_eq!(serde_json::to_string(&Method::GET).unwrap(), r#""GET""#);
+        assert_eq!(serde_json::from_str::<Method>(r#""GET""#).unwrap(), Method::GET);
+        assert_eq!(serde_json::to_string(&HttpVersion::Http11).unwrap(), r#""HTTP/1.1""#);
+        assert_eq!(serde_json::from_str::<HttpVersion>(r#""HTTP/2.0""#).unwrap(), HttpVersion::Http20);
+
+        // Both round-trip inside other values, extension methods included
+        #[derive(Debug, Serialize, Deserialize, PartialEq)]
+        struct Route {
+            methods: Vec<Method>,
+            version: HttpVersion,
+        }
+        let route = Route {
+            methods: vec![Method::POST, Method::Other("PROPFIND".to_string())],
+            version: HttpVersion::Http10,
+        };
+        let json = serde_json::to_string(&route).unwrap();
+        assert_eq!(json, r#"{"methods":["POST","PROPFIND"],"version":"HTTP/1.0"}"#);
+        assert_eq!(serde_json::from_str::<Route>(&json).unwrap(), route);
+
+        // Values that wouldn't parse in a request line are rejected
+        let error = serde_json::from_str::<Method>(r#""G T""#).unwrap_err();
+        assert!(error.to_string().contains("Invalid HTTP method: G T"));
+        assert!(serde_json::from_str::<HttpVersion>(r#""HTTP/3""#).is_err());
+        assert!(serde_json::from_str::<Method>("1").is_err());
     }
 }
@@ -3,11 +3,16 @@
 use std::fmt;
 use std::str::FromStr;
 
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
 use crate::parser::error::Error;
 
 /// Supported HTTP protocol versions.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum HttpVersion {
+    /// HTTP/0.9: The original one-line protocol, with a request line of only a method and a
+    /// path, and a response of only the body.
+    Http09,
     /// HTTP/1.0: The first version of the HTTP protocol widely used on the web.
     Http10,
     /// HTTP/1.1: Improved version with persistent connections, chunked transfer encoding, and more.
@@ -21,6 +26,7 @@ impl FromStr for HttpVersion {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         match s {
+            "HTTP/0.9" => Ok(HttpVersion::Http09),
             "HTTP/1.0" => Ok(HttpVersion::Http10),
             "HTTP/1.1" => Ok(HttpVersion::Http11),
             "HTTP/2" | "HTTP/2.0" => Ok(HttpVersion::Http20),
@@ -29,9 +35,26 @@ impl FromStr for HttpVersion {
     }
 }
 
+/// Versions are serialized as they appear in a request line, such as `"HTTP/1.1"`.
+impl Serialize for HttpVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Versions are deserialized from the forms a request line accepts, such as `"HTTP/1.1"` or
+/// `"HTTP/2"`.
+impl<'de> Deserialize<'de> for HttpVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let version = String::deserialize(deserializer)?;
+        version.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 impl fmt::Display for HttpVersion {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
+            HttpVersion::Http09 => write!(f, "HTTP/0.9"),
             HttpVersion::Http10 => write!(f, "HTTP/1.0"),
             HttpVersion::Http11 => write!(f, "HTTP/1.1"),
             HttpVersion::Http20 => write!(f, "HTTP/2"),
@@ -0,0 +1,88 @@
+//! Access logging, with one entry per answered request.
+
+use std::net::SocketAddr;
+use std::time::{Duration, SystemTime};
+
+use crate::parser::{HttpVersion, Method};
+use crate::server::date::{MONTHS, civil_from_days, seconds_since_epoch};
+
+/// The log target of access log entries, for filtering them apart from other server logs.
+pub const ACCESS_LOG_TARGET: &str = "microhttp_rs::access";
+
+/// The format of access log entries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessLogFormat {
+    /// The Common Log Format, followed by the time taken to answer in milliseconds:
+    /// `127.0.0.1 - - [10/Oct/2000:13:55:36 +0000] "GET /index.html HTTP/1.1" 200 2326 4`
+    Common,
+    /// A JSON object per entry, with the fields `time`, `remote_addr`, `method`, `path`,
+    /// `version`, `status`, `bytes` and `duration_ms`.
+    Json,
+}
+
+/// The fields of an access log entry.
+pub(crate) struct AccessLogEntry<'a> {
+    /// The address of the client, if known
+    pub remote_addr: Option<SocketAddr>,
+    /// The request method
+    pub method: &'a Method,
+    /// The request path
+    pub path: &'a str,
+    /// The request version
+    pub version: HttpVersion,
+    /// The response status code
+    pub status: u16,
+    /// The number of body bytes sent
+    pub bytes: usize,
+    /// The time taken to answer the request
+    pub duration: Duration,
+    /// The time the request was received
+    pub time: SystemTime,
+}
+
+impl AccessLogEntry<'_> {
+    /// Format the entry as a single log line.
+    pub(crate) fn format(&self, format: AccessLogFormat) -> String {
+        let duration_ms = self.duration.as_millis();
+        match format {
+            AccessLogFormat::Common => {
+                let remote_addr = self.remote_addr.map(|addr| addr.ip().to_string());
+                let bytes = if self.bytes == 0 { "-".to_string() } else { self.bytes.to_string() };
+                format!(
+                    "{addr} - - [{time}] \"{method} {path} {version}\" {status} {bytes} {duration_ms}",
+                    addr = remote_addr.as_deref().unwrap_or("-"),
+                    time = common_log_time(self.time),
+                    method = self.method,
+                    path = self.path,
+                    version = self.version,
+                    status = self.status,
+                )
+            }
+            AccessLogFormat::Json => serde_json::json!({
+                "time": common_log_time(self.time),
+                "remote_addr": self.remote_addr.map(|addr| addr.to_string()),
+                "method": self.method.to_string(),
+                "path": self.path,
+                "version": self.version.to_string(),
+                "status": self.status,
+                "bytes": self.bytes,
+                "duration_ms": duration_ms,
+            })
+            .to_string(),
+        }
+    }
+}
+
+/// Format a time as in the Common Log Format, such as `10/Oct/2000:13:55:36 +0000`.
+fn common_log_time(time: SystemTime) -> String {
+    let secs = seconds_since_epoch(time);
+    let (year, month, day) = civil_from_days((secs / 86400) as i64);
+    let secs_of_day = secs % 86400;
+    format!(
+        "{day:02}/{month}/{year}:{h:02}:{m:02}:{s:02} +0000",
+        month = MONTHS[month as usize - 1],
+        h = secs_of_day / 3600,
+        m = secs_of_day / 60 % 60,
+        s = secs_of_day % 60,
+    )
+}
@@ -0,0 +1,49 @@
+//! A ready-made HTTP Basic authentication middleware.
+
+use std::sync::Arc;
+
+use crate::parser::{AuthScheme, HttpRequest};
+use crate::server::handler::{HandlerFuture, Next};
+use crate::server::response::{HttpResponse, StatusCode};
+
+/// Build a middleware that lets through only requests with Basic credentials accepted by
+/// `verify`, which receives the user name and password.
+///
+/// Requests without an `Authorization` header, with another scheme or with rejected
+/// credentials are answered with `401 Unauthorized` and a `WWW-Authenticate: Basic realm="..."`
+/// challenge; accepted requests are passed on unchanged. Add the middleware with
+/// [`HttpServer::add_middleware`] or, to protect only some routes, [`Router::add_middleware`].
+///
+/// [`HttpServer::add_middleware`]: crate::server::HttpServer::add_middleware
+/// [`Router::add_middleware`]: crate::server::Router::add_middleware
+///
+/// # Examples
+///
+/// ```
+/// use microhttp_rs::{Router, basic_auth};
+///
+/// let mut admin = Router::new();
+/// admin.add_middleware(basic_auth("admin", |user, password| user == "admin" && password == "secret"));
+/// ```
+pub fn basic_auth<F>(realm: impl Into<String>, verify: F) -> impl Fn(HttpRequest, Next) -> HandlerFuture + Send + Sync + 'static
+where
+    F: Fn(&str, &str) -> bool + Send + Sync + 'static,
+{
+    let realm = realm.into();
+    let challenge: Arc<str> = format!("Basic realm=\"{}\"", realm.replace('\\', "\\\\").replace('"', "\\\"")).into();
+    move |req: HttpRequest, next: Next| -> HandlerFuture {
+        let authorized = matches!(
+            req.authorization(),
+            Some(AuthScheme::Basic { username, password }) if verify(&username, &password)
+        );
+        if authorized {
+            return next.run(req);
+        }
+
+        let response = HttpResponse::new(StatusCode::Unauthorized)
+            .with_header("WWW-Authenticate", challenge.to_string())
+            .with_content_type("text/plain")
+            .with_body_string("Unauthorized");
+        Box::pin(async move { Ok(response) })
+    }
+}
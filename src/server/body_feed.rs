@@ -0,0 +1,183 @@
+//! Request bodies read from the connection as the handler asks for them.
+
+use std::future::Future;
+use std::io;
+use std::time::Duration;
+
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite};
+use tokio::sync::{mpsc, oneshot};
+
+use crate::parser::{BodyReader, ChunkedDecoder, Error as ParserError, StreamedBody};
+use crate::server::http_server::send_continue;
+
+/// How the end of a streamed body is found.
+pub(crate) enum Framing {
+    /// The number of body bytes still to come, from `Content-Length`
+    Length(usize),
+    /// The decoder of a chunked body, whose trailer fields are dropped
+    Chunked(ChunkedDecoder),
+}
+
+impl Framing {
+    /// Check whether the whole body was read.
+    fn is_done(&self) -> bool {
+        match self {
+            Framing::Length(remaining) => *remaining == 0,
+            Framing::Chunked(decoder) => decoder.is_done(),
+        }
+    }
+
+    /// Take the next piece of body data from the start of `buf`.
+    ///
+    /// # Returns
+    ///
+    /// The data, None if more input is needed or the body is done, or an error if the chunked
+    /// framing is malformed
+    fn decode(&mut self, buf: &mut Vec<u8>) -> Result<Option<Vec<u8>>, ParserError> {
+        match self {
+            Framing::Length(remaining) => {
+                let n = (*remaining).min(buf.len());
+                if n == 0 {
+                    return Ok(None);
+                }
+                *remaining -= n;
+                Ok(Some(buf.drain(..n).collect()))
+            }
+            Framing::Chunked(decoder) => {
+                let mut data = Vec::new();
+                let consumed = decoder.decode(buf, &mut data)?;
+                buf.drain(..consumed);
+                decoder.take_trailers();
+                Ok((!data.is_empty()).then_some(data))
+            }
+        }
+    }
+}
+
+/// The connection's side of a streamed body, which reads it from the socket for its
+/// [`BodyReader`].
+pub(crate) struct BodyFeed {
+    start: oneshot::Receiver<()>,
+    sender: mpsc::Sender<io::Result<Vec<u8>>>,
+    framing: Framing,
+    expects_continue: bool,
+    read_timeout: Option<Duration>,
+}
+
+/// Create the reader of a streamed body and the feed that fills it.
+///
+/// # Arguments
+///
+/// * `expects_continue` - Whether the client waits for `100 Continue` before sending the body,
+///   which is sent when the reader is first polled
+/// * `read_timeout` - How long each read from the connection may wait for more of the body
+pub(crate) fn streamed_body(
+    framing: Framing,
+    expects_continue: bool,
+    read_timeout: Option<Duration>,
+) -> (StreamedBody, BodyFeed) {
+    let (start_tx, start) = oneshot::channel();
+    // A single chunk in flight keeps the connection from reading ahead of the handler
+    let (sender, receiver) = mpsc::channel(1);
+    let reader = BodyReader::streamed(start_tx, receiver);
+    let feed = BodyFeed {
+        start,
+        sender,
+        framing,
+        expects_continue,
+        read_timeout,
+    };
+    (StreamedBody::new(reader), feed)
+}
+
+impl BodyFeed {
+    /// Feed the body to its reader while `handler` runs.
+    ///
+    /// Nothing is read from the connection until the reader is first polled, and then only as
+    /// much as the reader takes. A read that gets no data within the read timeout fails the
+    /// reader with a `TimedOut` error. `buf` holds any bytes of the body that were read along with
+    /// the header section, and is left holding the bytes after the body.
+    ///
+    /// # Returns
+    ///
+    /// The output of the handler, and whether the whole body was read, which the connection
+    /// needs to serve another request
+    pub(crate) async fn run_alongside<F: Future>(
+        mut self,
+        socket: &mut (impl AsyncRead + AsyncWrite + Unpin),
+        buf: &mut Vec<u8>,
+        chunk: &mut [u8],
+        handler: F,
+    ) -> (F::Output, bool) {
+        tokio::pin!(handler);
+
+        // Wait for the handler to start reading; a handler that drops the reader never does
+        tokio::select! {
+            output = &mut handler => return (output, false),
+            started = &mut self.start => {
+                if started.is_err() {
+                    return (handler.await, false);
+                }
+            }
+        }
+
+        if self.expects_continue && buf.is_empty() {
+            if let Err(e) = send_continue(socket).await {
+                let _ = self.sender.send(Err(e)).await;
+                return (handler.await, false);
+            }
+        }
+
+        loop {
+            // Wait for room in the channel, so no more is read than the reader takes
+            let permit = tokio::select! {
+                output = &mut handler => return (output, false),
+                permit = self.sender.reserve() => match permit {
+                    Ok(permit) => permit,
+                    Err(_) => return (handler.await, false),
+                },
+            };
+
+            let item = loop {
+                match self.framing.decode(buf) {
+                    Ok(Some(data)) => break Ok(data),
+                    Ok(None) if self.framing.is_done() => break Ok(Vec::new()),
+                    Ok(None) => {}
+                    Err(e) => break Err(io::Error::new(io::ErrorKind::InvalidData, e)),
+                }
+                let read = async {
+                    match self.read_timeout {
+                        Some(read_timeout) => tokio::time::timeout(read_timeout, socket.read(chunk))
+                            .await
+                            .unwrap_or_else(|_| Err(io::Error::new(io::ErrorKind::TimedOut, "timed out waiting for the request body"))),
+                        None => socket.read(chunk).await,
+                    }
+                };
+                let read = tokio::select! {
+                    output = &mut handler => return (output, false),
+                    read = read => read,
+                };
+                match read {
+                    Ok(0) => {
+                        break Err(io::Error::new(
+                            io::ErrorKind::UnexpectedEof,
+                            "the connection closed before the end of the request body",
+                        ));
+                    }
+                    Ok(n) => buf.extend_from_slice(&chunk[..n]),
+                    Err(e) => break Err(e),
+                }
+            };
+
+            let status = match &item {
+                Ok(data) if data.is_empty() => Some(true),
+                Ok(_) => None,
+                Err(_) => Some(false),
+            };
+            permit.send(item);
+            if let Some(complete) = status {
+                return (handler.await, complete);
+            }
+        }
+    }
+}
@@ -0,0 +1,73 @@
+//! Gzip compression of response bodies, with the encoder of the flate2 crate.
+
+use std::io::Write;
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+
+use crate::parser::{encoding_quality, media_type, parse_quality_list};
+use crate::server::response::{HttpResponse, StatusCode};
+
+/// Content types that are already compressed and gain nothing from gzip.
+const COMPRESSED_TYPES: &[&str] = &[
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "application/zip",
+    "application/gzip",
+    "application/pdf",
+    "font/woff",
+    "font/woff2",
+];
+
+/// Compress a response body with gzip if the request's `Accept-Encoding` header allows it
+/// and compressing is worthwhile.
+///
+/// Streamed responses, partial responses (206), responses without a body (204 and 304),
+/// bodies smaller than `threshold`, already-encoded bodies and already-compressed content
+/// types are left as they are. Compressible responses get `Vary: Accept-Encoding` whether or not the client
+/// accepts gzip, so caches keep the two variants apart.
+pub(crate) fn compress_response(
+    response: HttpResponse,
+    accept_encoding: Option<&str>,
+    threshold: usize,
+) -> HttpResponse {
+    if response.stream.is_some()
+        || matches!(response.status, StatusCode::NoContent | StatusCode::PartialContent | StatusCode::NotModified)
+        || response.body.len() < threshold
+        || !response.get_all_headers("Content-Encoding").is_empty()
+    {
+        return response;
+    }
+
+    let is_compressed_type = response.get_all_headers("Content-Type").first().is_some_and(|content_type| {
+        let media_type = media_type(content_type);
+        COMPRESSED_TYPES.contains(&media_type.as_str())
+            || media_type.starts_with("video/")
+            || media_type.starts_with("audio/")
+    });
+    if is_compressed_type {
+        return response;
+    }
+
+    let response = response.with_added_header("Vary", "Accept-Encoding");
+    if !accept_encoding.is_some_and(accepts_gzip) {
+        return response;
+    }
+
+    let body = gzip(&response.body);
+    response.with_header("Content-Encoding", "gzip").with_body_bytes(body)
+}
+
+/// Check whether an `Accept-Encoding` header value allows gzip.
+fn accepts_gzip(accept_encoding: &str) -> bool {
+    encoding_quality(&parse_quality_list([accept_encoding]), "gzip") > 0.0
+}
+
+/// Compress data into the gzip format.
+pub(crate) fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(data).expect("writing to a Vec never fails");
+    encoder.finish().expect("writing to a Vec never fails")
+}
@@ -0,0 +1,57 @@
+//! Conditional GET and HEAD requests, answered with `304 Not Modified` when the client's
+//! cached copy is still current (RFC 9110 section 13).
+
+use crate::server::date::{parse_http_date, seconds_since_epoch};
+use crate::server::response::{HttpResponse, StatusCode};
+
+/// Replace a `200 OK` response with `304 Not Modified` if the request's validators show the
+/// client already has it.
+///
+/// `If-None-Match` is compared with the response's `ETag` using the weak comparison, and
+/// takes precedence over `If-Modified-Since`, which is compared with `Last-Modified`. The
+/// `304` response keeps the headers of the original one, but not its body or
+/// `Content-Length`. Other responses, including streamed ones, are left as they are.
+pub(crate) fn not_modified(
+    response: HttpResponse,
+    if_none_match: Option<&str>,
+    if_modified_since: Option<&str>,
+) -> HttpResponse {
+    if response.status_code() != 200 || response.stream.is_some() {
+        return response;
+    }
+
+    let is_fresh = match if_none_match {
+        Some(if_none_match) => response
+            .get_all_headers("ETag")
+            .first()
+            .is_some_and(|etag| etag_matches(if_none_match, etag)),
+        None => {
+            let last_modified = response.get_all_headers("Last-Modified").first().and_then(|value| parse_http_date(value));
+            match (last_modified, if_modified_since.and_then(parse_http_date)) {
+                (Some(last_modified), Some(since)) => seconds_since_epoch(last_modified) <= seconds_since_epoch(since),
+                _ => false,
+            }
+        }
+    };
+    if !is_fresh {
+        return response;
+    }
+
+    let mut response = response;
+    response.status = StatusCode::NotModified;
+    response.custom_status = None;
+    response.body.clear();
+    response.headers.remove("Content-Length");
+    response
+}
+
+/// Check whether an `If-None-Match` header lists an entity tag, ignoring weakness.
+fn etag_matches(if_none_match: &str, etag: &str) -> bool {
+    let if_none_match = if_none_match.trim();
+    if if_none_match == "*" {
+        return true;
+    }
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+    let etag = opaque(etag);
+    if_none_match.split(',').any(|candidate| opaque(candidate) == etag)
+}
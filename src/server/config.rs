@@ -1,16 +1,100 @@
 //! Server configuration.
 
 use std::net::SocketAddr;
+#[cfg(feature = "tls")]
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::server::access_log::AccessLogFormat;
+use crate::server::cors::CorsConfig;
 
 /// HTTP server configuration.
+///
+/// Build one with [`ServerConfig::builder`], or with a struct literal that takes the remaining
+/// fields from [`ServerConfig::default`].
 #[derive(Clone)]
 pub struct ServerConfig {
     /// The address to bind to.
     pub addr: SocketAddr,
     /// The maximum number of concurrent connections.
     pub max_connections: usize,
+    /// How long a new connection may wait for a slot once `max_connections` are open, or None
+    /// to reject it right away, the default. Connections still without a slot are answered
+    /// with `503 Service Unavailable`. Other connections are still accepted while one waits,
+    /// and wait for a slot in turn.
+    pub connection_wait: Option<Duration>,
+    /// Whether `TCP_NODELAY` is set on accepted connections, sending small responses right
+    /// away instead of waiting to coalesce them with later writes (Nagle's algorithm). On by
+    /// default, since responses are written in one go.
+    pub tcp_nodelay: bool,
+    /// How long an accepted connection may sit idle before TCP keepalive probes are sent to
+    /// detect a vanished peer, or None to leave keepalive off, the default.
+    pub tcp_keepalive: Option<Duration>,
     /// The read buffer size.
     pub read_buffer_size: usize,
+    /// The maximum size of a request body in bytes.
+    pub max_body_size: usize,
+    /// The maximum length of the request line in bytes.
+    pub max_request_line_size: usize,
+    /// The maximum size of the header section in bytes, including the request line.
+    pub max_header_size: usize,
+    /// The maximum number of header lines in a request, including chunked trailers.
+    pub max_headers: usize,
+    /// Whether requests must use `\r\n` line endings, as in
+    /// [`ParseOptions::strict_line_endings`](crate::parser::ParseOptions::strict_line_endings).
+    /// Requests with a bare `\r` or `\n` are answered with `400 Bad Request`.
+    pub strict_line_endings: bool,
+    /// Whether request paths are normalized before routing, resolving repeated slashes and `.`
+    /// and `..` segments as [`HttpRequest::normalized_path`](crate::parser::HttpRequest::normalized_path)
+    /// does. Requests whose path climbs above the root are answered with `400 Bad Request`.
+    pub normalize_paths: bool,
+    /// Whether OPTIONS requests for paths without an explicit OPTIONS route are answered
+    /// automatically with `204 No Content` and an `Allow` header.
+    pub auto_options: bool,
+    /// How long a client may take to send a complete request, or None or zero for no limit.
+    /// The body of a streaming route is read by its handler, so this limits each read of it
+    /// instead.
+    pub request_timeout: Option<Duration>,
+    /// How long a handler, including its middleware, may take to produce a response, or None
+    /// for no limit, the default. Requests whose handler takes longer are answered with
+    /// `504 Gateway Timeout`; the clock starts anew for every request.
+    pub handler_timeout: Option<Duration>,
+    /// How long a keep-alive connection may stay idle between requests before it is closed.
+    pub keep_alive_timeout: Duration,
+    /// How long shutdown waits for open connections to finish before aborting them.
+    pub shutdown_timeout: Duration,
+    /// The CORS configuration, or None to add no CORS headers.
+    pub cors: Option<CorsConfig>,
+    /// Whether `200 OK` responses to GET and HEAD requests are replaced with
+    /// `304 Not Modified` when the request's `If-None-Match` or `If-Modified-Since` header
+    /// matches their `ETag` or `Last-Modified` header. Off by default, since it changes what
+    /// existing handlers answer.
+    pub conditional_requests: bool,
+    /// Whether `200 OK` responses to GET requests with a `Range` header are answered with
+    /// `206 Partial Content` and the requested bytes. Eligible responses get
+    /// `Accept-Ranges: bytes`; handlers can opt out by setting `Accept-Ranges: none`. Off by
+    /// default, since it changes what existing handlers answer.
+    pub range_requests: bool,
+    /// Whether response bodies are gzip-compressed for clients that send
+    /// `Accept-Encoding: gzip`.
+    #[cfg(feature = "compression")]
+    pub compress_responses: bool,
+    /// The minimum body size in bytes for a response to be compressed.
+    #[cfg(feature = "compression")]
+    pub compression_threshold: usize,
+    /// The format of the access log entry written for every answered request, or None to
+    /// write no access log. Entries are logged at info level with the target
+    /// [`ACCESS_LOG_TARGET`](crate::server::ACCESS_LOG_TARGET).
+    pub access_log: Option<AccessLogFormat>,
+    /// Whether responses get a `Date` header with the current time, unless the handler set
+    /// one. Turn it off for byte-for-byte reproducible responses in tests.
+    pub send_date: bool,
+    /// The value of the `Server` header added to responses that don't set one, or None to
+    /// add no `Server` header. Defaults to `microhttp-rs`.
+    pub server_header: Option<String>,
+    /// The TLS configuration, or None to accept plain HTTP connections.
+    #[cfg(feature = "tls")]
+    pub tls: Option<Arc<tokio_rustls::rustls::ServerConfig>>,
 }
 
 impl Default for ServerConfig {
@@ -18,7 +102,242 @@ impl Default for ServerConfig {
         Self {
             addr: "127.0.0.1:8080".parse().unwrap(),
             max_connections: 1024,
+            connection_wait: None,
+            tcp_nodelay: true,
+            tcp_keepalive: None,
             read_buffer_size: 8192,
+            max_body_size: 2 * 1024 * 1024,
+            max_request_line_size: 8 * 1024,
+            max_header_size: 32 * 1024,
+            max_headers: 100,
+            strict_line_endings: false,
+            normalize_paths: false,
+            auto_options: true,
+            request_timeout: Some(Duration::from_secs(30)),
+            handler_timeout: None,
+            keep_alive_timeout: Duration::from_secs(5),
+            shutdown_timeout: Duration::from_secs(30),
+            cors: None,
+            conditional_requests: false,
+            range_requests: false,
+            #[cfg(feature = "compression")]
+            compress_responses: false,
+            #[cfg(feature = "compression")]
+            compression_threshold: 1024,
+            access_log: None,
+            send_date: true,
+            server_header: Some("microhttp-rs".to_string()),
+            #[cfg(feature = "tls")]
+            tls: None,
         }
     }
-}
\ No newline at end of file
+}
+
+impl ServerConfig {
+    /// Start building a configuration from the defaults.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    /// use microhttp_rs::ServerConfig;
+    ///
+    /// let config = ServerConfig::builder()
+    ///     .addr("127.0.0.1:3000".parse().unwrap())
+    ///     .max_connections(100)
+    ///     .handler_timeout(Duration::from_secs(10))
+    ///     .build();
+    /// assert_eq!(config.max_connections, 100);
+    /// assert_eq!(config.handler_timeout, Some(Duration::from_secs(10)));
+    /// ```
+    pub fn builder() -> ServerConfigBuilder {
+        ServerConfigBuilder::default()
+    }
+
+    /// Get the request timeout to apply, treating zero as no limit.
+    pub(crate) fn request_deadline(&self) -> Option<Duration> {
+        self.request_timeout.filter(|timeout| !timeout.is_zero())
+    }
+}
+
+/// A builder for [`ServerConfig`], created with [`ServerConfig::builder`].
+///
+/// Every option starts at its default, so only the ones that differ need setting. Options
+/// that can be turned off, such as timeouts, take either a value or None.
+#[derive(Clone, Default)]
+pub struct ServerConfigBuilder {
+    config: ServerConfig,
+}
+
+impl ServerConfigBuilder {
+    /// Set the address to bind to.
+    pub fn addr(mut self, addr: SocketAddr) -> Self {
+        self.config.addr = addr;
+        self
+    }
+
+    /// Set the maximum number of concurrent connections.
+    pub fn max_connections(mut self, max_connections: usize) -> Self {
+        self.config.max_connections = max_connections;
+        self
+    }
+
+    /// Set how long a new connection may wait for a slot once `max_connections` are open. Pass None to turn it off.
+    pub fn connection_wait(mut self, connection_wait: impl Into<Option<Duration>>) -> Self {
+        self.config.connection_wait = connection_wait.into();
+        self
+    }
+
+    /// Set whether `TCP_NODELAY` is set on accepted connections.
+    pub fn tcp_nodelay(mut self, tcp_nodelay: bool) -> Self {
+        self.config.tcp_nodelay = tcp_nodelay;
+        self
+    }
+
+    /// Set how long an idle connection waits before TCP keepalive probes are sent. Pass None to turn it off.
+    pub fn tcp_keepalive(mut self, tcp_keepalive: impl Into<Option<Duration>>) -> Self {
+        self.config.tcp_keepalive = tcp_keepalive.into();
+        self
+    }
+
+    /// Set the read buffer size.
+    pub fn read_buffer_size(mut self, read_buffer_size: usize) -> Self {
+        self.config.read_buffer_size = read_buffer_size;
+        self
+    }
+
+    /// Set the maximum size of a request body in bytes.
+    pub fn max_body_size(mut self, max_body_size: usize) -> Self {
+        self.config.max_body_size = max_body_size;
+        self
+    }
+
+    /// Set the maximum length of the request line in bytes.
+    pub fn max_request_line_size(mut self, max_request_line_size: usize) -> Self {
+        self.config.max_request_line_size = max_request_line_size;
+        self
+    }
+
+    /// Set the maximum size of the header section in bytes.
+    pub fn max_header_size(mut self, max_header_size: usize) -> Self {
+        self.config.max_header_size = max_header_size;
+        self
+    }
+
+    /// Set the maximum number of header lines in a request.
+    pub fn max_headers(mut self, max_headers: usize) -> Self {
+        self.config.max_headers = max_headers;
+        self
+    }
+
+    /// Set whether requests must use `\r\n` line endings.
+    pub fn strict_line_endings(mut self, strict_line_endings: bool) -> Self {
+        self.config.strict_line_endings = strict_line_endings;
+        self
+    }
+
+    /// Set whether request paths are normalized before routing.
+    pub fn normalize_paths(mut self, normalize_paths: bool) -> Self {
+        self.config.normalize_paths = normalize_paths;
+        self
+    }
+
+    /// Set whether OPTIONS requests are answered automatically.
+    pub fn auto_options(mut self, auto_options: bool) -> Self {
+        self.config.auto_options = auto_options;
+        self
+    }
+
+    /// Set how long a client may take to send a complete request. Pass None or zero to turn it
+    /// off.
+    pub fn request_timeout(mut self, request_timeout: impl Into<Option<Duration>>) -> Self {
+        self.config.request_timeout = request_timeout.into();
+        self
+    }
+
+    /// Set how long a handler may take to produce a response. Pass None to turn it off.
+    pub fn handler_timeout(mut self, handler_timeout: impl Into<Option<Duration>>) -> Self {
+        self.config.handler_timeout = handler_timeout.into();
+        self
+    }
+
+    /// Set how long a keep-alive connection may stay idle between requests.
+    pub fn keep_alive_timeout(mut self, keep_alive_timeout: Duration) -> Self {
+        self.config.keep_alive_timeout = keep_alive_timeout;
+        self
+    }
+
+    /// Set how long shutdown waits for open connections to finish.
+    pub fn shutdown_timeout(mut self, shutdown_timeout: Duration) -> Self {
+        self.config.shutdown_timeout = shutdown_timeout;
+        self
+    }
+
+    /// Set the CORS configuration. Pass None to turn it off.
+    pub fn cors(mut self, cors: impl Into<Option<CorsConfig>>) -> Self {
+        self.config.cors = cors.into();
+        self
+    }
+
+    /// Set whether conditional GET and HEAD requests are answered with `304 Not Modified`.
+    pub fn conditional_requests(mut self, conditional_requests: bool) -> Self {
+        self.config.conditional_requests = conditional_requests;
+        self
+    }
+
+    /// Set whether GET requests with a `Range` header are answered with `206 Partial Content`.
+    pub fn range_requests(mut self, range_requests: bool) -> Self {
+        self.config.range_requests = range_requests;
+        self
+    }
+
+    #[cfg(feature = "compression")]
+    /// Set whether response bodies are gzip-compressed.
+    pub fn compress_responses(mut self, compress_responses: bool) -> Self {
+        self.config.compress_responses = compress_responses;
+        self
+    }
+
+    #[cfg(feature = "compression")]
+    /// Set the minimum body size in bytes for a response to be compressed.
+    pub fn compression_threshold(mut self, compression_threshold: usize) -> Self {
+        self.config.compression_threshold = compression_threshold;
+        self
+    }
+
+    /// Set the format of the access log. Pass None to turn it off.
+    pub fn access_log(mut self, access_log: impl Into<Option<AccessLogFormat>>) -> Self {
+        self.config.access_log = access_log.into();
+        self
+    }
+
+    /// Set whether responses get a `Date` header.
+    pub fn send_date(mut self, send_date: bool) -> Self {
+        self.config.send_date = send_date;
+        self
+    }
+
+    /// Set the value of the `Server` header.
+    pub fn server_header(mut self, server_header: impl Into<String>) -> Self {
+        self.config.server_header = Some(server_header.into());
+        self
+    }
+
+    /// Send no `Server` header.
+    pub fn no_server_header(mut self) -> Self {
+        self.config.server_header = None;
+        self
+    }
+
+    #[cfg(feature = "tls")]
+    /// Set the TLS configuration. Pass None to turn it off.
+    pub fn tls(mut self, tls: impl Into<Option<Arc<tokio_rustls::rustls::ServerConfig>>>) -> Self {
+        self.config.tls = tls.into();
+        self
+    }
+
+    /// Build the configuration.
+    pub fn build(self) -> ServerConfig {
+        self.config
+    }
+}
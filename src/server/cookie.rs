@@ -0,0 +1,136 @@
+//! HTTP cookies for the `Set-Cookie` response header.
+
+use std::fmt;
+
+/// The `SameSite` attribute of a cookie, controlling whether it is sent with cross-site requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SameSite {
+    /// Strict: The cookie is only sent with same-site requests.
+    Strict,
+    /// Lax: The cookie is also sent when navigating to the site from another site.
+    Lax,
+    /// None: The cookie is sent with all requests; browsers require `Secure` as well.
+    None,
+}
+
+impl fmt::Display for SameSite {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SameSite::Strict => write!(f, "Strict"),
+            SameSite::Lax => write!(f, "Lax"),
+            SameSite::None => write!(f, "None"),
+        }
+    }
+}
+
+/// A cookie to be sent in a `Set-Cookie` response header.
+///
+/// # Examples
+///
+/// ```
+/// use microhttp_rs::{Cookie, SameSite};
+///
+/// let cookie = Cookie::new("session", "abc123")
+///     .with_path("/")
+///     .with_max_age(3600)
+///     .with_http_only(true)
+///     .with_same_site(SameSite::Lax);
+///
+/// assert_eq!(cookie.to_string(), "session=abc123; Path=/; Max-Age=3600; HttpOnly; SameSite=Lax");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cookie {
+    /// The cookie name
+    pub name: String,
+    /// The cookie value
+    pub value: String,
+    /// The path the cookie is sent for
+    pub path: Option<String>,
+    /// The domain the cookie is sent to
+    pub domain: Option<String>,
+    /// The number of seconds until the cookie expires
+    pub max_age: Option<i64>,
+    /// Whether the cookie is only sent over HTTPS
+    pub secure: bool,
+    /// Whether the cookie is hidden from JavaScript
+    pub http_only: bool,
+    /// Whether the cookie is sent with cross-site requests
+    pub same_site: Option<SameSite>,
+}
+
+impl Cookie {
+    /// Create a new cookie with the given name and value and no attributes.
+    pub fn new(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            path: None,
+            domain: None,
+            max_age: None,
+            secure: false,
+            http_only: false,
+            same_site: None,
+        }
+    }
+
+    /// Set the `Path` attribute.
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        self.path = Some(path.into());
+        self
+    }
+
+    /// Set the `Domain` attribute.
+    pub fn with_domain(mut self, domain: impl Into<String>) -> Self {
+        self.domain = Some(domain.into());
+        self
+    }
+
+    /// Set the `Max-Age` attribute, in seconds. A value of zero or less deletes the cookie.
+    pub fn with_max_age(mut self, seconds: i64) -> Self {
+        self.max_age = Some(seconds);
+        self
+    }
+
+    /// Set the `Secure` attribute.
+    pub fn with_secure(mut self, secure: bool) -> Self {
+        self.secure = secure;
+        self
+    }
+
+    /// Set the `HttpOnly` attribute.
+    pub fn with_http_only(mut self, http_only: bool) -> Self {
+        self.http_only = http_only;
+        self
+    }
+
+    /// Set the `SameSite` attribute.
+    pub fn with_same_site(mut self, same_site: SameSite) -> Self {
+        self.same_site = Some(same_site);
+        self
+    }
+}
+
+impl fmt::Display for Cookie {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.name, self.value)?;
+        if let Some(path) = &self.path {
+            write!(f, "; Path={path}")?;
+        }
+        if let Some(domain) = &self.domain {
+            write!(f, "; Domain={domain}")?;
+        }
+        if let Some(max_age) = self.max_age {
+            write!(f, "; Max-Age={max_age}")?;
+        }
+        if self.secure {
+            write!(f, "; Secure")?;
+        }
+        if self.http_only {
+            write!(f, "; HttpOnly")?;
+        }
+        if let Some(same_site) = self.same_site {
+            write!(f, "; SameSite={same_site}")?;
+        }
+        Ok(())
+    }
+}
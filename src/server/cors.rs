@@ -0,0 +1,163 @@
+//! Cross-Origin Resource Sharing (CORS) configuration.
+
+use crate::parser::{HttpRequest, Method};
+use crate::server::error::Error;
+
+/// CORS configuration applied to every route of a server.
+///
+/// When attached to [`ServerConfig::cors`](crate::ServerConfig::cors), preflight `OPTIONS`
+/// requests for paths with a route are answered automatically, and responses to requests
+/// from an allowed origin carry the matching `Access-Control-Allow-*` headers.
+///
+/// Browsers reject credentialed responses with `Access-Control-Allow-Origin: *`, so a
+/// configuration that allows any origin together with credentials is invalid and makes
+/// [`HttpServer::start`](crate::HttpServer::start) fail.
+///
+/// # Examples
+///
+/// ```
+/// use microhttp_rs::{CorsConfig, Method};
+///
+/// let cors = CorsConfig::new()
+///     .with_allowed_origin("https://example.com")
+///     .with_allowed_methods(vec![Method::GET, Method::POST])
+///     .with_allowed_header("Content-Type")
+///     .with_credentials(true)
+///     .with_max_age(600);
+///
+/// assert!(cors.validate().is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CorsConfig {
+    /// The origins allowed to make requests, or `*` to allow any origin
+    pub allowed_origins: Vec<String>,
+    /// The methods allowed in cross-origin requests
+    pub allowed_methods: Vec<Method>,
+    /// The request headers allowed in cross-origin requests
+    pub allowed_headers: Vec<String>,
+    /// Whether cross-origin requests may include credentials such as cookies
+    pub allow_credentials: bool,
+    /// The number of seconds browsers may cache a preflight response
+    pub max_age: Option<u64>,
+}
+
+impl Default for CorsConfig {
+    fn default() -> Self {
+        Self {
+            allowed_origins: Vec::new(),
+            allowed_methods: vec![
+                Method::GET,
+                Method::HEAD,
+                Method::POST,
+                Method::PUT,
+                Method::DELETE,
+                Method::PATCH,
+            ],
+            allowed_headers: Vec::new(),
+            allow_credentials: false,
+            max_age: None,
+        }
+    }
+}
+
+impl CorsConfig {
+    /// Create a CORS configuration that allows no origins and the common methods.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a CORS configuration that allows any origin.
+    pub fn permissive() -> Self {
+        Self::new().with_allowed_origin("*")
+    }
+
+    /// Allow requests from the given origin, or from any origin with `*`.
+    pub fn with_allowed_origin(mut self, origin: impl Into<String>) -> Self {
+        self.allowed_origins.push(origin.into());
+        self
+    }
+
+    /// Set the methods allowed in cross-origin requests.
+    pub fn with_allowed_methods(mut self, methods: Vec<Method>) -> Self {
+        self.allowed_methods = methods;
+        self
+    }
+
+    /// Allow the given request header in cross-origin requests.
+    pub fn with_allowed_header(mut self, header: impl Into<String>) -> Self {
+        self.allowed_headers.push(header.into());
+        self
+    }
+
+    /// Set whether cross-origin requests may include credentials.
+    pub fn with_credentials(mut self, allow_credentials: bool) -> Self {
+        self.allow_credentials = allow_credentials;
+        self
+    }
+
+    /// Set how many seconds browsers may cache a preflight response.
+    pub fn with_max_age(mut self, seconds: u64) -> Self {
+        self.max_age = Some(seconds);
+        self
+    }
+
+    /// Check that the configuration doesn't combine a wildcard origin with credentials.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.allow_credentials && self.allows_any_origin() {
+            return Err(Error::InvalidConfig(
+                "CORS cannot allow credentials for the wildcard origin \"*\"".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check whether a request is a CORS preflight request.
+    pub(crate) fn is_preflight(request: &HttpRequest) -> bool {
+        request.method == Method::OPTIONS
+            && request.has_header("Origin")
+            && request.has_header("Access-Control-Request-Method")
+    }
+
+    /// The headers to add to the response to a cross-origin request, or none if the request
+    /// has no `Origin` header or its origin isn't allowed.
+    pub(crate) fn response_headers(&self, request: &HttpRequest) -> Vec<(String, String)> {
+        let Some(origin) = request.get_header("Origin") else {
+            return Vec::new();
+        };
+
+        let mut headers = Vec::new();
+        if self.allows_any_origin() {
+            // Never pair the wildcard with credentials, even if validation was skipped
+            if self.allow_credentials {
+                return Vec::new();
+            }
+            headers.push(("Access-Control-Allow-Origin".to_string(), "*".to_string()));
+        } else if self.allowed_origins.iter().any(|o| o == origin) {
+            headers.push(("Access-Control-Allow-Origin".to_string(), origin.clone()));
+            headers.push(("Vary".to_string(), "Origin".to_string()));
+        } else {
+            return Vec::new();
+        }
+
+        if self.allow_credentials {
+            headers.push(("Access-Control-Allow-Credentials".to_string(), "true".to_string()));
+        }
+
+        if Self::is_preflight(request) {
+            let methods: Vec<String> = self.allowed_methods.iter().map(|m| m.to_string()).collect();
+            headers.push(("Access-Control-Allow-Methods".to_string(), methods.join(", ")));
+            if !self.allowed_headers.is_empty() {
+                headers.push(("Access-Control-Allow-Headers".to_string(), self.allowed_headers.join(", ")));
+            }
+            if let Some(max_age) = self.max_age {
+                headers.push(("Access-Control-Max-Age".to_string(), max_age.to_string()));
+            }
+        }
+
+        headers
+    }
+
+    fn allows_any_origin(&self) -> bool {
+        self.allowed_origins.iter().any(|o| o == "*")
+    }
+}
@@ -0,0 +1,84 @@
+//! Formatting and parsing of times for HTTP date headers and the access log.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The abbreviated English month names, from January.
+pub(crate) const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+/// Format a time as an IMF-fixdate, such as `Sun, 06 Nov 1994 08:49:37 GMT` (RFC 9110 section 5.6.7).
+pub(crate) fn http_date(time: SystemTime) -> String {
+    const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+
+    let secs = seconds_since_epoch(time);
+    let days = secs / 86400;
+    let (year, month, day) = civil_from_days(days as i64);
+    let secs_of_day = secs % 86400;
+    format!(
+        "{weekday}, {day:02} {month} {year} {h:02}:{m:02}:{s:02} GMT",
+        // 1970-01-01 was a Thursday
+        weekday = WEEKDAYS[(days % 7) as usize],
+        month = MONTHS[month as usize - 1],
+        h = secs_of_day / 3600,
+        m = secs_of_day / 60 % 60,
+        s = secs_of_day % 60,
+    )
+}
+
+/// Parse an IMF-fixdate, such as `Sun, 06 Nov 1994 08:49:37 GMT`.
+///
+/// The obsolete RFC 850 and asctime formats are not accepted, so headers using them are treated
+/// as invalid and ignored.
+pub(crate) fn parse_http_date(value: &str) -> Option<SystemTime> {
+    let (_weekday, rest) = value.trim().split_once(", ")?;
+    let parts: Vec<&str> = rest.split(' ').collect();
+    let [day, month, year, time, "GMT"] = parts[..] else {
+        return None;
+    };
+    let [h, m, s] = <[&str; 3]>::try_from(time.split(':').collect::<Vec<_>>()).ok()?;
+    if day.len() != 2 || year.len() != 4 || [h, m, s].iter().any(|part| part.len() != 2) {
+        return None;
+    }
+
+    let day: u32 = day.parse().ok()?;
+    let month = MONTHS.iter().position(|name| *name == month)? as u32 + 1;
+    let year: i64 = year.parse().ok()?;
+    let (h, m, s): (u64, u64, u64) = (h.parse().ok()?, m.parse().ok()?, s.parse().ok()?);
+    if !(1..=31).contains(&day) || h > 23 || m > 59 || s > 60 {
+        return None;
+    }
+
+    let days = u64::try_from(days_from_civil(year, month, day)).ok()?;
+    Some(UNIX_EPOCH + Duration::from_secs(days * 86400 + h * 3600 + m * 60 + s))
+}
+
+/// Get the whole seconds since the Unix epoch, or 0 for earlier times.
+pub(crate) fn seconds_since_epoch(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0)
+}
+
+/// Convert a (year, month, day) date into a number of days since 1970-01-01.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    // The inverse of civil_from_days, with years starting on March 1st
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let mp = i64::from((month + 9) % 12);
+    let day_of_year = (153 * mp + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// Convert a number of days since 1970-01-01 into a (year, month, day) date.
+pub(crate) fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    // Shift the epoch to 0000-03-01 so leap days fall at the end of the year
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -23,6 +23,47 @@ pub enum Error {
     #[error("Method {0} not allowed for path: {1}")]
     MethodNotAllowed(Method, String),
 
+    /// The client didn't send a complete request within the configured timeout.
+    #[error("Timed out waiting for the request")]
+    RequestTimeout,
+
+    /// The request body is larger than the configured maximum.
+    #[error("Request body exceeds the maximum size of {0} bytes")]
+    PayloadTooLarge(usize),
+
+    /// The client closed the connection before the response was complete.
+    #[error("The client disconnected")]
+    ClientDisconnected,
+
+    /// The server configuration is invalid.
+    #[error("Invalid server configuration: {0}")]
+    InvalidConfig(String),
+
+    /// The request line is longer than the configured maximum.
+    #[error("Request line exceeds the maximum size of {0} bytes")]
+    RequestLineTooLong(usize),
+
+    /// The request header section is larger than the configured maximum.
+    #[error("Request headers exceed the maximum size of {0} bytes")]
+    HeadersTooLarge(usize),
+
+    /// A response header has an invalid name or a value containing control characters.
+    #[error("Invalid header: {0}")]
+    InvalidHeader(String),
+
+    /// A handler or middleware panicked while handling the request.
+    #[error("Handler panicked: {0}")]
+    HandlerPanic(String),
+
+    /// A handler, with its middleware, didn't produce a response within the configured timeout.
+    #[error("Handler timed out after {0:?}")]
+    HandlerTimeout(std::time::Duration),
+
+    /// A WebSocket client broke the protocol, or a message couldn't be sent.
+    #[cfg(feature = "websocket")]
+    #[error("WebSocket error: {0}")]
+    WebSocket(String),
+
     /// Internal server error.
     #[error("Internal server error: {0}")]
     InternalError(String),
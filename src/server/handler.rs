@@ -1,11 +1,12 @@
 //! HTTP request handlers and routing.
 
+use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 
 use crate::parser::{HttpRequest, Method};
-use crate::server::{HttpResponse, Error};
+use crate::server::{HttpResponse, Error, StatusCode};
 
 /// Type alias for a boxed future that returns a Result<HttpResponse, Error>.
 pub type HandlerFuture = Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>>;
@@ -13,12 +14,167 @@ pub type HandlerFuture = Pin<Box<dyn Future<Output = Result<HttpResponse, Error>
 /// Type alias for a handler function that takes an HttpRequest and returns a HandlerFuture.
 pub type HandlerFn = Arc<dyn Fn(HttpRequest) -> HandlerFuture + Send + Sync>;
 
+/// Type alias for an error handler that builds the response for a failed request from its status
+/// code and error.
+pub type ErrorHandlerFn = Arc<dyn Fn(StatusCode, &Error) -> HttpResponse + Send + Sync>;
+
+/// Type alias for a middleware function that takes an HttpRequest and the next layer, and returns a HandlerFuture.
+pub type MiddlewareFn = Arc<dyn Fn(HttpRequest, Next) -> HandlerFuture + Send + Sync>;
+
+/// The remaining layers of middleware and the route handler for a request.
+///
+/// A middleware calls [`Next::run`] to pass the request on, or returns a response without
+/// calling it to short-circuit the chain.
+#[derive(Clone)]
+pub struct Next {
+    /// The registered middleware.
+    middleware: Arc<Vec<MiddlewareFn>>,
+    /// The index of the next middleware to run.
+    index: usize,
+    /// The handler of the matched route.
+    handler: HandlerFn,
+}
+
+impl Next {
+    /// Create a chain that runs the given middleware in order before the handler.
+    pub(crate) fn new(middleware: Arc<Vec<MiddlewareFn>>, handler: HandlerFn) -> Self {
+        Self {
+            middleware,
+            index: 0,
+            handler,
+        }
+    }
+
+    /// Run the next middleware, or the route handler once all middleware have run.
+    pub fn run(self, request: HttpRequest) -> HandlerFuture {
+        match self.middleware.get(self.index).cloned() {
+            Some(middleware) => {
+                let next = Self {
+                    index: self.index + 1,
+                    ..self
+                };
+                middleware(request, next)
+            }
+            None => (self.handler)(request),
+        }
+    }
+}
+
+/// A segment of a route path pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    /// A segment that must match exactly, such as `users` in `/users/:id`.
+    Static(String),
+    /// A named parameter that matches any single segment, such as `:id` in `/users/:id`.
+    Param(String),
+    /// A named catch-all that matches the rest of the path, such as `*path` in `/static/*path`.
+    Wildcard(String),
+}
+
 /// Represents a route in the HTTP server.
 pub struct Route {
     /// The path to match.
     pub path: String,
+    /// The parsed segments of the path pattern.
+    pub segments: Vec<Segment>,
     /// The HTTP methods to match.
     pub methods: Vec<Method>,
     /// The handler function.
     pub handler: HandlerFn,
-}
\ No newline at end of file
+    /// Whether the request body is left on the connection for the handler to read through
+    /// [`HttpRequest::body_reader`], instead of being received before the handler runs.
+    pub streams_body: bool,
+}
+
+impl Route {
+    /// Match a request path against this route's pattern.
+    ///
+    /// Any query string is ignored, and empty segments (including a trailing slash) are
+    /// skipped, so `/users/42/` matches the pattern `/users/:id`.
+    ///
+    /// # Returns
+    ///
+    /// The captured path parameters if the path matches, None otherwise
+    pub fn match_path(&self, path: &str) -> Option<HashMap<String, String>> {
+        let path = path.split_once('?').map_or(path, |(path, _)| path);
+        let mut parts = split_path(path);
+        let mut params = HashMap::new();
+
+        for segment in &self.segments {
+            let part = parts.next()?;
+            match segment {
+                Segment::Static(expected) => {
+                    if expected != part {
+                        return None;
+                    }
+                }
+                Segment::Param(name) => {
+                    params.insert(name.clone(), part.to_string());
+                }
+                Segment::Wildcard(name) => {
+                    let rest: Vec<&str> = std::iter::once(part).chain(parts.by_ref()).collect();
+                    params.insert(name.clone(), rest.join("/"));
+                }
+            }
+        }
+
+        if parts.next().is_some() {
+            return None;
+        }
+
+        Some(params)
+    }
+
+    /// Get the specificity of this route's pattern, used to rank routes matching the same path.
+    ///
+    /// Patterns are compared segment by segment, with static segments ranking above parameters
+    /// and parameters ranking above wildcards.
+    pub(crate) fn specificity(&self) -> Vec<u8> {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Static(_) => 2,
+                Segment::Param(_) => 1,
+                Segment::Wildcard(_) => 0,
+            })
+            .collect()
+    }
+}
+
+/// Parse a route path pattern into segments.
+///
+/// Segments starting with `:` are named parameters and a final segment starting with `*` is a
+/// wildcard matching one or more remaining segments; all other segments must match exactly.
+///
+/// # Panics
+///
+/// Panics if the pattern contains more than one wildcard, or a wildcard that isn't the last
+/// segment.
+pub fn parse_pattern(path: &str) -> Vec<Segment> {
+    let segments: Vec<Segment> = split_path(path)
+        .map(|part| {
+            if let Some(name) = part.strip_prefix(':') {
+                Segment::Param(name.to_string())
+            } else if let Some(name) = part.strip_prefix('*') {
+                Segment::Wildcard(name.to_string())
+            } else {
+                Segment::Static(part.to_string())
+            }
+        })
+        .collect();
+
+    let wildcards = segments.iter().filter(|s| matches!(s, Segment::Wildcard(_))).count();
+    if wildcards > 1 {
+        panic!("Route pattern {path} contains more than one wildcard segment");
+    }
+    if wildcards == 1 && !matches!(segments.last(), Some(Segment::Wildcard(_))) {
+        panic!("Route pattern {path} has a wildcard segment that is not the last segment");
+    }
+
+    segments
+}
+
+/// Split a path into its non-empty segments.
+fn split_path(path: &str) -> impl Iterator<Item = &str> {
+    path.split('/').filter(|part| !part.is_empty())
+}
@@ -0,0 +1,28 @@
+//! Escaping of text for inclusion in HTML.
+
+/// Escape text for inclusion in HTML element content or a quoted attribute value.
+///
+/// The characters `<`, `>`, `&`, `"` and `'` are replaced with character references; any
+/// other character is kept as is.
+///
+/// # Examples
+///
+/// ```
+/// use microhttp_rs::html_escape;
+///
+/// assert_eq!(html_escape("<b>Tom & \"Jerry\"</b>"), "&lt;b&gt;Tom &amp; &quot;Jerry&quot;&lt;/b&gt;");
+/// ```
+pub fn html_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#x27;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
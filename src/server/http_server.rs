@@ -1,57 +1,276 @@
 //! HTTP server implementation.
 
+use std::collections::HashMap;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::Arc;
 use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
-use tokio::net::TcpListener;
-use tokio::sync::{RwLock, mpsc};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{OwnedSemaphorePermit, RwLock, Semaphore, mpsc};
 use tokio::task::JoinSet;
 use tokio::signal;
-use log::{info, warn, error};
+use log::{debug, info, warn, error};
 use std::net::SocketAddr;
+use std::time::{Duration, Instant, SystemTime};
 
-use crate::parser::{HttpRequest, Method, parse_request};
+use crate::parser::{Error as ParserError, HttpRequest, HttpVersion, Method, ParseOptions, parse_request_with_options};
+use crate::parser::{ChunkedDecoder, check_body_length, find_header_end, find_simple_request_end};
+use crate::server::access_log::{ACCESS_LOG_TARGET, AccessLogEntry};
+use crate::server::basic_auth::basic_auth;
+use crate::server::body_feed::{BodyFeed, Framing, streamed_body};
+use crate::server::catch_panic::{CatchPanic, panic_message};
+#[cfg(feature = "compression")]
+use crate::server::compression::compress_response;
+use crate::server::conditional::not_modified;
 use crate::server::config::ServerConfig;
+use crate::server::cors::CorsConfig;
+use crate::server::date::http_date;
 use crate::server::error::Error;
-use crate::server::handler::Route;
+use crate::server::handler::{MiddlewareFn, Next};
+use crate::server::into_response::IntoHandlerResult;
+use crate::server::metrics::{Metrics, ServerMetrics};
+use crate::server::range::apply_range;
 use crate::server::response::{HttpResponse, StatusCode};
+use crate::server::router::{MatchResult, Router};
+#[cfg(feature = "websocket")]
+use crate::server::websocket::WebSocketUpgrade;
 
 /// An HTTP server.
-pub struct HttpServer {
+///
+/// The server holds application state of type `S`, such as a database pool, which handlers
+/// registered with [`HttpServer::add_route_with_state`] receive alongside the request. Servers
+/// created with [`HttpServer::new`] have no state.
+pub struct HttpServer<S = ()> {
     /// The server configuration.
     pub config: ServerConfig,
-    /// The routes.
-    pub routes: Arc<RwLock<Vec<Route>>>,
+    /// The router holding the routes.
+    pub routes: Arc<RwLock<Router>>,
+    /// The middleware, in registration order.
+    pub middleware: Arc<RwLock<Vec<MiddlewareFn>>>,
+    /// The connection and request counters.
+    pub metrics: Arc<Metrics>,
+    /// The application state shared with handlers.
+    state: Arc<S>,
 }
 
 impl HttpServer {
-    /// Create a new HTTP server with the given configuration.
+    /// Create a new HTTP server with the given configuration and no application state.
     pub fn new(config: ServerConfig) -> Self {
+        Self::with_state(config, ())
+    }
+}
+
+impl<S: Send + Sync + 'static> HttpServer<S> {
+    /// Create a new HTTP server with the given configuration and application state.
+    pub fn with_state(config: ServerConfig, state: S) -> Self {
         Self {
             config,
-            routes: Arc::new(RwLock::new(Vec::new())),
+            routes: Arc::new(RwLock::new(Router::new())),
+            middleware: Arc::new(RwLock::new(Vec::new())),
+            metrics: Arc::new(Metrics::default()),
+            state: Arc::new(state),
         }
     }
 
+    /// Get the application state.
+    pub fn state(&self) -> &Arc<S> {
+        &self.state
+    }
+
+    /// Get a snapshot of the connection and request counters.
+    pub fn metrics(&self) -> ServerMetrics {
+        self.metrics.snapshot()
+    }
+
     /// Add a route to the server.
+    ///
+    /// The path may contain named parameters (`/users/:id`) and a final wildcard segment
+    /// (`/static/*path`), which are available to the handler through
+    /// [`HttpRequest::get_path_param`]. When several routes match a path, static segments take
+    /// priority over parameters, and parameters over wildcards.
+    ///
+    /// The handler may return anything that implements [`IntoHandlerResult`], from a
+    /// `Result<HttpResponse, Error>` to a plain string or status code.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the path contains more than one wildcard, or a wildcard that isn't the last
+    /// segment.
     pub async fn add_route<F, Fut>(&self, path: impl Into<String>, methods: Vec<Method>, handler: F)
     where
         F: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoHandlerResult,
+    {
+        self.routes.write().await.add_route(path, methods, handler);
+    }
+
+    /// Add a route that accepts only GET, like [`HttpServer::add_route`] with
+    /// `vec![Method::GET]`. A HEAD request for the path is served by it too.
+    pub async fn get<F, Fut>(&self, path: impl Into<String>, handler: F)
+    where
+        F: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoHandlerResult,
+    {
+        self.add_route(path, vec![Method::GET], handler).await;
+    }
+
+    /// Add a route that accepts only POST, like [`HttpServer::add_route`] with
+    /// `vec![Method::POST]`.
+    pub async fn post<F, Fut>(&self, path: impl Into<String>, handler: F)
+    where
+        F: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoHandlerResult,
+    {
+        self.add_route(path, vec![Method::POST], handler).await;
+    }
+
+    /// Add a route that accepts only PUT, like [`HttpServer::add_route`] with
+    /// `vec![Method::PUT]`.
+    pub async fn put<F, Fut>(&self, path: impl Into<String>, handler: F)
+    where
+        F: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoHandlerResult,
+    {
+        self.add_route(path, vec![Method::PUT], handler).await;
+    }
+
+    /// Add a route that accepts only DELETE, like [`HttpServer::add_route`] with
+    /// `vec![Method::DELETE]`.
+    pub async fn delete<F, Fut>(&self, path: impl Into<String>, handler: F)
+    where
+        F: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoHandlerResult,
+    {
+        self.add_route(path, vec![Method::DELETE], handler).await;
+    }
+
+    /// Add a route that accepts only PATCH, like [`HttpServer::add_route`] with
+    /// `vec![Method::PATCH]`.
+    pub async fn patch<F, Fut>(&self, path: impl Into<String>, handler: F)
+    where
+        F: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoHandlerResult,
+    {
+        self.add_route(path, vec![Method::PATCH], handler).await;
+    }
+
+    /// Add a route whose handler reads the request body as it arrives, for uploads too large
+    /// to hold in memory.
+    ///
+    /// See [`Router::add_streaming_route`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the path contains more than one wildcard, or a wildcard that isn't the last
+    /// segment.
+    pub async fn add_streaming_route<F, Fut>(&self, path: impl Into<String>, methods: Vec<Method>, handler: F)
+    where
+        F: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoHandlerResult,
+    {
+        self.routes.write().await.add_streaming_route(path, methods, handler);
+    }
+
+    /// Mount the routes of a router under a path prefix.
+    ///
+    /// See [`Router::mount`] for how the prefix is matched.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the prefix contains a wildcard.
+    pub async fn mount(&self, prefix: &str, router: Router) {
+        self.routes.write().await.mount(prefix, router);
+    }
+
+    /// Set the handler that serves requests for paths no route matches.
+    ///
+    /// See [`Router::set_not_found_handler`].
+    pub async fn set_not_found_handler<F, Fut>(&self, handler: F)
+    where
+        F: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoHandlerResult,
+    {
+        self.routes.write().await.set_not_found_handler(handler);
+    }
+
+    /// Set a fallback handler for requests no route matches, such as one serving `index.html`
+    /// for a single-page app.
+    ///
+    /// See [`Router::fallback`].
+    pub async fn fallback<F, Fut>(&self, handler: F)
+    where
+        F: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoHandlerResult,
+    {
+        self.routes.write().await.fallback(handler);
+    }
+
+    /// Set the handler that builds the `404`, `405`, `500` and `504` error responses.
+    ///
+    /// See [`Router::set_error_handler`].
+    pub async fn set_error_handler<F>(&self, handler: F)
+    where
+        F: Fn(StatusCode, &Error) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.routes.write().await.set_error_handler(handler);
+    }
+
+    /// Add a route whose handler receives the application state along with the request.
+    ///
+    /// Paths are matched just like those of [`HttpServer::add_route`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the path contains more than one wildcard, or a wildcard that isn't the last
+    /// segment.
+    pub async fn add_route_with_state<F, Fut>(&self, path: impl Into<String>, methods: Vec<Method>, handler: F)
+    where
+        F: Fn(HttpRequest, Arc<S>) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoHandlerResult,
+    {
+        let state = self.state.clone();
+        self.add_route(path, methods, move |req| handler(req, state.clone())).await;
+    }
+
+    /// Add a middleware to the server.
+    ///
+    /// Middleware wrap the handler of every matched route and run in registration order. Each
+    /// middleware receives the request and a [`Next`] that runs the remaining layers; returning
+    /// a response without calling [`Next::run`] short-circuits the chain. An error returned by a
+    /// middleware is answered with `500 Internal Server Error`, just like a handler error.
+    pub async fn add_middleware<F, Fut>(&self, middleware: F)
+    where
+        F: Fn(HttpRequest, Next) -> Fut + Send + Sync + 'static,
         Fut: Future<Output = Result<HttpResponse, Error>> + Send + 'static,
     {
-        let path = path.into();
-        let handler = Arc::new(move |req: HttpRequest| -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
-            Box::pin(handler(req))
+        let middleware = Arc::new(move |req: HttpRequest, next: Next| -> Pin<Box<dyn Future<Output = Result<HttpResponse, Error>> + Send>> {
+            Box::pin(middleware(req, next))
         });
 
-        let route = Route {
-            path,
-            methods,
-            handler,
-        };
+        self.middleware.write().await.push(middleware);
+    }
 
-        self.routes.write().await.push(route);
+    /// Require HTTP Basic authentication for every route, accepting the credentials for
+    /// which `verify` returns true.
+    ///
+    /// This adds the [`basic_auth`] middleware, which runs after any middleware added before
+    /// it. Rejected requests are answered with `401 Unauthorized` and a `WWW-Authenticate`
+    /// challenge for `realm`.
+    pub async fn require_basic_auth<F>(&self, realm: impl Into<String>, verify: F)
+    where
+        F: Fn(&str, &str) -> bool + Send + Sync + 'static,
+    {
+        self.add_middleware(basic_auth(realm, verify)).await;
     }
 
     /// Display the server banner and registered endpoints.
@@ -63,7 +282,7 @@ impl HttpServer {
         // Display registered endpoints
         let routes = self.routes.read().await;
         info!("Registered endpoints:");
-        for route in routes.iter() {
+        for route in routes.routes() {
             let methods = route.methods.iter()
                 .map(|m| format!("{m}"))
                 .collect::<Vec<String>>()
@@ -74,75 +293,140 @@ impl HttpServer {
         Ok(())
     }
 
-    /// Set up the TCP listener.
+    /// Set up the TCP listener, after checking the configuration.
     async fn setup_listener(&self) -> Result<TcpListener, Error> {
+        // Refuse to start with an invalid CORS configuration
+        if let Some(cors) = &self.config.cors {
+            cors.validate()?;
+        }
+
         let listener = TcpListener::bind(&self.config.addr).await?;
-        info!("Server listening on http://{addr}", addr = self.config.addr);
+        let scheme = if self.uses_tls() { "https" } else { "http" };
+        info!("Server listening on {scheme}://{addr}", addr = listener.local_addr()?);
         Ok(listener)
     }
 
-    /// Set up a Ctrl+C handler for graceful shutdown.
-    fn setup_ctrl_c_handler(shutdown_tx: Arc<mpsc::Sender<()>>, tasks: &mut JoinSet<()>) {
-        // Spawn a task to handle Ctrl+C
+    /// Set up signal handlers for graceful shutdown: Ctrl+C everywhere, and SIGTERM on Unix,
+    /// which container orchestrators such as Kubernetes send to stop a process.
+    fn setup_signal_handlers(shutdown_tx: Arc<mpsc::Sender<()>>, tasks: &mut JoinSet<()>) {
+        // Spawn a task to wait for either signal
         tasks.spawn(async move {
-            match signal::ctrl_c().await {
-                Ok(()) => {
-                    info!("Received Ctrl+C, initiating graceful shutdown");
-                    let _ = shutdown_tx.send(()).await;
+            #[cfg(unix)]
+            let terminate = async {
+                match signal::unix::signal(signal::unix::SignalKind::terminate()) {
+                    Ok(mut terminate) => {
+                        terminate.recv().await;
+                    }
+                    Err(e) => {
+                        // Ctrl+C still works, so keep waiting for that
+                        error!("Error setting up SIGTERM handler: {e}");
+                        std::future::pending::<()>().await;
+                    }
                 }
-                Err(e) => {
+            };
+            #[cfg(not(unix))]
+            let terminate = std::future::pending::<()>();
+
+            let ctrl_c = async {
+                if let Err(e) = signal::ctrl_c().await {
                     error!("Error setting up Ctrl+C handler: {e}");
+                    std::future::pending::<()>().await;
                 }
+            };
+
+            tokio::select! {
+                () = ctrl_c => info!("Received Ctrl+C, initiating graceful shutdown"),
+                () = terminate => info!("Received SIGTERM, initiating graceful shutdown"),
             }
+            let _ = shutdown_tx.send(()).await;
         });
     }
 
     /// Handle a new connection.
+    ///
+    /// The connection is served, or rejected with `503 Service Unavailable` when no slot frees
+    /// up in time, on a task of its own, so that waiting for a slot doesn't hold up accepting
+    /// other connections.
     async fn handle_new_connection(
-        mut socket: tokio::net::TcpStream,
+        &self,
+        mut socket: TcpStream,
         addr: SocketAddr,
-        semaphore: Arc<tokio::sync::Semaphore>,
-        routes: Arc<RwLock<Vec<Route>>>,
-        read_buffer_size: usize,
-        shutdown_tx: Arc<mpsc::Sender<()>>,
+        semaphore: Arc<Semaphore>,
         tasks: &mut JoinSet<()>,
     ) {
-
-        // Try to acquire a permit from the semaphore
-        let permit = match semaphore.clone().try_acquire_owned() {
-            Ok(permit) => permit,
-            Err(_) => {
-                warn!("Connection limit reached, rejecting connection from {addr}");
-                // Send a 503 Service Unavailable response
-                let response = HttpResponse::new(StatusCode::ServiceUnavailable)
-                    .with_content_type("text/plain")
-                    .with_body_string("Server is at capacity, please try again later");
-                let _ = socket.write_all(&response.to_bytes()).await;
-                return;
-            }
-        };
+        if let Err(e) = configure_socket(&socket, &self.config) {
+            warn!("Failed to set socket options for {addr}: {e}");
+        }
 
         // Clone references for the task
-        let routes = routes.clone();
-        let shutdown_tx = shutdown_tx.clone();
+        let routes = self.routes.clone();
+        let middleware = self.middleware.clone();
+        let config = self.config.clone();
+        let metrics = self.metrics.clone();
+        let uses_tls = self.uses_tls();
 
         // Spawn a task to handle the connection
         tasks.spawn(async move {
-            // The permit is dropped when the task completes, releasing the semaphore slot
-            let _permit = permit;
-
-            if let Err(e) = Self::handle_connection(&mut socket, routes, read_buffer_size).await {
-                error!("Error handling connection: {e}");
+            // Try to acquire a permit from the semaphore; it is dropped when the task
+            // completes, releasing the semaphore slot
+            let Some(_permit) = acquire_connection_permit(semaphore, config.connection_wait).await else {
+                warn!("Connection limit reached, rejecting connection from {addr}");
+                metrics.connection_rejected();
+                // Send a 503 Service Unavailable response, unless the client expects TLS
+                if !uses_tls {
+                    metrics.response_sent(StatusCode::ServiceUnavailable as u16);
+                    let response = HttpResponse::new(StatusCode::ServiceUnavailable)
+                        .with_header("Connection", "close")
+                        .with_content_type("text/plain")
+                        .with_body_string("Server is at capacity, please try again later");
+                    let response = with_server_header(response, &config);
+                    let _ = socket.write_all(&response.to_bytes()).await;
+                }
+                return;
+            };
 
-                // If there's a critical error, signal shutdown
-                if matches!(e, Error::IoError(_)) {
-                    info!("Critical I/O error, initiating shutdown");
-                    let _ = shutdown_tx.send(()).await;
+            #[cfg(feature = "tls")]
+            let result = match &config.tls {
+                Some(tls) => {
+                    let accept = tokio_rustls::TlsAcceptor::from(tls.clone()).accept(socket);
+                    let handshake = match config.request_deadline() {
+                        Some(request_timeout) => tokio::time::timeout(request_timeout, accept).await.ok(),
+                        None => Some(accept.await),
+                    };
+                    match handshake {
+                        Some(Ok(mut tls_stream)) => {
+                            HttpServer::handle_connection(&mut tls_stream, routes, middleware, &config, &metrics, Some(addr)).await
+                        }
+                        Some(Err(e)) => {
+                            warn!("TLS handshake with {addr} failed: {e}");
+                            return;
+                        }
+                        None => {
+                            warn!("TLS handshake with {addr} timed out");
+                            return;
+                        }
+                    }
                 }
+                None => HttpServer::handle_connection(&mut socket, routes, middleware, &config, &metrics, Some(addr)).await,
+            };
+            #[cfg(not(feature = "tls"))]
+            let result = HttpServer::handle_connection(&mut socket, routes, middleware, &config, &metrics, Some(addr)).await;
+
+            // Errors only end this connection; the server keeps serving the others
+            if let Err(e) = result {
+                warn!("Error handling connection from {addr}: {e}");
             }
         });
     }
 
+    /// Check whether connections are accepted over TLS.
+    fn uses_tls(&self) -> bool {
+        #[cfg(feature = "tls")]
+        return self.config.tls.is_some();
+        #[cfg(not(feature = "tls"))]
+        return false;
+    }
+
     /// Handle connection errors.
     async fn handle_connection_error(e: std::io::Error) -> bool {
         error!("Error accepting connection: {e}");
@@ -159,11 +443,13 @@ impl HttpServer {
     }
 
     /// Perform graceful shutdown.
-    async fn perform_shutdown(tasks: &mut JoinSet<()>) {
+    ///
+    /// Waits up to `shutdown_timeout` for the running tasks to complete, then aborts those
+    /// still running.
+    pub(crate) async fn perform_shutdown(tasks: &mut JoinSet<()>, shutdown_timeout: Duration) {
         // Wait for all tasks to complete (with timeout)
         info!("Waiting for {len} active connections to complete...", len = tasks.len());
-        let shutdown_timeout = tokio::time::Duration::from_secs(30);
-        let _ = tokio::time::timeout(shutdown_timeout, async {
+        let drained = tokio::time::timeout(shutdown_timeout, async {
             while let Some(res) = tasks.join_next().await {
                 if let Err(e) = res {
                     error!("Task failed during shutdown: {e}");
@@ -171,19 +457,49 @@ impl HttpServer {
             }
         }).await;
 
+        if drained.is_err() {
+            warn!(
+                "Shutdown timeout of {shutdown_timeout:?} elapsed with {len} tasks still running, aborting them",
+                len = tasks.len()
+            );
+            tasks.abort_all();
+            while tasks.join_next().await.is_some() {}
+        }
+
         info!("Server shutdown complete");
     }
 
+    /// Bind the listening socket without accepting connections yet.
+    ///
+    /// The returned [`BoundServer`] knows the address actually bound, including the port
+    /// picked by the system when the configured port is 0, and starts accepting connections
+    /// with [`BoundServer::serve`].
+    pub async fn bind(self) -> Result<BoundServer<S>, Error> {
+        let listener = self.setup_listener().await?;
+        let local_addr = listener.local_addr()?;
+        Ok(BoundServer {
+            server: self,
+            listener,
+            local_addr,
+        })
+    }
+
     /// Start the server and listen for incoming connections.
+    ///
+    /// This binds the configured address and serves connections until shutdown, like
+    /// [`HttpServer::bind`] followed by [`BoundServer::serve`].
     pub async fn start(&self) -> Result<(), Error> {
+        let listener = self.setup_listener().await?;
+        self.serve_listener(listener).await
+    }
+
+    /// Accept and serve connections on a bound listener until shutdown.
+    async fn serve_listener(&self, listener: TcpListener) -> Result<(), Error> {
         // Display server information
         self.display_server_info().await?;
 
-        // Set up the TCP listener
-        let listener = self.setup_listener().await?;
-
         // Create a semaphore to limit concurrent connections
-        let semaphore = Arc::new(tokio::sync::Semaphore::new(self.config.max_connections));
+        let semaphore = Arc::new(Semaphore::new(self.config.max_connections));
 
         // Create a channel for shutdown signaling
         let (shutdown_tx, mut shutdown_rx) = mpsc::channel::<()>(1);
@@ -192,8 +508,8 @@ impl HttpServer {
         // Use JoinSet to keep track of all spawned tasks
         let mut tasks = JoinSet::new();
 
-        // Set up a Ctrl+C handler for graceful shutdown
-        Self::setup_ctrl_c_handler(shutdown_tx.clone(), &mut tasks);
+        // Shut down gracefully on Ctrl+C or SIGTERM
+        Self::setup_signal_handlers(shutdown_tx.clone(), &mut tasks);
 
         loop {
             tokio::select! {
@@ -207,13 +523,10 @@ impl HttpServer {
                 accept_result = listener.accept() => {
                     match accept_result {
                         Ok((socket, addr)) => {
-                            Self::handle_new_connection(
+                            self.handle_new_connection(
                                 socket, 
                                 addr, 
                                 semaphore.clone(), 
-                                self.routes.clone(), 
-                                self.config.read_buffer_size, 
-                                shutdown_tx.clone(), 
                                 &mut tasks
                             ).await;
                         },
@@ -228,83 +541,737 @@ impl HttpServer {
         }
 
         // Perform graceful shutdown
-        Self::perform_shutdown(&mut tasks).await;
+        Self::perform_shutdown(&mut tasks, self.config.shutdown_timeout).await;
 
         Ok(())
     }
+}
 
+/// A server whose listening socket is bound, ready to accept connections.
+///
+/// Created with [`HttpServer::bind`]. The bound server dereferences to the [`HttpServer`], so
+/// routes and middleware can still be added until [`BoundServer::serve`] is called.
+///
+/// # Examples
+///
+/// ```no_run
+/// use microhttp_rs::{HttpServer, Method, ServerConfig};
+///
+/// # async fn run() -> Result<(), microhttp_rs::ServerError> {
+/// let config = ServerConfig { addr: "127.0.0.1:0".parse().unwrap(), ..ServerConfig::default() };
+/// let server = HttpServer::new(config).bind().await?;
+/// server.add_route("/", vec![Method::GET], |_req| async { "Hello" }).await;
+/// println!("Listening on {}", server.local_addr());
+/// tokio::spawn(server.serve());
+/// # Ok(())
+/// # }
+/// ```
+pub struct BoundServer<S = ()> {
+    server: HttpServer<S>,
+    listener: TcpListener,
+    local_addr: SocketAddr,
+}
+
+impl<S: Send + Sync + 'static> BoundServer<S> {
+    /// Get the address the server is bound to.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Accept and serve connections until shutdown.
+    ///
+    /// See [`HttpServer::start`], which binds and serves in one call.
+    pub async fn serve(self) -> Result<(), Error> {
+        self.server.serve_listener(self.listener).await
+    }
+}
+
+impl<S> std::ops::Deref for BoundServer<S> {
+    type Target = HttpServer<S>;
+
+    fn deref(&self) -> &HttpServer<S> {
+        &self.server
+    }
+}
+
+// Connection handling doesn't depend on the application state, which handlers capture when
+// their routes are added
+impl HttpServer {
     /// Handle a single connection.
+    ///
+    /// Requests are read and answered one after another on the same connection while the client
+    /// keeps it alive: HTTP/1.1 connections stay open unless the request carries
+    /// `Connection: close`, and HTTP/1.0 connections only when it carries `Connection: keep-alive`.
+    /// The connection is closed when the client closes it, when no new request arrives within the
+    /// configured keep-alive timeout, or when an error occurs. A request that isn't received in
+    /// full within the configured request timeout is answered with `408 Request Timeout`.
+    ///
+    /// A client that resets the connection or goes away mid-write ends it like a close does,
+    /// without an error.
+    ///
+    /// `peer_addr` is the address of the client, made available to handlers as
+    /// [`HttpRequest::peer_addr`] and recorded in the access log if one is configured.
     pub async fn handle_connection(
         socket: &mut (impl AsyncRead + AsyncWrite + Unpin),
-        routes: Arc<RwLock<Vec<Route>>>,
-        read_buffer_size: usize,
+        routes: Arc<RwLock<Router>>,
+        middleware: Arc<RwLock<Vec<MiddlewareFn>>>,
+        config: &ServerConfig,
+        metrics: &Metrics,
+        peer_addr: Option<SocketAddr>,
     ) -> Result<(), Error> {
-        let mut buf = vec![0; read_buffer_size];
+        match Self::serve_requests(socket, routes, middleware, config, metrics, peer_addr).await {
+            Err(Error::IoError(e)) if is_disconnect(&e) => {
+                debug!("Client disconnected: {e}");
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    /// Serve the requests of a connection, for [`HttpServer::handle_connection`].
+    async fn serve_requests(
+        socket: &mut (impl AsyncRead + AsyncWrite + Unpin),
+        routes: Arc<RwLock<Router>>,
+        middleware: Arc<RwLock<Vec<MiddlewareFn>>>,
+        config: &ServerConfig,
+        metrics: &Metrics,
+        peer_addr: Option<SocketAddr>,
+    ) -> Result<(), Error> {
+        let _active = metrics.connection_opened();
+        let mut buf = Vec::with_capacity(config.read_buffer_size);
+        let mut chunk = vec![0; config.read_buffer_size];
+        let mut first_request = true;
+
+        loop {
+            // Wait for the next request, closing the connection if it stays idle for too long
+            if !first_request && buf.is_empty() {
+                match tokio::time::timeout(config.keep_alive_timeout, socket.read(&mut chunk)).await {
+                    Ok(result) => {
+                        let n = result?;
+                        if n == 0 {
+                            return Ok(()); // Connection closed
+                        }
+                        buf.extend_from_slice(&chunk[..n]);
+                    }
+                    Err(_) => {
+                        debug!("Closing idle keep-alive connection");
+                        return Ok(());
+                    }
+                }
+            }
+            first_request = false;
+
+            // Read the request, giving up if the client is too slow to send it
+            let read = Self::read_request(socket, &mut buf, &mut chunk, &routes, config);
+            let result = match config.request_deadline() {
+                Some(request_timeout) => match tokio::time::timeout(request_timeout, read).await {
+                    Ok(result) => result,
+                    Err(_) => {
+                        let response = HttpResponse::new(StatusCode::RequestTimeout)
+                            .with_header("Connection", "close")
+                            .with_content_type("text/plain")
+                            .with_body_string("Timed out waiting for the request");
+                        let response = with_server_header(response, config);
+                        metrics.response_sent(response.status_code());
+                        socket.write_all(&response.to_bytes()).await?;
+                        return Err(Error::RequestTimeout);
+                    }
+                },
+                None => read.await,
+            };
+
+            let (mut request, body_feed) = match result {
+                Ok(Some(request)) => request,
+                Ok(None) => return Ok(()), // Connection closed
+                Err(e) => {
+                    if let Some(status) = rejection_status(&e) {
+                        metrics.response_sent(status as u16);
+                    }
+                    return Err(e);
+                }
+            };
 
-        // Read data from the socket
-        let n = socket.read(&mut buf).await?;
-        if n == 0 {
-            return Ok(()); // Connection closed
+            request.peer_addr = peer_addr;
+            let body_feed = body_feed.map(|feed| (feed, &mut buf, &mut chunk[..]));
+            match Self::handle_request(socket, request, body_feed, &routes, &middleware, config, metrics).await? {
+                AfterResponse::KeepAlive => {}
+                AfterResponse::Close => return Ok(()),
+                #[cfg(feature = "websocket")]
+                AfterResponse::Upgrade(upgrade) => {
+                    // Bytes after the upgrade request are already WebSocket frames
+                    upgrade.run(socket, &buf, config.max_body_size).await;
+                    return Ok(());
+                }
+            }
         }
+    }
 
-        // Parse the HTTP request
-        let request = match parse_request(&buf[..n]) {
+    /// Read a single request from the connection.
+    ///
+    /// The request is read until the end of the header section, after which the body is read
+    /// until `Content-Length` bytes (or the final chunk of a chunked body) have been received.
+    /// A client that closes the connection before that gets `400 Bad Request`, so handlers
+    /// never see a truncated body. A client that closes it right after the last header line,
+    /// without the blank line that ends the header section, has its request handled as long as
+    /// the request announces no body. The bytes of the request are removed from `buf`,
+    /// leaving any bytes of a following pipelined request in place.
+    ///
+    /// Bodies larger than the configured maximum are answered with `413 Payload Too Large`.
+    ///
+    /// The body of a request for a streaming route is left on the connection, to be read while
+    /// the handler runs through the returned [`BodyFeed`].
+    ///
+    /// # Returns
+    ///
+    /// The request and the feed of a streamed body, or None if the connection was closed
+    /// before any bytes arrived
+    async fn read_request(
+        socket: &mut (impl AsyncRead + AsyncWrite + Unpin),
+        buf: &mut Vec<u8>,
+        chunk: &mut [u8],
+        routes: &RwLock<Router>,
+        config: &ServerConfig,
+    ) -> Result<Option<(HttpRequest, Option<BodyFeed>)>, Error> {
+        // Read until the end of the header section (or until the client closes the connection)
+        let header_end = loop {
+            // An HTTP/0.9 simple request has no headers and ends with its request line
+            let header_end = find_header_end(buf).or_else(|| find_simple_request_end(buf));
+            Self::check_head_size(socket, buf, header_end, config).await?;
+            if let Some(end) = header_end {
+                break Some(end);
+            }
+
+            let n = socket.read(chunk).await?;
+            if n == 0 {
+                break None;
+            }
+            buf.extend_from_slice(&chunk[..n]);
+        };
+
+        if buf.is_empty() {
+            return Ok(None);
+        }
+
+        // A client may close its side right after the last header line, leaving out the
+        // blank line, but a line cut short means the request was truncated
+        if header_end.is_none() && !buf.ends_with(b"\n") {
+            let e = ParserError::IncompleteHeaders(format!("the connection closed after {} bytes, in the middle of a line", buf.len()));
+            return Err(Self::reject_parse_error(socket, e, config).await);
+        }
+
+        // Parse the HTTP request line and headers
+        let head = &buf[..header_end.unwrap_or(buf.len())];
+        let options = ParseOptions::default()
+            .with_max_headers(config.max_headers)
+            .with_strict_line_endings(config.strict_line_endings)
+            .with_normalize_path(config.normalize_paths);
+        let mut request = match parse_request_with_options(head, options) {
             Ok(req) => req,
-            Err(e) => {
-                let response = HttpResponse::new(StatusCode::BadRequest)
-                    .with_content_type("text/plain")
-                    .with_body_string(format!("Error parsing request: {e}"));
-                socket.write_all(&response.to_bytes()).await?;
-                return Err(Error::ParseError(e));
+            Err(e) => return Err(Self::reject_parse_error(socket, e, config).await),
+        };
+
+        // The length the request gives for its body, which parsing has already checked
+        let content_length = match check_body_length(request.headers_in_order()) {
+            Ok(content_length) => content_length.unwrap_or(0),
+            Err(e) => return Err(Self::reject_parse_error(socket, e, config).await),
+        };
+
+        let Some(header_end) = header_end else {
+            // The connection was closed before the end of the headers, so a body the request
+            // announces can never arrive
+            let expects_body = request.is_chunked() || content_length > 0;
+            if expects_body {
+                let e = ParserError::IncompleteBody("the connection closed before the end of the headers".to_string());
+                return Err(Self::reject_parse_error(socket, e, config).await);
             }
+            buf.clear();
+            return Ok(Some((request, None)));
         };
 
-        // Find a matching route
-        let routes_guard = routes.read().await;
-        let matching_routes: Vec<&Route> = routes_guard
-            .iter()
-            .filter(|route| route.path == request.path)
-            .collect();
+        // A client that sent `Expect: 100-continue` waits for an interim response before
+        // sending the body
+        let expects_continue = request.version == HttpVersion::Http11
+            && request
+                .get_header("Expect")
+                .is_some_and(|v| v.trim().eq_ignore_ascii_case("100-continue"));
 
-        if matching_routes.is_empty() {
-            let response = HttpResponse::new(StatusCode::NotFound)
-                .with_content_type("text/plain")
-                .with_body_string(format!("Not found: {path}", path = request.path));
-            socket.write_all(&response.to_bytes()).await?;
-            return Err(Error::NotFound(request.path));
+        // A streaming route reads the body itself while its handler runs
+        if routes.read().await.streams_body(&request.method, &request.path) {
+            let framing = if request.is_chunked() {
+                Some(Framing::Chunked(ChunkedDecoder::new(config.strict_line_endings)))
+            } else {
+                // There is nothing to stream without a body
+                (content_length > 0).then_some(Framing::Length(content_length))
+            };
+            if let Some(framing) = framing {
+                buf.drain(..header_end);
+                let (body, feed) = streamed_body(framing, expects_continue, config.request_deadline());
+                request.streamed_body = Some(body);
+                return Ok(Some((request, Some(feed))));
+            }
         }
 
-        // Find a route that matches the method
-        let route = matching_routes
-            .iter()
-            .find(|route| route.methods.contains(&request.method));
+        // Read the body, either as chunks or based on the Content-Length header
+        let request_end = if request.is_chunked() {
+            if expects_continue && buf.len() == header_end {
+                send_continue(socket).await?;
+            }
 
-        match route {
-            Some(route) => {
-                // Call the handler
-                let response = match (route.handler)(request).await {
-                    Ok(resp) => resp,
-                    Err(e) => {
-                        let response = HttpResponse::new(StatusCode::InternalServerError)
-                            .with_content_type("text/plain")
-                            .with_body_string(format!("Internal server error: {e}"));
-                        socket.write_all(&response.to_bytes()).await?;
-                        return Err(e);
+            // Decode each read as it arrives, keeping the decoder's place in the body
+            let mut decoder = ChunkedDecoder::new(config.strict_line_endings);
+            let mut decoded = 0;
+            loop {
+                match decoder.decode(&buf[header_end + decoded..], &mut request.body) {
+                    Ok(n) => decoded += n,
+                    Err(e) => return Err(Self::reject_parse_error(socket, e, config).await),
+                }
+                if request.body.len() > config.max_body_size {
+                    return Err(Self::reject_payload_too_large(socket, config).await);
+                }
+                if request.headers.len() + decoder.trailers().len() > config.max_headers {
+                    let e = ParserError::TooManyHeaders(config.max_headers);
+                    return Err(Self::reject_parse_error(socket, e, config).await);
+                }
+                if decoder.is_done() {
+                    break;
+                }
+
+                let n = socket.read(chunk).await?;
+                if n == 0 {
+                    // The connection closed before the final chunk arrived
+                    let e = ParserError::IncompleteBody(format!(
+                        "the connection closed after {} bytes, before the final chunk",
+                        request.body.len()
+                    ));
+                    return Err(Self::reject_parse_error(socket, e, config).await);
+                }
+                buf.extend_from_slice(&chunk[..n]);
+            }
+
+            for (name, value) in decoder.take_trailers() {
+                request.append_header(name, value);
+            }
+            header_end + decoded
+        } else {
+            if content_length > config.max_body_size {
+                return Err(Self::reject_payload_too_large(socket, config).await);
+            }
+
+            if expects_continue && content_length > 0 && buf.len() == header_end {
+                send_continue(socket).await?;
+            }
+
+            while buf.len() - header_end < content_length {
+                let n = socket.read(chunk).await?;
+                if n == 0 {
+                    // The connection closed before the full body arrived
+                    let e = ParserError::IncompleteBody(format!(
+                        "received {} of {content_length} bytes",
+                        buf.len() - header_end
+                    ));
+                    return Err(Self::reject_parse_error(socket, e, config).await);
+                }
+                buf.extend_from_slice(&chunk[..n]);
+            }
+
+            let body_end = header_end + content_length;
+            request.body = buf[header_end..body_end].to_vec();
+            body_end
+        };
+
+        // Keep any bytes that belong to the next request
+        buf.drain(..request_end);
+
+        Ok(Some((request, None)))
+    }
+
+    /// Check the request line and header section against the configured limits, answering
+    /// with `414 URI Too Long` or `431 Request Header Fields Too Large` if they are exceeded.
+    ///
+    /// `header_end` is the end of the header section, or None if it hasn't arrived yet.
+    async fn check_head_size(
+        socket: &mut (impl AsyncRead + AsyncWrite + Unpin),
+        buf: &[u8],
+        header_end: Option<usize>,
+        config: &ServerConfig,
+    ) -> Result<(), Error> {
+        let head = &buf[..header_end.unwrap_or(buf.len())];
+        let request_line_len = head.iter().position(|&b| b == b'\n').unwrap_or(head.len());
+
+        let (status, error) = if request_line_len > config.max_request_line_size {
+            (StatusCode::UriTooLong, Error::RequestLineTooLong(config.max_request_line_size))
+        } else if head.len() > config.max_header_size {
+            (StatusCode::RequestHeaderFieldsTooLarge, Error::HeadersTooLarge(config.max_header_size))
+        } else {
+            return Ok(());
+        };
+
+        let response = HttpResponse::new(status)
+            .with_header("Connection", "close")
+            .with_content_type("text/plain")
+            .with_body_string(error.to_string());
+        let response = with_server_header(response, config);
+        socket.write_all(&response.to_bytes()).await?;
+        Err(error)
+    }
+
+    /// Answer a request that failed to parse with the status and message
+    /// [`status_and_message`] picks for the error.
+    ///
+    /// # Returns
+    ///
+    /// The error to end the connection with
+    async fn reject_parse_error(
+        socket: &mut (impl AsyncRead + AsyncWrite + Unpin),
+        e: ParserError,
+        config: &ServerConfig,
+    ) -> Error {
+        let (status, message) = status_and_message(&e);
+        let response = HttpResponse::new(status)
+            .with_header("Connection", "close")
+            .with_content_type("text/plain")
+            .with_body_string(message);
+        let response = with_server_header(response, config);
+        match socket.write_all(&response.to_bytes()).await {
+            Ok(()) => Error::ParseError(e),
+            Err(e) => Error::IoError(e),
+        }
+    }
+
+    /// Answer a request whose body exceeds the maximum size with `413 Payload Too Large`.
+    ///
+    /// # Returns
+    ///
+    /// The error to end the connection with
+    async fn reject_payload_too_large(
+        socket: &mut (impl AsyncRead + AsyncWrite + Unpin),
+        config: &ServerConfig,
+    ) -> Error {
+        let max_body_size = config.max_body_size;
+        let response = HttpResponse::new(StatusCode::PayloadTooLarge)
+            .with_header("Connection", "close")
+            .with_content_type("text/plain")
+            .with_body_string(format!("Request body exceeds the maximum size of {max_body_size} bytes"));
+        let response = with_server_header(response, config);
+        match socket.write_all(&response.to_bytes()).await {
+            Ok(()) => Error::PayloadTooLarge(max_body_size),
+            Err(e) => Error::IoError(e),
+        }
+    }
+
+    /// Route a request to its handler and write the response.
+    ///
+    /// A HEAD request for a path without an explicit HEAD route is served by its GET route, and
+    /// responses to HEAD requests are sent without a body. An OPTIONS request for a path without
+    /// an explicit OPTIONS route is answered with the allowed methods if `auto_options` is enabled.
+    ///
+    /// The response carries a `Connection` header telling the client whether the connection
+    /// stays open: it does if the client wants it kept alive, unless the request failed, the
+    /// body is streamed, the handler left part of a streamed request body unread, or the handler
+    /// set `Connection: close`.
+    ///
+    /// `body_feed` is the feed of a streamed request body, with the connection's read buffers
+    /// it reads the body into.
+    ///
+    /// # Returns
+    ///
+    /// What becomes of the connection
+    async fn handle_request(
+        socket: &mut (impl AsyncRead + AsyncWrite + Unpin),
+        request: HttpRequest,
+        body_feed: Option<(BodyFeed, &mut Vec<u8>, &mut [u8])>,
+        routes: &RwLock<Router>,
+        middleware: &RwLock<Vec<MiddlewareFn>>,
+        config: &ServerConfig,
+        metrics: &Metrics,
+    ) -> Result<AfterResponse, Error> {
+        let started = Instant::now();
+        // Responses to HEAD requests carry the headers of the full response but no body
+        let is_head = request.method == Method::HEAD;
+        let request_version = request.version;
+        let cors_headers = config
+            .cors
+            .as_ref()
+            .map(|cors| cors.response_headers(&request))
+            .unwrap_or_default();
+        #[cfg(feature = "compression")]
+        let accept_encoding = request.get_header("Accept-Encoding").cloned();
+        let is_conditional = config.conditional_requests && matches!(request.method, Method::GET | Method::HEAD);
+        let if_none_match = request.get_header("If-None-Match").cloned().filter(|_| is_conditional);
+        let if_modified_since = request.get_header("If-Modified-Since").cloned().filter(|_| is_conditional);
+        // Range requests are only defined for GET, but HEAD responses still advertise support
+        let is_ranged = config.range_requests && matches!(request.method, Method::GET | Method::HEAD);
+        let range = request.get_header("Range").cloned().filter(|_| request.method == Method::GET);
+        let if_range = request.get_header("If-Range").cloned();
+        let keep_alive = wants_keep_alive(&request);
+        let peer_addr = request.peer_addr;
+        let access_log = config
+            .access_log
+            .map(|format| (format, request.method.clone(), request.path.clone(), SystemTime::now()));
+        let routed = Self::route_request(request, routes, middleware, config);
+        let ((mut response, result), body_read) = match body_feed {
+            Some((feed, buf, chunk)) => feed.run_alongside(socket, buf, chunk, routed).await,
+            None => (routed.await, true),
+        };
+        if is_conditional {
+            response = not_modified(response, if_none_match.as_deref(), if_modified_since.as_deref());
+        }
+        if is_ranged {
+            response = apply_range(response, range.as_deref(), if_range.as_deref());
+        }
+        metrics.request_served(response.status_code());
+
+        // HEAD responses are left uncompressed
+        #[cfg(feature = "compression")]
+        if config.compress_responses && !is_head {
+            response = compress_response(response, accept_encoding.as_deref(), config.compression_threshold);
+        }
+
+        for (name, value) in cors_headers {
+            // Keep any Vary header the handler set alongside the CORS one
+            response = if name == "Vary" {
+                response.with_added_header(name, value)
+            } else {
+                response.with_header(name, value)
+            };
+        }
+
+        // A connection switching protocols keeps the Connection header of the 101 response
+        #[cfg(feature = "websocket")]
+        let upgrade = response
+            .upgrade
+            .take()
+            .filter(|_| result.is_ok() && response.status_code() == StatusCode::SwitchingProtocols as u16);
+        #[cfg(feature = "websocket")]
+        let upgrading = upgrade.is_some();
+        #[cfg(not(feature = "websocket"))]
+        let upgrading = false;
+
+        // Errors, streamed bodies and request bodies left unread end the connection
+        let keep_alive =
+            keep_alive && body_read && result.is_ok() && response.stream.is_none() && !has_close_token(&response);
+        if request_version != HttpVersion::Http09 && !upgrading {
+            response = response.with_header("Connection", if keep_alive { "keep-alive" } else { "close" });
+        }
+        if config.send_date && response.get_all_headers("Date").is_empty() {
+            response = response.with_header("Date", http_date(SystemTime::now()));
+        }
+        response = with_server_header(response, config);
+
+        let status = response.status_code();
+        let written = Self::write_response(socket, response, is_head, request_version).await;
+
+        if let Some((format, method, path, time)) = access_log {
+            let entry = AccessLogEntry {
+                remote_addr: peer_addr,
+                method: &method,
+                path: &path,
+                version: request_version,
+                status,
+                bytes: written.as_ref().map(|(bytes, _)| *bytes).unwrap_or(0),
+                duration: started.elapsed(),
+                time,
+            };
+            info!(target: ACCESS_LOG_TARGET, "{}", entry.format(format));
+        }
+
+        let (_, reusable) = written?;
+        result?;
+        #[cfg(feature = "websocket")]
+        if let Some(upgrade) = upgrade {
+            return Ok(AfterResponse::Upgrade(upgrade));
+        }
+        Ok(if reusable { AfterResponse::KeepAlive } else { AfterResponse::Close })
+    }
+
+    /// Write a response in the form the request version expects.
+    ///
+    /// # Returns
+    ///
+    /// The number of body bytes written, and whether the connection can be reused afterwards
+    async fn write_response(
+        socket: &mut (impl AsyncRead + AsyncWrite + Unpin),
+        mut response: HttpResponse,
+        is_head: bool,
+        version: HttpVersion,
+    ) -> Result<(usize, bool), Error> {
+        // HTTP/0.9 responses are just the body, ended by closing the connection
+        if version == HttpVersion::Http09 {
+            let bytes = match response.stream.as_ref().and_then(|stream| stream.take()) {
+                Some(receiver) => Self::write_body_stream(socket, receiver, None).await?,
+                None => {
+                    socket.write_all(&response.body).await?;
+                    response.body.len()
+                }
+            };
+            socket.flush().await?;
+            return Ok((bytes, false));
+        }
+
+        let reusable = !has_close_token(&response);
+
+        if let Some(receiver) = response.stream.as_ref().and_then(|stream| stream.take()) {
+            // HTTP/1.0 clients don't understand chunked encoding; the end of the body is
+            // marked by closing the connection instead
+            let is_http10 = version == HttpVersion::Http10;
+            if is_http10 {
+                response.headers.remove("Transfer-Encoding");
+            }
+            socket.write_all(&response.to_head_bytes()).await?;
+            socket.flush().await?;
+            let bytes = if is_head {
+                0
+            } else {
+                let last_chunk = (!is_http10).then(|| response.last_chunk_bytes());
+                Self::write_body_stream(socket, receiver, last_chunk).await?
+            };
+            return Ok((bytes, false));
+        }
+
+        if is_head {
+            socket.write_all(&response.to_head_bytes()).await?;
+            Ok((0, reusable))
+        } else {
+            socket.write_all(&response.to_bytes()).await?;
+            Ok((response.body.len(), reusable))
+        }
+    }
+
+    /// Write the chunks of a streamed body as they arrive, flushing after each one.
+    ///
+    /// With a `last_chunk`, the body is sent with chunked encoding and ended by it, trailers
+    /// included; without one, the chunks are sent as they are.
+    ///
+    /// Returns when every sender is dropped or the client closes the connection; in the
+    /// latter case the receiver is dropped so the producer sees further sends fail.
+    ///
+    /// # Returns
+    ///
+    /// The number of body bytes written, not counting the chunk framing
+    async fn write_body_stream(
+        socket: &mut (impl AsyncRead + AsyncWrite + Unpin),
+        mut receiver: mpsc::Receiver<Vec<u8>>,
+        last_chunk: Option<Vec<u8>>,
+    ) -> Result<usize, Error> {
+        let chunked = last_chunk.is_some();
+        let mut probe = [0; 512];
+        let mut written = 0;
+        loop {
+            tokio::select! {
+                chunk = receiver.recv() => match chunk {
+                    // An empty chunk would end a chunked body early
+                    Some(data) if data.is_empty() => {}
+                    Some(data) => {
+                        if chunked {
+                            socket.write_all(format!("{:x}\r\n", data.len()).as_bytes()).await?;
+                            socket.write_all(&data).await?;
+                            socket.write_all(b"\r\n").await?;
+                        } else {
+                            socket.write_all(&data).await?;
+                        }
+                        socket.flush().await?;
+                        written += data.len();
                     }
-                };
+                    None => {
+                        if let Some(last_chunk) = &last_chunk {
+                            socket.write_all(last_chunk).await?;
+                        }
+                        socket.flush().await?;
+                        return Ok(written);
+                    }
+                },
+                // Watch the connection so a disconnect is noticed even while no chunks are sent
+                read = socket.read(&mut probe) => match read {
+                    Ok(0) | Err(_) => {
+                        debug!("Client disconnected during a streamed response");
+                        return Ok(written);
+                    }
+                    // Anything the client sends while the body streams is ignored
+                    Ok(_) => {}
+                },
+            }
+        }
+    }
+
+    /// Route a request to its handler and produce the response to send, along with the
+    /// error that ends the connection, if any.
+    ///
+    /// Unmatched paths are served by the router's not-found handler if one is set, and the
+    /// `404`, `405`, `500` and `504` responses are built by its error handler if one is set.
+    ///
+    /// Methods the server doesn't serve on a path are answered in one of two ways:
+    ///
+    /// - A standard method, or an extension method some route accepts, gets
+    ///   `405 Method Not Allowed` with an `Allow` header listing the methods of the path.
+    /// - An extension method no route accepts, such as `PROPFIND` on a server without WebDAV
+    ///   routes, gets `501 Not Implemented` with an `Allow` header listing every method the
+    ///   server accepts, whatever the path.
+    async fn route_request(
+        mut request: HttpRequest,
+        routes: &RwLock<Router>,
+        middleware: &RwLock<Vec<MiddlewareFn>>,
+        config: &ServerConfig,
+    ) -> (HttpResponse, Result<(), Error>) {
+        // `OPTIONS *` asks about the server as a whole, so it is answered with every method any
+        // route accepts rather than routed
+        if request.path == "*" {
+            let methods = routes.read().await.methods();
+            return (Self::options_response(methods), Ok(()));
+        }
+
+        let (matched, not_found_handler, error_handler, server_methods) = {
+            let routes = routes.read().await;
+            let matched = routes.match_route(&request.method, &request.path);
+            (matched, routes.not_found_handler().cloned(), routes.error_handler().cloned(), routes.methods())
+        };
+        let error_response = |status: StatusCode, error: &Error, default: HttpResponse| match &error_handler {
+            Some(error_handler) => error_handler(status, error),
+            None => default,
+        };
+
+        // CONNECT names a host rather than a path, so only routes that accept it can serve it
+        if request.method == Method::CONNECT && !matches!(matched, MatchResult::Matched { .. }) {
+            let response = HttpResponse::new(StatusCode::NotImplemented)
+                .with_content_type("text/plain")
+                .with_body_string("CONNECT is not supported");
+            return (response, Ok(()));
+        }
 
-                // Send the response
-                socket.write_all(&response.to_bytes()).await?;
+        // An extension method that no route accepts isn't implemented by the server at all,
+        // wherever it is sent
+        if matches!(request.method, Method::Other(_)) && !server_methods.contains(&request.method) {
+            let response = HttpResponse::new(StatusCode::NotImplemented)
+                .with_header("Allow", Self::allow_header(server_methods))
+                .with_content_type("text/plain")
+                .with_body_string(format!("Method {} is not implemented", request.method));
+            return (response, Ok(()));
+        }
+
+        let (handler, path_params) = match matched {
+            MatchResult::NotFound => match not_found_handler {
+                Some(handler) => (handler, HashMap::new()),
+                None => {
+                    let error = Error::NotFound(request.path);
+                    let default = HttpResponse::new(StatusCode::NotFound)
+                        .with_content_type("text/plain")
+                        .with_body_string(error.to_string());
+                    return (error_response(StatusCode::NotFound, &error, default), Err(error));
+                }
+            },
+            // Answer CORS preflight requests without calling a handler
+            _ if config.cors.is_some() && CorsConfig::is_preflight(&request) => {
+                return (HttpResponse::new(StatusCode::NoContent), Ok(()));
             }
-            None => {
-                // Method not allowed
-                let allowed_methods: Vec<String> = matching_routes
-                    .iter()
-                    .flat_map(|route| route.methods.iter().map(|m| m.to_string()))
-                    .collect();
-
-                let response = HttpResponse::new(StatusCode::MethodNotAllowed)
-                    .with_header("Allow", allowed_methods.join(", "))
+            // The matched method only picks the route: a HEAD request served by a GET route
+            // stays a HEAD request, so its body is still stripped
+            MatchResult::Matched { handler, path_params, .. } => (handler, path_params),
+            MatchResult::MethodNotAllowed(methods) if request.method == Method::OPTIONS && config.auto_options => {
+                // Answer with the methods the path supports
+                return (Self::options_response(methods), Ok(()));
+            }
+            MatchResult::MethodNotAllowed(methods) => {
+                let allowed_methods: Vec<String> = methods.iter().map(|m| m.to_string()).collect();
+                let default = HttpResponse::new(StatusCode::MethodNotAllowed)
                     .with_content_type("text/plain")
                     .with_body_string(format!(
                         "Method {method} not allowed for path: {path}. Allowed methods: {allowed}",
@@ -313,11 +1280,214 @@ impl HttpServer {
                         allowed = allowed_methods.join(", ")
                     ));
 
-                socket.write_all(&response.to_bytes()).await?;
-                return Err(Error::MethodNotAllowed(request.method, request.path));
+                // The Allow header is required, whoever builds the body
+                let error = Error::MethodNotAllowed(request.method, request.path);
+                let response = error_response(StatusCode::MethodNotAllowed, &error, default)
+                    .with_header("Allow", allowed_methods.join(", "));
+                return (response, Err(error));
             }
+        };
+
+        request.path_params = path_params;
+
+        // Call the handler through the middleware chain
+        let middleware = Arc::new(middleware.read().await.clone());
+        let next = Next::new(middleware, handler);
+        // Run the chain inside the wrapper, so panics raised before the handler returns its
+        // future are caught too
+        let handle = CatchPanic::new(async move { next.run(request).await });
+        let handled = match config.handler_timeout {
+            Some(handler_timeout) => tokio::time::timeout(handler_timeout, handle).await.ok(),
+            None => Some(handle.await),
+        };
+        let error = match handled {
+            Some(Ok(Ok(response))) => return (response, Ok(())),
+            Some(Ok(Err(e))) => e,
+            Some(Err(payload)) => {
+                let message = panic_message(payload.as_ref());
+                error!("Handler panicked: {message}");
+                Error::HandlerPanic(message)
+            }
+            None => {
+                let error = Error::HandlerTimeout(config.handler_timeout.unwrap_or_default());
+                warn!("{error}");
+                let default = HttpResponse::new(StatusCode::GatewayTimeout)
+                    .with_content_type("text/plain")
+                    .with_body_string("The handler timed out");
+                return (error_response(StatusCode::GatewayTimeout, &error, default), Err(error));
+            }
+        };
+
+        // Answer like any other failure, without revealing panic messages to the client
+        let default_body = match &error {
+            Error::HandlerPanic(_) => "Internal server error".to_string(),
+            e => format!("Internal server error: {e}"),
+        };
+        let default = HttpResponse::new(StatusCode::InternalServerError)
+            .with_content_type("text/plain")
+            .with_body_string(default_body);
+        (error_response(StatusCode::InternalServerError, &error, default), Err(error))
+    }
+
+    /// Build the automatic answer to an OPTIONS request, listing the given methods along with
+    /// HEAD (when GET is supported) and OPTIONS in the `Allow` header.
+    fn options_response(methods: Vec<Method>) -> HttpResponse {
+        HttpResponse::new(StatusCode::NoContent).with_header("Allow", Self::allow_header(methods))
+    }
+
+    /// Build an `Allow` header value from the methods routes accept, adding HEAD for GET
+    /// routes and OPTIONS, which are answered without a route of their own.
+    fn allow_header(mut methods: Vec<Method>) -> String {
+        if methods.contains(&Method::GET) && !methods.contains(&Method::HEAD) {
+            methods.push(Method::HEAD);
+        }
+        if !methods.contains(&Method::OPTIONS) {
+            methods.push(Method::OPTIONS);
         }
 
-        Ok(())
+        let allowed_methods: Vec<String> = methods.iter().map(|m| m.to_string()).collect();
+        allowed_methods.join(", ")
+    }
+}
+
+/// Tell the client to go ahead and send the request body.
+pub(crate) async fn send_continue(socket: &mut (impl AsyncWrite + Unpin)) -> std::io::Result<()> {
+    let status = StatusCode::Continue;
+    let interim = format!("HTTP/1.1 {} {}\r\n\r\n", status as u16, status.reason_phrase());
+    socket.write_all(interim.as_bytes()).await?;
+    socket.flush().await
+}
+
+/// Add the configured `Server` header to a response, unless it already has one.
+fn with_server_header(response: HttpResponse, config: &ServerConfig) -> HttpResponse {
+    match &config.server_header {
+        Some(server) if response.get_all_headers("Server").is_empty() => response.with_header("Server", server.clone()),
+        _ => response,
+    }
+}
+
+/// Get the status of the response sent when reading a request failed with the given error, if
+/// one was sent.
+fn rejection_status(error: &Error) -> Option<StatusCode> {
+    match error {
+        Error::ParseError(e) => Some(status_and_message(e).0),
+        Error::PayloadTooLarge(_) => Some(StatusCode::PayloadTooLarge),
+        Error::RequestLineTooLong(_) => Some(StatusCode::UriTooLong),
+        Error::HeadersTooLarge(_) => Some(StatusCode::RequestHeaderFieldsTooLarge),
+        _ => None,
+    }
+}
+
+/// Choose the status and body of the response to a request that failed to parse.
+///
+/// Most errors get `400 Bad Request`; a request with too many headers gets `431 Request Header
+/// Fields Too Large`, and one for an HTTP version other than those the parser knows gets
+/// `505 HTTP Version Not Supported`. The message names the error and, where the fix isn't
+/// obvious from it, what a valid request looks like.
+pub(crate) fn status_and_message(err: &ParserError) -> (StatusCode, String) {
+    let status = match err {
+        ParserError::TooManyHeaders(_) | ParserError::HeadersTooLarge(_) => StatusCode::RequestHeaderFieldsTooLarge,
+        ParserError::InvalidVersion(version) if version.starts_with("HTTP/") => StatusCode::HttpVersionNotSupported,
+        ParserError::MultipartTooLarge(_) => StatusCode::PayloadTooLarge,
+        _ => StatusCode::BadRequest,
+    };
+    let hint = match err {
+        ParserError::EmptyRequest | ParserError::MalformedRequestLine(_) => {
+            Some("expected a method, a target and a version, such as GET / HTTP/1.1")
+        }
+        ParserError::InvalidMethod(_) => Some("methods are tokens such as GET or PROPFIND"),
+        ParserError::InvalidPath => {
+            Some("expected a path within the root such as /index.html, an absolute URL, or * for OPTIONS")
+        }
+        ParserError::MissingHeader(name) if name.eq_ignore_ascii_case("Host") => {
+            Some("HTTP/1.1 requests must name the host they are for")
+        }
+        ParserError::InvalidHeaderFormat => {
+            Some("each header line is a name and a value separated by a colon, and Content-Length is a number")
+        }
+        ParserError::AmbiguousBodyLength(_) => {
+            Some("give the body length once, with either Content-Length or Transfer-Encoding")
+        }
+        ParserError::InvalidLineEnding => Some("every line must end with CR LF"),
+        ParserError::TooManyHeaders(_) => Some("send fewer header lines"),
+        ParserError::HeadersTooLarge(_) => Some("send fewer or shorter header lines"),
+        ParserError::InvalidChunkEncoding(_) => {
+            Some("each chunk starts with its size in hexadecimal on a line of its own")
+        }
+        _ => None,
+    };
+    let message = match hint {
+        Some(hint) => format!("Error parsing request: {err} ({hint})"),
+        None => format!("Error parsing request: {err}"),
+    };
+    (status, message)
+}
+
+/// Check whether a response tells the client the connection will be closed.
+fn has_close_token(response: &HttpResponse) -> bool {
+    response
+        .get_all_headers("Connection")
+        .iter()
+        .any(|v| v.split(',').any(|t| t.trim().eq_ignore_ascii_case("close")))
+}
+
+/// What becomes of a connection after a response has been written.
+enum AfterResponse {
+    /// The connection stays open for the next request
+    KeepAlive,
+    /// The connection is closed
+    Close,
+    /// The connection is handed over to a WebSocket session
+    #[cfg(feature = "websocket")]
+    Upgrade(WebSocketUpgrade),
+}
+
+/// Check whether the client wants the connection kept open after this request.
+fn wants_keep_alive(request: &HttpRequest) -> bool {
+    let has_token = |token: &str| {
+        request
+            .get_header("Connection")
+            .is_some_and(|v| v.split(',').any(|t| t.trim().eq_ignore_ascii_case(token)))
+    };
+
+    match request.version {
+        HttpVersion::Http09 => false,
+        HttpVersion::Http10 => has_token("keep-alive"),
+        _ => !has_token("close"),
     }
-}
\ No newline at end of file
+}
+
+/// Apply the socket options of the configuration to an accepted connection.
+pub(crate) fn configure_socket(socket: &TcpStream, config: &ServerConfig) -> std::io::Result<()> {
+    socket.set_nodelay(config.tcp_nodelay)?;
+    if let Some(time) = config.tcp_keepalive {
+        socket2::SockRef::from(socket).set_tcp_keepalive(&socket2::TcpKeepalive::new().with_time(time))?;
+    }
+    Ok(())
+}
+
+/// Check whether an I/O error means the client went away, by resetting or closing the
+/// connection while the server was using it.
+fn is_disconnect(e: &std::io::Error) -> bool {
+    matches!(
+        e.kind(),
+        std::io::ErrorKind::ConnectionReset | std::io::ErrorKind::BrokenPipe | std::io::ErrorKind::UnexpectedEof
+    )
+}
+
+/// Take a connection slot, waiting up to `wait` for one to free up if they are all taken.
+///
+/// Returns None if no slot became available in time.
+pub(crate) async fn acquire_connection_permit(
+    semaphore: Arc<Semaphore>,
+    wait: Option<Duration>,
+) -> Option<OwnedSemaphorePermit> {
+    match semaphore.clone().try_acquire_owned() {
+        Ok(permit) => Some(permit),
+        Err(_) => {
+            let wait = wait?;
+            debug!("Connection limit reached, waiting up to {wait:?} for a slot");
+            tokio::time::timeout(wait, semaphore.acquire_owned()).await.ok()?.ok()
+        }
+    }
+}
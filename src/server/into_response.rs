@@ -0,0 +1,91 @@
+//! Conversion of handler return values into responses.
+
+use crate::server::error::Error;
+use crate::server::response::{HttpResponse, StatusCode};
+
+/// A value that can be sent as a response.
+///
+/// Strings become `200 OK` plain text responses, a bare [`StatusCode`] becomes an empty
+/// response with that status, and a `(StatusCode, body)` pair sets both.
+pub trait IntoResponse {
+    /// Convert the value into a response.
+    fn into_response(self) -> HttpResponse;
+}
+
+impl IntoResponse for HttpResponse {
+    fn into_response(self) -> HttpResponse {
+        self
+    }
+}
+
+impl IntoResponse for StatusCode {
+    fn into_response(self) -> HttpResponse {
+        HttpResponse::new(self).with_body_bytes(Vec::new())
+    }
+}
+
+impl IntoResponse for String {
+    fn into_response(self) -> HttpResponse {
+        (StatusCode::Ok, self).into_response()
+    }
+}
+
+impl IntoResponse for &str {
+    fn into_response(self) -> HttpResponse {
+        (StatusCode::Ok, self).into_response()
+    }
+}
+
+impl IntoResponse for (StatusCode, String) {
+    fn into_response(self) -> HttpResponse {
+        HttpResponse::new(self.0).with_content_type("text/plain").with_body_string(self.1)
+    }
+}
+
+impl IntoResponse for (StatusCode, &str) {
+    fn into_response(self) -> HttpResponse {
+        (self.0, self.1.to_string()).into_response()
+    }
+}
+
+impl<T: IntoResponse, E: IntoResponse> IntoResponse for Result<T, E> {
+    fn into_response(self) -> HttpResponse {
+        match self {
+            Ok(value) => value.into_response(),
+            Err(error) => error.into_response(),
+        }
+    }
+}
+
+/// A value a route handler can return.
+///
+/// This covers every [`IntoResponse`] type other than `Result`, plus `Result<T, Error>` for any
+/// `T: IntoResponse`, whose errors are answered with `500 Internal Server Error` and end the
+/// connection, as for any handler error. Handlers that produce a `Result` with another error
+/// type can wrap it in `Ok`, since the `Result` itself is an [`IntoResponse`].
+pub trait IntoHandlerResult {
+    /// Convert the value into the result of a handler.
+    fn into_handler_result(self) -> Result<HttpResponse, Error>;
+}
+
+impl<T: IntoResponse> IntoHandlerResult for Result<T, Error> {
+    fn into_handler_result(self) -> Result<HttpResponse, Error> {
+        self.map(IntoResponse::into_response)
+    }
+}
+
+// A blanket implementation over `IntoResponse` would make the error type of `Ok(...)` handler
+// results ambiguous, so the other types are listed one by one
+macro_rules! impl_into_handler_result {
+    ($($ty:ty),*) => {
+        $(
+            impl IntoHandlerResult for $ty {
+                fn into_handler_result(self) -> Result<HttpResponse, Error> {
+                    Ok(self.into_response())
+                }
+            }
+        )*
+    };
+}
+
+impl_into_handler_result!(HttpResponse, StatusCode, String, &str, (StatusCode, String), (StatusCode, &str));
@@ -0,0 +1,100 @@
+//! Connection and request counters for observability.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// A snapshot of the server counters, as returned by [`HttpServer::metrics`].
+///
+/// [`HttpServer::metrics`]: crate::server::HttpServer::metrics
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ServerMetrics {
+    /// The number of connections accepted since the server was created
+    pub connections_accepted: u64,
+    /// The number of connections rejected because the connection limit was reached
+    pub connections_rejected: u64,
+    /// The number of connections currently open
+    pub active_connections: u64,
+    /// The number of requests that reached routing and were answered
+    pub requests_served: u64,
+    /// The number of `1xx` responses sent
+    pub responses_1xx: u64,
+    /// The number of `2xx` responses sent
+    pub responses_2xx: u64,
+    /// The number of `3xx` responses sent
+    pub responses_3xx: u64,
+    /// The number of `4xx` responses sent
+    pub responses_4xx: u64,
+    /// The number of `5xx` responses sent
+    pub responses_5xx: u64,
+}
+
+/// The live counters of a server.
+///
+/// Every counter is a relaxed atomic, so updating them never takes a lock on the request path.
+/// Use [`Metrics::snapshot`] to read them.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    connections_accepted: AtomicU64,
+    connections_rejected: AtomicU64,
+    active_connections: AtomicU64,
+    requests_served: AtomicU64,
+    /// Responses by status class, from `1xx` to `5xx`
+    responses: [AtomicU64; 5],
+}
+
+impl Metrics {
+    /// Read the current value of every counter.
+    ///
+    /// The counters are read one at a time, so a snapshot taken while requests are in flight
+    /// may be slightly inconsistent between counters.
+    pub fn snapshot(&self) -> ServerMetrics {
+        let responses = |class: usize| self.responses[class].load(Ordering::Relaxed);
+        ServerMetrics {
+            connections_accepted: self.connections_accepted.load(Ordering::Relaxed),
+            connections_rejected: self.connections_rejected.load(Ordering::Relaxed),
+            active_connections: self.active_connections.load(Ordering::Relaxed),
+            requests_served: self.requests_served.load(Ordering::Relaxed),
+            responses_1xx: responses(0),
+            responses_2xx: responses(1),
+            responses_3xx: responses(2),
+            responses_4xx: responses(3),
+            responses_5xx: responses(4),
+        }
+    }
+
+    /// Count an accepted connection as open until the returned guard is dropped.
+    pub(crate) fn connection_opened(&self) -> ActiveConnection<'_> {
+        self.connections_accepted.fetch_add(1, Ordering::Relaxed);
+        self.active_connections.fetch_add(1, Ordering::Relaxed);
+        ActiveConnection { metrics: self }
+    }
+
+    /// Count a connection rejected at the connection limit.
+    pub(crate) fn connection_rejected(&self) {
+        self.connections_rejected.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Count a request answered with the given status code.
+    pub(crate) fn request_served(&self, status: u16) {
+        self.requests_served.fetch_add(1, Ordering::Relaxed);
+        self.response_sent(status);
+    }
+
+    /// Count a response with the given status code, including those sent without routing a
+    /// request, such as `400 Bad Request` for a malformed one.
+    pub(crate) fn response_sent(&self, status: u16) {
+        if let Some(counter) = (status / 100).checked_sub(1).and_then(|class| self.responses.get(class as usize)) {
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Keeps a connection counted as active while it is alive.
+pub(crate) struct ActiveConnection<'a> {
+    metrics: &'a Metrics,
+}
+
+impl Drop for ActiveConnection<'_> {
+    fn drop(&mut self) {
+        self.metrics.active_connections.fetch_sub(1, Ordering::Relaxed);
+    }
+}
@@ -0,0 +1,37 @@
+//! Content types inferred from file extensions.
+
+/// Get the content type for a file extension, without the leading dot.
+///
+/// The lookup is case-insensitive. Unknown extensions map to `application/octet-stream`.
+///
+/// # Examples
+///
+/// ```
+/// use microhttp_rs::mime_from_extension;
+///
+/// assert_eq!(mime_from_extension("html"), "text/html; charset=utf-8");
+/// assert_eq!(mime_from_extension("PNG"), "image/png");
+/// assert_eq!(mime_from_extension("unknown"), "application/octet-stream");
+/// ```
+pub fn mime_from_extension(ext: &str) -> &'static str {
+    match ext.to_ascii_lowercase().as_str() {
+        "html" | "htm" => "text/html; charset=utf-8",
+        "css" => "text/css; charset=utf-8",
+        "js" | "mjs" => "text/javascript; charset=utf-8",
+        "json" => "application/json",
+        "txt" => "text/plain; charset=utf-8",
+        "xml" => "application/xml",
+        "csv" => "text/csv; charset=utf-8",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "ico" => "image/x-icon",
+        "wasm" => "application/wasm",
+        "pdf" => "application/pdf",
+        "woff" => "font/woff",
+        "woff2" => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
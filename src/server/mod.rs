@@ -4,14 +4,58 @@
 //! that leverages Rust's concurrency features and the microhttp-rs parser.
 
 mod response;
+mod access_log;
+mod basic_auth;
+mod body_feed;
+mod catch_panic;
+mod cookie;
+#[cfg(feature = "compression")]
+mod compression;
+mod conditional;
 mod config;
+mod cors;
+mod date;
 mod error;
 mod handler;
+mod html;
+mod into_response;
+mod metrics;
+mod mime;
+mod problem;
+mod proxy;
+mod range;
+mod rate_limit;
+mod router;
+mod sse;
+mod stream;
+#[cfg(feature = "tls")]
+mod tls;
+#[cfg(feature = "websocket")]
+mod websocket;
 mod http_server;
 mod tests;
 
 // Re-export public items
 pub use response::{HttpResponse, StatusCode};
-pub use config::ServerConfig;
+pub use access_log::{ACCESS_LOG_TARGET, AccessLogFormat};
+pub use basic_auth::basic_auth;
+pub use cookie::{Cookie, SameSite};
+pub use config::{ServerConfig, ServerConfigBuilder};
+pub use cors::CorsConfig;
 pub use error::Error;
-pub use http_server::HttpServer;
+pub use handler::{ErrorHandlerFn, HandlerFn, HandlerFuture, MiddlewareFn, Next};
+pub use metrics::{Metrics, ServerMetrics};
+pub use into_response::{IntoHandlerResult, IntoResponse};
+pub use html::html_escape;
+pub use mime::mime_from_extension;
+pub use problem::Problem;
+pub use proxy::proxy_to;
+pub use rate_limit::rate_limit;
+pub use router::{MatchResult, Router};
+pub use sse::{SseEvent, SseResponse};
+pub use stream::BodyStream;
+#[cfg(feature = "tls")]
+pub use tls::{load_tls_config, tls_config_from_pem};
+#[cfg(feature = "websocket")]
+pub use websocket::{WebSocket, WebSocketMessage, is_websocket_upgrade, websocket_upgrade};
+pub use http_server::{BoundServer, HttpServer};
@@ -0,0 +1,101 @@
+//! Problem details (RFC 7807) error responses.
+
+use serde_json::{Map, Value};
+
+use crate::server::into_response::IntoResponse;
+use crate::server::response::{HttpResponse, StatusCode};
+
+/// A problem details object, sent as an `application/problem+json` response.
+///
+/// The `type` member defaults to `about:blank`, meaning the problem is described by its
+/// status code alone. Extension members are sent alongside the standard ones; when an
+/// extension has the name of a standard member, the standard member wins.
+///
+/// # Examples
+///
+/// ```
+/// use microhttp_rs::{IntoResponse, Problem, StatusCode};
+///
+/// let response = Problem::new(StatusCode::Forbidden, "Out of credit")
+///     .with_type("https://example.com/probs/out-of-credit")
+///     .with_detail("Your balance is 30, but that costs 50.")
+///     .with_extension("balance", 30)
+///     .into_response();
+/// assert_eq!(response.get_all_headers("Content-Type"), vec!["application/problem+json"]);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Problem {
+    /// A URI reference identifying the problem type
+    pub problem_type: String,
+    /// A short summary of the problem type
+    pub title: String,
+    /// The status code of the response
+    pub status: StatusCode,
+    /// An explanation of this occurrence of the problem
+    pub detail: Option<String>,
+    /// A URI reference identifying this occurrence of the problem
+    pub instance: Option<String>,
+    /// Additional members describing the problem
+    pub extensions: Map<String, Value>,
+}
+
+impl Problem {
+    /// Create a problem with the given status and title, of type `about:blank`.
+    pub fn new(status: StatusCode, title: impl Into<String>) -> Self {
+        Self {
+            problem_type: "about:blank".to_string(),
+            title: title.into(),
+            status,
+            detail: None,
+            instance: None,
+            extensions: Map::new(),
+        }
+    }
+
+    /// Set the problem type URI.
+    pub fn with_type(mut self, problem_type: impl Into<String>) -> Self {
+        self.problem_type = problem_type.into();
+        self
+    }
+
+    /// Set the explanation of this occurrence of the problem.
+    pub fn with_detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Set the URI identifying this occurrence of the problem.
+    pub fn with_instance(mut self, instance: impl Into<String>) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Add an extension member.
+    pub fn with_extension(mut self, name: impl Into<String>, value: impl Into<Value>) -> Self {
+        self.extensions.insert(name.into(), value.into());
+        self
+    }
+
+    /// Get the problem as a JSON object.
+    pub fn to_json(&self) -> Value {
+        let mut members = self.extensions.clone();
+        members.insert("type".to_string(), self.problem_type.clone().into());
+        members.insert("title".to_string(), self.title.clone().into());
+        members.insert("status".to_string(), (self.status as u16).into());
+        if let Some(detail) = &self.detail {
+            members.insert("detail".to_string(), detail.clone().into());
+        }
+        if let Some(instance) = &self.instance {
+            members.insert("instance".to_string(), instance.clone().into());
+        }
+        Value::Object(members)
+    }
+}
+
+impl IntoResponse for Problem {
+    fn into_response(self) -> HttpResponse {
+        HttpResponse::new(self.status)
+            .with_content_type("application/problem+json")
+            .with_body_bytes(self.to_json().to_string())
+    }
+}
@@ -0,0 +1,119 @@
+//! A reverse proxy handler that forwards requests to an upstream server.
+
+use std::sync::Arc;
+
+use log::warn;
+
+use crate::client;
+use crate::parser::{HttpRequest, HttpResponseParsed, HttpVersion, remove_hop_by_hop_headers};
+use crate::server::handler::HandlerFuture;
+use crate::server::response::{HttpResponse, StatusCode};
+
+/// Build a handler that forwards requests to an upstream server and answers with its
+/// response.
+///
+/// `upstream` is the address of the server, such as `127.0.0.1:9000` or
+/// `http://backend:9000/api`; a path after the address is put before the path of every
+/// forwarded request. The method, path with query string, headers and body of the request are
+/// forwarded, except for hop-by-hop headers such as `Connection` and `Transfer-Encoding`, and
+/// the client address is added to `X-Forwarded-For`. Upstream servers that can't be reached,
+/// or answer with a malformed response, get the client a `502 Bad Gateway`.
+///
+/// Bodies are buffered in both directions, and only plain HTTP upstreams are supported.
+///
+/// # Examples
+///
+/// ```
+/// use microhttp_rs::{HttpServer, Method, ServerConfig, proxy_to};
+///
+/// # async fn example() {
+/// let server = HttpServer::new(ServerConfig::default());
+/// server.add_route("/api/*path", vec![Method::GET, Method::POST], proxy_to("127.0.0.1:9000")).await;
+/// # }
+/// ```
+pub fn proxy_to(upstream: &str) -> impl Fn(HttpRequest) -> HandlerFuture + Send + Sync + 'static {
+    let upstream = upstream.strip_prefix("http://").unwrap_or(upstream);
+    let (addr, prefix) = match upstream.split_once('/') {
+        Some((addr, prefix)) => (addr, format!("/{}", prefix.trim_end_matches('/'))),
+        None => (upstream, String::new()),
+    };
+    let addr: Arc<str> = addr.into();
+    let prefix: Arc<str> = prefix.trim_end_matches('/').into();
+
+    move |req: HttpRequest| -> HandlerFuture {
+        let addr = addr.clone();
+        let upstream_request = upstream_request(&req, &addr, &prefix);
+        Box::pin(async move {
+            let response = match client::send(&*addr, &upstream_request).await {
+                Ok(response) => response,
+                Err(e) => {
+                    warn!("Proxying {} to {addr} failed: {e}", upstream_request.path);
+                    return Ok(HttpResponse::new(StatusCode::BadGateway)
+                        .with_content_type("text/plain")
+                        .with_body_string("Bad gateway"));
+                }
+            };
+            Ok(downstream_response(response))
+        })
+    }
+}
+
+/// Build the request sent upstream for a request from a client.
+fn upstream_request(req: &HttpRequest, addr: &str, prefix: &str) -> HttpRequest {
+    let mut upstream = HttpRequest::new(
+        req.method.clone(),
+        format!("{prefix}{}", req.path),
+        HttpVersion::Http11,
+        Default::default(),
+    );
+    let mut headers = req.headers.clone();
+    remove_hop_by_hop_headers(headers.entries_mut());
+    for (name, value) in headers {
+        // The body is sent with a Content-Length of its own
+        if !name.eq_ignore_ascii_case("Content-Length") && !name.eq_ignore_ascii_case("X-Forwarded-For") {
+            upstream.append_header(name, value);
+        }
+    }
+    // HTTP/1.0 clients may leave out the Host header, which HTTP/1.1 requires
+    if upstream.get_header("Host").is_none() {
+        upstream.append_header("Host", addr);
+    }
+
+    // Earlier proxies may have listed addresses already
+    let forwarded_for: Vec<String> = req
+        .get_all_headers("X-Forwarded-For")
+        .into_iter()
+        .cloned()
+        .chain(req.peer_addr.map(|addr| addr.ip().to_string()))
+        .collect();
+    if !forwarded_for.is_empty() {
+        upstream.append_header("X-Forwarded-For", forwarded_for.join(", "));
+    }
+
+    if !req.body.is_empty() || req.has_header("Content-Length") || req.is_chunked() {
+        upstream.append_header("Content-Length", req.body.len().to_string());
+    }
+    upstream.body = req.body.clone();
+    upstream
+}
+
+/// Build the response sent to the client for a response from upstream.
+fn downstream_response(mut response: HttpResponseParsed) -> HttpResponse {
+    let mut downstream = HttpResponse::new(StatusCode::Ok).with_status_code(response.status, &response.reason);
+    let mut seen: Vec<String> = Vec::new();
+    remove_hop_by_hop_headers(&mut response.headers);
+    for (name, value) in response.headers {
+        // The length is computed again when the response is written
+        if name.eq_ignore_ascii_case("Content-Length") {
+            continue;
+        }
+        // Repeated headers such as Set-Cookie are sent once per value
+        downstream = if seen.iter().any(|s| s.eq_ignore_ascii_case(&name)) {
+            downstream.with_added_header(name, value)
+        } else {
+            seen.push(name.clone());
+            downstream.with_header(name, value)
+        };
+    }
+    downstream.with_body_bytes(response.body)
+}
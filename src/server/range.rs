@@ -0,0 +1,107 @@
+//! Byte range requests, answered with `206 Partial Content` (RFC 9110 section 14).
+
+use crate::server::response::{HttpResponse, StatusCode};
+
+/// A byte range requested in a `Range` header, before it is resolved against a body length.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ByteRange {
+    /// `bytes=first-last`, with `last` inclusive
+    Bounded(u64, u64),
+    /// `bytes=first-`, up to the end of the body
+    From(u64),
+    /// `bytes=-length`, the last `length` bytes of the body
+    Suffix(u64),
+}
+
+impl ByteRange {
+    /// Parse a `Range` header holding a single byte range.
+    ///
+    /// Returns None for other units, several ranges, and malformed values, which are all
+    /// answered with the full body.
+    pub(crate) fn parse(value: &str) -> Option<ByteRange> {
+        let spec = value.trim().strip_prefix("bytes=")?.trim();
+        let (first, last) = spec.split_once('-')?;
+        let number = |s: &str| -> Option<u64> {
+            if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+                return None;
+            }
+            s.parse().ok()
+        };
+
+        match (first, last) {
+            ("", length) => number(length).map(ByteRange::Suffix),
+            (first, "") => number(first).map(ByteRange::From),
+            (first, last) => {
+                let (first, last) = (number(first)?, number(last)?);
+                (first <= last).then_some(ByteRange::Bounded(first, last))
+            }
+        }
+    }
+
+    /// Resolve the range against a body length, giving the inclusive first and last byte
+    /// positions, or None if it is not satisfiable.
+    pub(crate) fn resolve(self, length: u64) -> Option<(u64, u64)> {
+        match self {
+            ByteRange::Bounded(first, last) if first < length => Some((first, last.min(length - 1))),
+            ByteRange::From(first) if first < length => Some((first, length - 1)),
+            ByteRange::Suffix(suffix) if suffix > 0 && length > 0 => Some((length.saturating_sub(suffix), length - 1)),
+            _ => None,
+        }
+    }
+}
+
+/// Answer a `Range` request for a `200 OK` response with the requested part of its body.
+///
+/// Satisfiable ranges give `206 Partial Content` with a `Content-Range` header, and ranges
+/// past the end of the body give `416 Range Not Satisfiable`. The range is ignored, and the
+/// full response sent, if `If-Range` doesn't match the response's `ETag` or `Last-Modified`
+/// header, or if the response is streamed, already encoded or sets `Accept-Ranges: none`.
+/// Every eligible `200 OK` response gets `Accept-Ranges: bytes`.
+pub(crate) fn apply_range(response: HttpResponse, range: Option<&str>, if_range: Option<&str>) -> HttpResponse {
+    if response.status_code() != 200
+        || response.stream.is_some()
+        || !response.get_all_headers("Content-Encoding").is_empty()
+        || response.get_all_headers("Accept-Ranges").iter().any(|v| v.trim().eq_ignore_ascii_case("none"))
+    {
+        return response;
+    }
+    let response = response.with_header("Accept-Ranges", "bytes");
+
+    let Some(range) = range.and_then(ByteRange::parse) else {
+        return response;
+    };
+    if let Some(if_range) = if_range {
+        // Only strong entity tags and exact dates validate a range
+        let if_range = if_range.trim();
+        let matches = |name: &str| response.get_all_headers(name).iter().any(|v| v.as_str() == if_range);
+        let is_current = match if_range {
+            _ if if_range.starts_with("W/") => false,
+            _ if if_range.starts_with('"') => matches("ETag"),
+            _ => matches("Last-Modified"),
+        };
+        if !is_current {
+            return response;
+        }
+    }
+
+    let length = response.body.len() as u64;
+    match range.resolve(length) {
+        Some((first, last)) => {
+            let body = response.body[first as usize..=last as usize].to_vec();
+            let mut response = response
+                .with_header("Content-Range", format!("bytes {first}-{last}/{length}"))
+                .with_body_bytes(body);
+            response.status = StatusCode::PartialContent;
+            response.custom_status = None;
+            response
+        }
+        None => {
+            let mut response = response
+                .with_header("Content-Range", format!("bytes */{length}"))
+                .with_body_bytes(Vec::new());
+            response.status = StatusCode::RangeNotSatisfiable;
+            response.custom_status = None;
+            response
+        }
+    }
+}
@@ -0,0 +1,107 @@
+//! A fixed-window rate limiting middleware keyed by client IP address.
+
+use std::collections::HashMap;
+use std::hash::{BuildHasher, RandomState};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::parser::HttpRequest;
+use crate::server::handler::{HandlerFuture, Next};
+use crate::server::response::{HttpResponse, StatusCode};
+
+/// The number of independently locked shards the clients are spread over, so concurrent
+/// connections rarely wait on each other.
+const SHARDS: usize = 16;
+
+/// The number of clients a shard holds before clients whose window has ended are dropped.
+const PRUNE_THRESHOLD: usize = 1024;
+
+/// Build a middleware that lets each client IP address make at most `max_requests` requests
+/// per `window`.
+///
+/// Windows start with a client's first request. Further requests within the window are
+/// answered with `429 Too Many Requests` and a `Retry-After` header giving the seconds until
+/// the window ends. Requests whose peer address is unknown share a single allowance.
+///
+/// # Panics
+///
+/// Panics if `window` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use microhttp_rs::{Router, rate_limit};
+///
+/// let mut api = Router::new();
+/// api.add_middleware(rate_limit(100, Duration::from_secs(60)));
+/// ```
+pub fn rate_limit(max_requests: u32, window: Duration) -> impl Fn(HttpRequest, Next) -> HandlerFuture + Send + Sync + 'static {
+    let limiter = Arc::new(RateLimiter::new(max_requests, window));
+    move |req: HttpRequest, next: Next| -> HandlerFuture {
+        let client = req.peer_addr.map(|addr| addr.ip());
+        match limiter.check(client, Instant::now()) {
+            Ok(()) => next.run(req),
+            Err(retry_after) => {
+                // Round up, so clients retrying on time find the window over
+                let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+                let response = HttpResponse::new(StatusCode::TooManyRequests)
+                    .with_header("Retry-After", seconds.max(1).to_string())
+                    .with_content_type("text/plain")
+                    .with_body_string("Too many requests");
+                Box::pin(async move { Ok(response) })
+            }
+        }
+    }
+}
+
+/// The request count of a client in its current window.
+struct Window {
+    start: Instant,
+    count: u32,
+}
+
+/// Fixed-window request counters, sharded by client.
+pub(crate) struct RateLimiter {
+    max_requests: u32,
+    window: Duration,
+    hasher: RandomState,
+    shards: Vec<Mutex<HashMap<Option<IpAddr>, Window>>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(max_requests: u32, window: Duration) -> Self {
+        assert!(!window.is_zero(), "Rate limit window must not be zero");
+        Self {
+            max_requests,
+            window,
+            hasher: RandomState::new(),
+            shards: (0..SHARDS).map(|_| Mutex::new(HashMap::new())).collect(),
+        }
+    }
+
+    /// Count a request from a client at the given time.
+    ///
+    /// Returns how long until the client's window ends if the request is over the limit.
+    pub(crate) fn check(&self, client: Option<IpAddr>, now: Instant) -> Result<(), Duration> {
+        let shard = &self.shards[self.hasher.hash_one(client) as usize % SHARDS];
+        // The lock is only held to update a counter, and a panic can't leave one inconsistent
+        let mut windows = shard.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if windows.len() >= PRUNE_THRESHOLD && !windows.contains_key(&client) {
+            windows.retain(|_, w| now.duration_since(w.start) < self.window);
+        }
+
+        let window = windows.entry(client).or_insert(Window { start: now, count: 0 });
+        let elapsed = now.duration_since(window.start);
+        if elapsed >= self.window {
+            *window = Window { start: now, count: 0 };
+        }
+        if window.count >= self.max_requests {
+            return Err(self.window - now.duration_since(window.start));
+        }
+        window.count += 1;
+        Ok(())
+    }
+}
@@ -1,13 +1,29 @@
 //! HTTP response types and utilities.
 
-use std::collections::HashMap;
+use std::path::Path;
+use std::time::SystemTime;
+use log::warn;
+use tokio::sync::mpsc;
 use serde::Serialize;
 
+use crate::parser::{HeaderMap, is_token, remove_hop_by_hop_headers};
+use crate::server::cookie::Cookie;
+use crate::server::date::http_date;
 use crate::server::error::Error;
+use crate::server::into_response::IntoResponse;
+use crate::server::mime::mime_from_extension;
+use crate::server::problem::Problem;
+use crate::server::stream::BodyStream;
+#[cfg(feature = "websocket")]
+use crate::server::websocket::WebSocketUpgrade;
 
 /// HTTP status codes with their standard reason phrases.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum StatusCode {
+    /// 100 Continue: The client should send the request body.
+    Continue = 100,
+    /// 101 Switching Protocols: The connection switches to the protocol in the Upgrade header.
+    SwitchingProtocols = 101,
     /// 200 OK: The request has succeeded.
     Ok = 200,
     /// 201 Created: The request has been fulfilled and a new resource has been created.
@@ -16,6 +32,20 @@ pub enum StatusCode {
     Accepted = 202,
     /// 204 No Content: The server has fulfilled the request but does not need to return a response body.
     NoContent = 204,
+    /// 206 Partial Content: The response holds the requested byte ranges of the resource.
+    PartialContent = 206,
+    /// 301 Moved Permanently: The resource has been permanently moved to the URL in the Location header.
+    MovedPermanently = 301,
+    /// 302 Found: The resource temporarily resides at the URL in the Location header.
+    Found = 302,
+    /// 303 See Other: The response can be found at the URL in the Location header using GET.
+    SeeOther = 303,
+    /// 304 Not Modified: The cached representation the client holds is still current.
+    NotModified = 304,
+    /// 307 Temporary Redirect: Like 302, but the request method must not be changed.
+    TemporaryRedirect = 307,
+    /// 308 Permanent Redirect: Like 301, but the request method must not be changed.
+    PermanentRedirect = 308,
     /// 400 Bad Request: The server cannot process the request due to a client error.
     BadRequest = 400,
     /// 401 Unauthorized: Authentication is required and has failed or has not been provided.
@@ -26,6 +56,24 @@ pub enum StatusCode {
     NotFound = 404,
     /// 405 Method Not Allowed: The request method is not supported for the requested resource.
     MethodNotAllowed = 405,
+    /// 408 Request Timeout: The server timed out waiting for the request.
+    RequestTimeout = 408,
+    /// 409 Conflict: The request conflicts with the current state of the resource.
+    Conflict = 409,
+    /// 410 Gone: The resource is no longer available and will not be available again.
+    Gone = 410,
+    /// 413 Payload Too Large: The request body is larger than the server is willing to process.
+    PayloadTooLarge = 413,
+    /// 414 URI Too Long: The request target is longer than the server is willing to interpret.
+    UriTooLong = 414,
+    /// 416 Range Not Satisfiable: None of the requested byte ranges overlap the resource.
+    RangeNotSatisfiable = 416,
+    /// 422 Unprocessable Content: The request is well-formed but its content cannot be processed.
+    UnprocessableEntity = 422,
+    /// 429 Too Many Requests: The client has sent too many requests in a given amount of time.
+    TooManyRequests = 429,
+    /// 431 Request Header Fields Too Large: The request headers are larger than the server is willing to process.
+    RequestHeaderFieldsTooLarge = 431,
     /// 500 Internal Server Error: The server encountered an unexpected condition.
     InternalServerError = 500,
     /// 501 Not Implemented: The server does not support the functionality required to fulfill the request.
@@ -34,27 +82,103 @@ pub enum StatusCode {
     BadGateway = 502,
     /// 503 Service Unavailable: The server is currently unable to handle the request.
     ServiceUnavailable = 503,
+    /// 504 Gateway Timeout: The server did not receive a timely response from an upstream server.
+    GatewayTimeout = 504,
+    /// 505 HTTP Version Not Supported: The server does not support the HTTP version of the request.
+    HttpVersionNotSupported = 505,
 }
 
 impl StatusCode {
+    /// Get the status code for a numeric code, if it is one of the known codes.
+    pub fn from_u16(code: u16) -> Option<StatusCode> {
+        match code {
+            100 => Some(StatusCode::Continue),
+            101 => Some(StatusCode::SwitchingProtocols),
+            200 => Some(StatusCode::Ok),
+            201 => Some(StatusCode::Created),
+            202 => Some(StatusCode::Accepted),
+            204 => Some(StatusCode::NoContent),
+            206 => Some(StatusCode::PartialContent),
+            301 => Some(StatusCode::MovedPermanently),
+            302 => Some(StatusCode::Found),
+            303 => Some(StatusCode::SeeOther),
+            304 => Some(StatusCode::NotModified),
+            307 => Some(StatusCode::TemporaryRedirect),
+            308 => Some(StatusCode::PermanentRedirect),
+            400 => Some(StatusCode::BadRequest),
+            401 => Some(StatusCode::Unauthorized),
+            403 => Some(StatusCode::Forbidden),
+            404 => Some(StatusCode::NotFound),
+            405 => Some(StatusCode::MethodNotAllowed),
+            408 => Some(StatusCode::RequestTimeout),
+            409 => Some(StatusCode::Conflict),
+            410 => Some(StatusCode::Gone),
+            413 => Some(StatusCode::PayloadTooLarge),
+            414 => Some(StatusCode::UriTooLong),
+            416 => Some(StatusCode::RangeNotSatisfiable),
+            422 => Some(StatusCode::UnprocessableEntity),
+            429 => Some(StatusCode::TooManyRequests),
+            431 => Some(StatusCode::RequestHeaderFieldsTooLarge),
+            500 => Some(StatusCode::InternalServerError),
+            501 => Some(StatusCode::NotImplemented),
+            502 => Some(StatusCode::BadGateway),
+            503 => Some(StatusCode::ServiceUnavailable),
+            504 => Some(StatusCode::GatewayTimeout),
+            505 => Some(StatusCode::HttpVersionNotSupported),
+            _ => None,
+        }
+    }
+
     /// Get the reason phrase for this status code.
     pub fn reason_phrase(&self) -> &'static str {
         match self {
+            StatusCode::Continue => "Continue",
+            StatusCode::SwitchingProtocols => "Switching Protocols",
             StatusCode::Ok => "OK",
             StatusCode::Created => "Created",
             StatusCode::Accepted => "Accepted",
             StatusCode::NoContent => "No Content",
+            StatusCode::PartialContent => "Partial Content",
+            StatusCode::MovedPermanently => "Moved Permanently",
+            StatusCode::Found => "Found",
+            StatusCode::SeeOther => "See Other",
+            StatusCode::NotModified => "Not Modified",
+            StatusCode::TemporaryRedirect => "Temporary Redirect",
+            StatusCode::PermanentRedirect => "Permanent Redirect",
             StatusCode::BadRequest => "Bad Request",
             StatusCode::Unauthorized => "Unauthorized",
             StatusCode::Forbidden => "Forbidden",
             StatusCode::NotFound => "Not Found",
             StatusCode::MethodNotAllowed => "Method Not Allowed",
+            StatusCode::RequestTimeout => "Request Timeout",
+            StatusCode::Conflict => "Conflict",
+            StatusCode::Gone => "Gone",
+            StatusCode::PayloadTooLarge => "Payload Too Large",
+            StatusCode::UriTooLong => "URI Too Long",
+            StatusCode::RangeNotSatisfiable => "Range Not Satisfiable",
+            StatusCode::UnprocessableEntity => "Unprocessable Content",
+            StatusCode::TooManyRequests => "Too Many Requests",
+            StatusCode::RequestHeaderFieldsTooLarge => "Request Header Fields Too Large",
             StatusCode::InternalServerError => "Internal Server Error",
             StatusCode::NotImplemented => "Not Implemented",
             StatusCode::BadGateway => "Bad Gateway",
             StatusCode::ServiceUnavailable => "Service Unavailable",
+            StatusCode::GatewayTimeout => "Gateway Timeout",
+            StatusCode::HttpVersionNotSupported => "HTTP Version Not Supported",
         }
     }
+
+    /// Check if this is a redirection status code that uses the Location header.
+    pub fn is_redirection(&self) -> bool {
+        matches!(
+            self,
+            StatusCode::MovedPermanently
+                | StatusCode::Found
+                | StatusCode::SeeOther
+                | StatusCode::TemporaryRedirect
+                | StatusCode::PermanentRedirect
+        )
+    }
 }
 
 /// Represents an HTTP response.
@@ -62,25 +186,112 @@ impl StatusCode {
 pub struct HttpResponse {
     /// The HTTP status code
     pub status: StatusCode,
-    /// The HTTP headers
-    pub headers: HashMap<String, String>,
+    /// A numeric status code and reason phrase that override `status`, for codes outside the
+    /// known set
+    pub custom_status: Option<(u16, String)>,
+    /// The HTTP headers, sent in order with one line per value
+    pub headers: HeaderMap,
     /// The response body
     pub body: Vec<u8>,
+    /// A body sent in chunks as they arrive, instead of `body`
+    pub stream: Option<BodyStream>,
+    /// Trailer fields sent after the final chunk of a chunked `stream`; ignored for other
+    /// responses
+    pub trailers: Vec<(String, String)>,
+    /// The session run on the connection after a `101 Switching Protocols` response
+    #[cfg(feature = "websocket")]
+    pub(crate) upgrade: Option<WebSocketUpgrade>,
 }
 
 impl HttpResponse {
     /// Create a new HTTP response with the given status code.
     pub fn new(status: StatusCode) -> Self {
-        let mut headers = HashMap::new();
-        headers.insert("Server".to_string(), "microhttp-rs".to_string());
-
         Self {
             status,
-            headers,
+            custom_status: None,
+            headers: HeaderMap::new(),
             body: Vec::new(),
+            stream: None,
+            trailers: Vec::new(),
+            #[cfg(feature = "websocket")]
+            upgrade: None,
+        }
+    }
+
+    /// Create a redirect response to the given location.
+    ///
+    /// The response has a `Location` header and an empty body. The status should be one of the
+    /// redirection codes (301, 302, 303, 307 or 308); other codes trigger a debug assertion.
+    pub fn redirect(status: StatusCode, location: impl Into<String>) -> Self {
+        debug_assert!(status.is_redirection(), "{status:?} is not a redirection status code");
+        Self::new(status)
+            .with_header("Location", location)
+            .with_body_bytes(Vec::new())
+    }
+
+    /// Create a JSON error response such as `{"error":"No such user","status":404}`.
+    pub fn json_error(status: StatusCode, message: impl Into<String>) -> Self {
+        let body = serde_json::json!({
+            "error": message.into(),
+            "status": status as u16,
+        });
+        Self::new(status)
+            .with_content_type("application/json")
+            .with_body_bytes(body.to_string())
+    }
+
+    /// Create a problem details response (RFC 7807) with an `application/problem+json` body
+    /// such as `{"type":"about:blank","title":"Not Found","status":404,"detail":"No such user"}`.
+    ///
+    /// Use [`Problem`] to set the type, an instance or extension members.
+    pub fn problem(status: StatusCode, title: impl Into<String>, detail: impl Into<String>) -> Self {
+        Problem::new(status, title).with_detail(detail).into_response()
+    }
+
+    /// Set an arbitrary numeric status code and reason phrase.
+    ///
+    /// Known codes also update `status`, so `with_status_code(404, "Not Found")` is equivalent
+    /// to using [`StatusCode::NotFound`]; other codes such as 418 are sent exactly as given.
+    ///
+    /// Only three-digit codes can be sent, so a code outside `100..=999` leaves the response
+    /// unchanged and logs a warning. Control characters such as CR or LF in the reason, which
+    /// could split the response, are replaced with spaces.
+    pub fn with_status_code(mut self, code: u16, reason: &str) -> Self {
+        if !(100..=999).contains(&code) {
+            warn!("Ignoring status code {code}, which is not a three-digit code");
+            return self;
+        }
+        if let Some(status) = StatusCode::from_u16(code) {
+            self.status = status;
+        }
+        self.custom_status = Some((code, reason.replace(is_forbidden_in_value, " ")));
+        self
+    }
+
+    /// Get the numeric status code that will be sent.
+    ///
+    /// A `custom_status` whose code is outside `100..=999` can't be sent, so `status` is used
+    /// instead.
+    pub fn status_code(&self) -> u16 {
+        match self.sendable_custom_status() {
+            Some((code, _)) => *code,
+            None => self.status as u16,
+        }
+    }
+
+    /// Get the reason phrase that will be sent, before control characters are replaced.
+    pub fn reason_phrase(&self) -> &str {
+        match self.sendable_custom_status() {
+            Some((_, reason)) => reason,
+            None => self.status.reason_phrase(),
         }
     }
 
+    /// Get the custom status, if its code can be sent.
+    fn sendable_custom_status(&self) -> Option<&(u16, String)> {
+        self.custom_status.as_ref().filter(|(code, _)| (100..=999).contains(code))
+    }
+
     /// Set the response body with a string.
     pub fn with_body_string(mut self, body: impl Into<String>) -> Self {
         let body_string = body.into();
@@ -96,17 +307,131 @@ impl HttpResponse {
         self.with_header("Content-Length", content_length)
     }
 
+    /// Stream the response body from a channel, sending each chunk as soon as it arrives.
+    ///
+    /// The body is sent with `Transfer-Encoding: chunked` (or unframed to HTTP/1.0 clients)
+    /// and ends when every sender is dropped, after which the connection is closed. If the
+    /// client disconnects first, the receiver is dropped and further sends fail.
+    pub fn with_body_stream(mut self, receiver: mpsc::Receiver<Vec<u8>>) -> Self {
+        self.headers.remove("Content-Length");
+        self.body.clear();
+        self.stream = Some(BodyStream::new(receiver));
+        self.with_header("Transfer-Encoding", "chunked").with_header("Connection", "close")
+    }
+
+    /// Add a trailer field, sent after the final chunk of a streamed body, such as the
+    /// `grpc-status` of gRPC-Web.
+    ///
+    /// The names of the trailers are announced in the `Trailer` header. Trailers are only
+    /// sent with `Transfer-Encoding: chunked`, so they are dropped from responses that are not
+    /// streamed and from streamed responses to HTTP/1.0 clients. Framing and routing fields
+    /// such as `Content-Length` must not be sent as trailers.
+    pub fn with_trailer(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.trailers.push((name.into(), value.into()));
+        self
+    }
+
     /// Add or replace a header.
+    ///
+    /// Any values previously set for the same name, including those added with
+    /// [`HttpResponse::with_added_header`], are replaced; the header keeps the position of the
+    /// first of them.
     pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
-        self.headers.insert(name.into(), value.into());
+        self.headers.insert(name, value);
         self
     }
 
+    /// Add or replace a header, checking that it can be sent safely.
+    ///
+    /// Use this instead of [`HttpResponse::with_header`] when the name or value comes from user
+    /// input. Unchecked headers are still sanitized when the response is written, but silently.
+    ///
+    /// # Returns
+    ///
+    /// The response, or an InvalidHeader error if the name is not a valid token or the value
+    /// contains control characters such as CR or LF, which could split the response
+    pub fn try_with_header(self, name: impl Into<String>, value: impl Into<String>) -> Result<Self, Error> {
+        let (name, value) = (name.into(), value.into());
+        validate_header(&name, &value)?;
+        Ok(self.with_header(name, value))
+    }
+
+    /// Add a header value, keeping any existing values for the same name, and checking that it
+    /// can be sent safely like [`HttpResponse::try_with_header`] does.
+    pub fn try_with_added_header(self, name: impl Into<String>, value: impl Into<String>) -> Result<Self, Error> {
+        let (name, value) = (name.into(), value.into());
+        validate_header(&name, &value)?;
+        Ok(self.with_added_header(name, value))
+    }
+
+    /// Add a header value, keeping any existing values for the same name.
+    ///
+    /// Each value is sent on its own header line.
+    pub fn with_added_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.append(name, value);
+        self
+    }
+
+    /// Remove the hop-by-hop headers, including any named in `Connection`.
+    ///
+    /// See [`remove_hop_by_hop_headers`](crate::parser::remove_hop_by_hop_headers).
+    pub fn remove_hop_by_hop_headers(&mut self) {
+        remove_hop_by_hop_headers(self.headers.entries_mut());
+    }
+
+    /// Get a header value; when a header is repeated, the last value is returned.
+    ///
+    /// The lookup is case-insensitive.
+    pub fn get_header(&self, name: &str) -> Option<&String> {
+        self.headers.get(name)
+    }
+
+    /// Get all values of a header, in the order they will be sent.
+    ///
+    /// The lookup is case-insensitive.
+    pub fn get_all_headers(&self, name: &str) -> Vec<&String> {
+        self.headers.get_all(name)
+    }
+
+    /// Add a cookie, sent in its own Set-Cookie header.
+    pub fn with_cookie(self, cookie: Cookie) -> Self {
+        self.with_added_header("Set-Cookie", cookie.to_string())
+    }
+
     /// Set the content type.
     pub fn with_content_type(self, content_type: impl Into<String>) -> Self {
         self.with_header("Content-Type", content_type)
     }
 
+    /// Set the response body to an HTML document, sent as `text/html; charset=utf-8`.
+    ///
+    /// Text from users should be escaped with [`html_escape`] before it is put in the body.
+    ///
+    /// [`html_escape`]: crate::server::html_escape
+    pub fn with_html(self, body: impl Into<String>) -> Self {
+        self.with_content_type("text/html; charset=utf-8").with_body_string(body)
+    }
+
+    /// Set the entity tag, sent in the `ETag` header.
+    ///
+    /// The tag is quoted unless it already is, so `with_etag("v1")` sends `ETag: "v1"`; weak
+    /// tags such as `W/"v1"` are sent as given. GET and HEAD requests whose `If-None-Match`
+    /// header matches the tag are answered with `304 Not Modified`.
+    pub fn with_etag(self, etag: impl Into<String>) -> Self {
+        let etag = etag.into();
+        let is_quoted = etag.trim_start_matches("W/").starts_with('"') && etag.len() > 1 && etag.ends_with('"');
+        let etag = if is_quoted { etag } else { format!("\"{etag}\"") };
+        self.with_header("ETag", etag)
+    }
+
+    /// Set the time the resource was last modified, sent in the `Last-Modified` header.
+    ///
+    /// GET and HEAD requests with an `If-Modified-Since` header at or after this time are
+    /// answered with `304 Not Modified`.
+    pub fn with_last_modified(self, time: SystemTime) -> Self {
+        self.with_header("Last-Modified", http_date(time))
+    }
+
     /// Set the response body with a JSON value.
     ///
     /// This method serializes the provided value to JSON and sets it as the response body.
@@ -117,26 +442,174 @@ impl HttpResponse {
             .with_body_bytes(json))
     }
 
+    /// Set the response body to the contents of a file, with the content type inferred from
+    /// its extension.
+    ///
+    /// Files without an extension are sent as `application/octet-stream`. The file is read
+    /// in full before the response is built, and its modification time, where the platform
+    /// provides one, is sent as `Last-Modified`.
+    pub fn with_body_file(self, path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let body = std::fs::read(path)?;
+        let content_type = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map_or("application/octet-stream", mime_from_extension);
+        let response = self.with_content_type(content_type).with_body_bytes(body);
+        Ok(match std::fs::metadata(path).and_then(|metadata| metadata.modified()) {
+            Ok(modified) => response.with_last_modified(modified),
+            Err(_) => response,
+        })
+    }
+
     /// Convert the response to bytes.
+    ///
+    /// The `Content-Length` header is computed from the body, as described for
+    /// [`HttpResponse::to_head_bytes`].
     pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.to_head_bytes();
+
+        // Add the body
+        bytes.extend_from_slice(&self.body);
+
+        bytes
+    }
+
+    /// Convert the status line and headers of the response to bytes, without the body.
+    ///
+    /// This is what gets sent in reply to a HEAD request; the headers, including
+    /// `Content-Length`, are the same as for the full response.
+    ///
+    /// Unless the body is streamed or the status can't have a body (`1xx`, `204` and `304`),
+    /// `Content-Length` is always the length of `body`, replacing any value set among the
+    /// headers, so changing the body after setting headers can't leave a stale length. A
+    /// `Content-Length` header that disagrees with the body is logged as a warning. `1xx` and
+    /// `204` responses are sent without `Content-Length`, even if one was set.
+    ///
+    /// Headers are written in the order they were first set, with one line per value of a
+    /// repeated header, followed by the computed `Content-Length` and then `Trailer`. The
+    /// output is the same for the same response, whether the body or the content type was set
+    /// first.
+    ///
+    /// Neither the status line nor the headers are allowed to split the response: headers
+    /// whose name is not a valid token are left out, and control characters in the reason
+    /// phrase and in header values are replaced with spaces.
+    pub fn to_head_bytes(&self) -> Vec<u8> {
         let mut bytes = Vec::new();
 
         // Add the status line
-        let status_line = format!("HTTP/1.1 {} {}\r\n", self.status as u16, self.status.reason_phrase());
+        let reason = self.reason_phrase().replace(is_forbidden_in_value, " ");
+        let status_line = format!("HTTP/1.1 {} {reason}\r\n", self.status_code());
         bytes.extend_from_slice(status_line.as_bytes());
 
-        // Add the headers
+        // Add the headers, with the body length and trailer names computed here rather than
+        // taken from them
+        let has_length = self.stream.is_none()
+            && !matches!(self.status_code(), 100..=199 | 204 | 304);
+        // Informational and 204 responses must not carry a length at all (RFC 9110 section 8.6)
+        let forbids_length = matches!(self.status_code(), 100..=199 | 204);
+        let has_trailers = self.trailer_names().is_some();
         for (name, value) in &self.headers {
+            if forbids_length && name.eq_ignore_ascii_case("Content-Length") {
+                continue;
+            }
+            if has_length && name.eq_ignore_ascii_case("Content-Length") {
+                if value.trim() != self.body.len().to_string() {
+                    warn!("Replacing Content-Length {value} with the body length {len}", len = self.body.len());
+                }
+                continue;
+            }
+            if !is_token(name) || (has_trailers && name.eq_ignore_ascii_case("Trailer")) {
+                continue;
+            }
+            let value = if value.chars().any(is_forbidden_in_value) {
+                value.replace(is_forbidden_in_value, " ")
+            } else {
+                value.to_string()
+            };
             let header_line = format!("{name}: {value}\r\n");
             bytes.extend_from_slice(header_line.as_bytes());
         }
+        if has_length {
+            bytes.extend_from_slice(format!("Content-Length: {}\r\n", self.body.len()).as_bytes());
+        }
+        if let Some(names) = self.trailer_names() {
+            bytes.extend_from_slice(format!("Trailer: {names}\r\n").as_bytes());
+        }
 
         // Add the empty line that separates headers from body
         bytes.extend_from_slice(b"\r\n");
 
-        // Add the body
-        bytes.extend_from_slice(&self.body);
+        bytes
+    }
+
+    /// Get the comma-separated names of the trailers to announce, or None if no trailers will
+    /// be sent because there are none or the body isn't streamed in chunks.
+    fn trailer_names(&self) -> Option<String> {
+        let is_chunked = self.stream.is_some()
+            && self
+                .get_all_headers("Transfer-Encoding")
+                .iter()
+                .any(|value| value.trim().eq_ignore_ascii_case("chunked"));
+        if !is_chunked {
+            return None;
+        }
+
+        let mut names: Vec<&str> = Vec::new();
+        for (name, _) in self.trailers.iter().filter(|(name, _)| is_token(name)) {
+            if !names.iter().any(|n| n.eq_ignore_ascii_case(name)) {
+                names.push(name);
+            }
+        }
+        (!names.is_empty()).then(|| names.join(", "))
+    }
 
+    /// Convert the last chunk of a chunked body to bytes, along with the trailers.
+    ///
+    /// Trailers are sanitized like headers.
+    pub(crate) fn last_chunk_bytes(&self) -> Vec<u8> {
+        let mut bytes = b"0\r\n".to_vec();
+        if self.trailer_names().is_some() {
+            for (name, value) in self.trailers.iter().filter(|(name, _)| is_token(name)) {
+                let value = value.replace(is_forbidden_in_value, " ");
+                bytes.extend_from_slice(format!("{name}: {value}\r\n").as_bytes());
+            }
+        }
+        bytes.extend_from_slice(b"\r\n");
         bytes
     }
 }
+
+/// Responses are equal when their status, headers, body and trailers are.
+///
+/// A streamed body can't be compared, so two responses with a `stream` are equal if the rest
+/// of them is.
+impl PartialEq for HttpResponse {
+    fn eq(&self, other: &Self) -> bool {
+        self.status == other.status
+            && self.custom_status == other.custom_status
+            && self.headers == other.headers
+            && self.body == other.body
+            && self.stream.is_some() == other.stream.is_some()
+            && self.trailers == other.trailers
+    }
+}
+
+impl Eq for HttpResponse {}
+
+/// Check that a header name is a valid token and its value has no control characters.
+fn validate_header(name: &str, value: &str) -> Result<(), Error> {
+    if !is_token(name) {
+        return Err(Error::InvalidHeader(format!("invalid header name {name:?}")));
+    }
+    if value.chars().any(is_forbidden_in_value) {
+        return Err(Error::InvalidHeader(format!("invalid value for header {name}: {value:?}")));
+    }
+    Ok(())
+}
+
+/// Check whether a character may not appear in a header value: any control character other
+/// than a horizontal tab (RFC 9110 section 5.5).
+fn is_forbidden_in_value(c: char) -> bool {
+    c.is_ascii_control() && c != '\t'
+}
@@ -0,0 +1,375 @@
+//! Matching of request paths and methods against registered routes.
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Arc;
+
+use crate::parser::{HttpRequest, Method};
+use crate::server::error::Error;
+use crate::server::into_response::IntoHandlerResult;
+use crate::server::handler::{ErrorHandlerFn, HandlerFn, HandlerFuture, MiddlewareFn, Next, Route, Segment, parse_pattern};
+use crate::server::response::{HttpResponse, StatusCode};
+
+/// The outcome of matching a request against a [`Router`].
+pub enum MatchResult {
+    /// A route accepts the path and method.
+    Matched {
+        /// The handler of the matched route
+        handler: HandlerFn,
+        /// The path parameters captured by the route pattern
+        path_params: HashMap<String, String>,
+        /// The method the route was matched for, which is GET for a HEAD request served by a
+        /// GET route
+        method: Method,
+    },
+    /// Routes accept the path, but not the method; holds the methods they do accept.
+    MethodNotAllowed(Vec<Method>),
+    /// No route accepts the path.
+    NotFound,
+}
+
+/// A set of routes, matched by path and method.
+///
+/// Paths may contain named parameters (`/users/:id`) and a final wildcard segment
+/// (`/static/*path`). When several routes match a path, static segments take priority over
+/// parameters, and parameters over wildcards.
+///
+/// Routers can be nested with [`Router::mount`], and middleware added to a router with
+/// [`Router::add_middleware`] only wrap the handlers of its own routes, including those of
+/// routers mounted into it.
+#[derive(Default)]
+pub struct Router {
+    routes: Vec<Route>,
+    middleware: Arc<Vec<MiddlewareFn>>,
+    not_found_handler: Option<HandlerFn>,
+    error_handler: Option<ErrorHandlerFn>,
+}
+
+impl Router {
+    /// Create an empty router.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a route.
+    ///
+    /// The handler may return anything that implements [`IntoHandlerResult`], from a
+    /// `Result<HttpResponse, Error>` to a plain string.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the path contains more than one wildcard, or a wildcard that isn't the last
+    /// segment.
+    pub fn add_route<F, Fut>(&mut self, path: impl Into<String>, methods: Vec<Method>, handler: F)
+    where
+        F: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoHandlerResult,
+    {
+        self.push_route(path.into(), methods, handler, false);
+    }
+
+    /// Add a route that accepts only GET, like [`Router::add_route`] with
+    /// `vec![Method::GET]`. A HEAD request for the path is served by it too.
+    pub fn get<F, Fut>(&mut self, path: impl Into<String>, handler: F)
+    where
+        F: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoHandlerResult,
+    {
+        self.add_route(path, vec![Method::GET], handler);
+    }
+
+    /// Add a route that accepts only POST, like [`Router::add_route`] with
+    /// `vec![Method::POST]`.
+    pub fn post<F, Fut>(&mut self, path: impl Into<String>, handler: F)
+    where
+        F: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoHandlerResult,
+    {
+        self.add_route(path, vec![Method::POST], handler);
+    }
+
+    /// Add a route that accepts only PUT, like [`Router::add_route`] with
+    /// `vec![Method::PUT]`.
+    pub fn put<F, Fut>(&mut self, path: impl Into<String>, handler: F)
+    where
+        F: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoHandlerResult,
+    {
+        self.add_route(path, vec![Method::PUT], handler);
+    }
+
+    /// Add a route that accepts only DELETE, like [`Router::add_route`] with
+    /// `vec![Method::DELETE]`.
+    pub fn delete<F, Fut>(&mut self, path: impl Into<String>, handler: F)
+    where
+        F: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoHandlerResult,
+    {
+        self.add_route(path, vec![Method::DELETE], handler);
+    }
+
+    /// Add a route that accepts only PATCH, like [`Router::add_route`] with
+    /// `vec![Method::PATCH]`.
+    pub fn patch<F, Fut>(&mut self, path: impl Into<String>, handler: F)
+    where
+        F: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoHandlerResult,
+    {
+        self.add_route(path, vec![Method::PATCH], handler);
+    }
+
+    /// Add a route whose handler reads the request body as it arrives.
+    ///
+    /// The body isn't received before the handler runs, so it isn't limited by the maximum
+    /// body size; the handler reads it with [`HttpRequest::body_reader`], and
+    /// [`HttpRequest::body`] is left empty. A client that sent `Expect: 100-continue` is told to
+    /// send the body when the handler first reads it. If the handler responds without reading
+    /// the whole body, the connection is closed after the response.
+    ///
+    /// The body is read after the request timeout for the header section has passed, so it
+    /// applies to each read of the body instead: a read that waits longer than
+    /// [`ServerConfig::request_timeout`] for more data fails with a `TimedOut` error. The
+    /// handler timeout, if set, limits the whole upload.
+    ///
+    /// [`ServerConfig::request_timeout`]: crate::ServerConfig::request_timeout
+    ///
+    /// # Panics
+    ///
+    /// Panics if the path contains more than one wildcard, or a wildcard that isn't the last
+    /// segment.
+    pub fn add_streaming_route<F, Fut>(&mut self, path: impl Into<String>, methods: Vec<Method>, handler: F)
+    where
+        F: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoHandlerResult,
+    {
+        self.push_route(path.into(), methods, handler, true);
+    }
+
+    fn push_route<F, Fut>(&mut self, path: String, methods: Vec<Method>, handler: F, streams_body: bool)
+    where
+        F: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoHandlerResult,
+    {
+        let handler: HandlerFn = Arc::new(move |req: HttpRequest| -> HandlerFuture {
+            let response = handler(req);
+            Box::pin(async move { response.await.into_handler_result() })
+        });
+
+        self.routes.push(Route {
+            segments: parse_pattern(&path),
+            path,
+            methods,
+            handler,
+            streams_body,
+        });
+    }
+
+    /// Add a middleware that wraps the handlers of this router's routes.
+    ///
+    /// Middleware run in registration order, after the server middleware and the middleware of
+    /// any router this one is mounted into.
+    pub fn add_middleware<F, Fut>(&mut self, middleware: F)
+    where
+        F: Fn(HttpRequest, Next) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<HttpResponse, Error>> + Send + 'static,
+    {
+        let middleware: MiddlewareFn = Arc::new(move |req: HttpRequest, next: Next| -> HandlerFuture {
+            Box::pin(middleware(req, next))
+        });
+        Arc::make_mut(&mut self.middleware).push(middleware);
+    }
+
+    /// Set the handler that serves requests for paths no route matches, instead of the default
+    /// `404 Not Found` response.
+    pub fn set_not_found_handler<F, Fut>(&mut self, handler: F)
+    where
+        F: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoHandlerResult,
+    {
+        self.not_found_handler = Some(Arc::new(move |req: HttpRequest| -> HandlerFuture {
+            let response = handler(req);
+            Box::pin(async move { response.await.into_handler_result() })
+        }));
+    }
+
+    /// Set a fallback handler for requests no route matches, such as one serving `index.html`
+    /// for the client-side routes of a single-page app.
+    ///
+    /// The fallback serves every method, but only for paths no route matches: a path a route
+    /// matches for other methods still gets `405 Method Not Allowed`. It is the same handler
+    /// [`Router::set_not_found_handler`] sets.
+    pub fn fallback<F, Fut>(&mut self, handler: F)
+    where
+        F: Fn(HttpRequest) -> Fut + Send + Sync + 'static,
+        Fut: Future + Send + 'static,
+        Fut::Output: IntoHandlerResult,
+    {
+        self.set_not_found_handler(handler);
+    }
+
+    /// Set the handler that builds the `404 Not Found`, `405 Method Not Allowed`,
+    /// `500 Internal Server Error` and `504 Gateway Timeout` responses, instead of the default
+    /// plain text ones.
+    ///
+    /// The handler receives the status code and the error being answered. A `405` response
+    /// always gets an `Allow` header listing the supported methods, whatever the handler returns.
+    pub fn set_error_handler<F>(&mut self, handler: F)
+    where
+        F: Fn(StatusCode, &Error) -> HttpResponse + Send + Sync + 'static,
+    {
+        self.error_handler = Some(Arc::new(handler));
+    }
+
+    /// Get the handler for unmatched paths, if one is set.
+    pub(crate) fn not_found_handler(&self) -> Option<&HandlerFn> {
+        self.not_found_handler.as_ref()
+    }
+
+    /// Get the error handler, if one is set.
+    pub(crate) fn error_handler(&self) -> Option<&ErrorHandlerFn> {
+        self.error_handler.as_ref()
+    }
+
+    /// Mount the routes of another router under a path prefix.
+    ///
+    /// The router's routes are matched against the rest of the path after the prefix, so a
+    /// route `/users/:id` mounted under `/api` serves `/api/users/42`. The prefix may contain
+    /// named parameters, which are captured along with the parameters of the route. The
+    /// mounted router's middleware keep wrapping only its own routes, while its not-found and
+    /// error handlers are not used.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the prefix contains a wildcard.
+    pub fn mount(&mut self, prefix: &str, router: Router) {
+        if parse_pattern(prefix).iter().any(|segment| matches!(segment, Segment::Wildcard(_))) {
+            panic!("Mount prefix {prefix} must not contain a wildcard segment");
+        }
+
+        let Router { routes, middleware, .. } = router;
+        for route in routes {
+            let path = format!("{}/{}", prefix.trim_end_matches('/'), route.path.trim_start_matches('/'));
+            let handler = if middleware.is_empty() {
+                route.handler
+            } else {
+                with_middleware(middleware.clone(), route.handler)
+            };
+            self.routes.push(Route {
+                segments: parse_pattern(&path),
+                path,
+                methods: route.methods,
+                handler,
+                streams_body: route.streams_body,
+            });
+        }
+    }
+
+    /// Get the routes, in registration order.
+    pub(crate) fn routes(&self) -> &[Route] {
+        &self.routes
+    }
+
+    /// Get every method accepted by at least one route, in registration order.
+    pub fn methods(&self) -> Vec<Method> {
+        let mut methods: Vec<Method> = Vec::new();
+        for method in self.routes.iter().flat_map(|route| route.methods.iter()) {
+            if !methods.contains(method) {
+                methods.push(method.clone());
+            }
+        }
+        methods
+    }
+
+    /// Find the route for a method and path.
+    ///
+    /// A HEAD request for a path without an explicit HEAD route is matched to its GET route.
+    /// Any query string in the path is ignored.
+    pub fn match_route(&self, method: &Method, path: &str) -> MatchResult {
+        match self.find_route(method, path) {
+            Some(Ok((route, path_params, method))) => {
+                let handler = if self.middleware.is_empty() {
+                    route.handler.clone()
+                } else {
+                    with_middleware(self.middleware.clone(), route.handler.clone())
+                };
+                MatchResult::Matched {
+                    handler,
+                    path_params,
+                    method,
+                }
+            }
+            Some(Err(methods)) => MatchResult::MethodNotAllowed(methods),
+            None => MatchResult::NotFound,
+        }
+    }
+
+    /// Check whether the route for a method and path reads the request body as it arrives.
+    pub(crate) fn streams_body(&self, method: &Method, path: &str) -> bool {
+        matches!(self.find_route(method, path), Some(Ok((route, _, _))) if route.streams_body)
+    }
+
+    /// Find the route for a method and path.
+    ///
+    /// # Returns
+    ///
+    /// The route, the methods routes accept if none accepts the method, or None if no route
+    /// accepts the path
+    fn find_route(&self, method: &Method, path: &str) -> Option<Result<FoundRoute<'_>, Vec<Method>>> {
+        let mut matching_routes: Vec<(&Route, HashMap<String, String>)> = self
+            .routes
+            .iter()
+            .filter_map(|route| route.match_path(path).map(|params| (route, params)))
+            .collect();
+
+        if matching_routes.is_empty() {
+            return None;
+        }
+
+        // Prefer the most specific route, with static segments over parameters
+        matching_routes.sort_by_key(|(route, _)| std::cmp::Reverse(route.specificity()));
+        let matched = matching_routes
+            .iter()
+            .position(|(route, _)| route.methods.contains(method))
+            .map(|i| (i, method.clone()))
+            .or_else(|| {
+                // Serve HEAD requests with the GET handler when there is no explicit HEAD route
+                matching_routes
+                    .iter()
+                    .position(|(route, _)| *method == Method::HEAD && route.methods.contains(&Method::GET))
+                    .map(|i| (i, Method::GET))
+            });
+
+        match matched {
+            Some((i, method)) => {
+                let (route, path_params) = matching_routes.swap_remove(i);
+                Some(Ok((route, path_params, method)))
+            }
+            None => {
+                let mut methods: Vec<Method> = Vec::new();
+                for method in matching_routes.iter().flat_map(|(route, _)| route.methods.iter()) {
+                    if !methods.contains(method) {
+                        methods.push(method.clone());
+                    }
+                }
+                Some(Err(methods))
+            }
+        }
+    }
+}
+
+/// A matched route, with its path parameters and the method it was matched for.
+type FoundRoute<'a> = (&'a Route, HashMap<String, String>, Method);
+
+/// Wrap a handler so it runs behind the given middleware.
+fn with_middleware(middleware: Arc<Vec<MiddlewareFn>>, handler: HandlerFn) -> HandlerFn {
+    Arc::new(move |req: HttpRequest| Next::new(middleware.clone(), handler.clone()).run(req))
+}
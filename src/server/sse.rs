@@ -0,0 +1,148 @@
+//! Server-Sent Events (`text/event-stream`) responses.
+
+use std::fmt;
+
+use tokio::sync::mpsc;
+
+use crate::server::error::Error;
+use crate::server::response::{HttpResponse, StatusCode};
+
+/// A single event of an event stream.
+///
+/// # Examples
+///
+/// ```
+/// use microhttp_rs::SseEvent;
+///
+/// let event = SseEvent::new("line one\nline two").with_event("update").with_id("7");
+/// assert_eq!(event.to_string(), "id: 7\nevent: update\ndata: line one\ndata: line two\n\n");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SseEvent {
+    /// The event type, or None for the default `message` type
+    pub event: Option<String>,
+    /// The event data; each line is sent in its own `data:` field
+    pub data: String,
+    /// The event ID, which the client sends back in `Last-Event-ID` when it reconnects
+    pub id: Option<String>,
+    /// How many milliseconds the client should wait before reconnecting
+    pub retry: Option<u64>,
+}
+
+impl SseEvent {
+    /// Create an event with the given data.
+    pub fn new(data: impl Into<String>) -> Self {
+        Self {
+            event: None,
+            data: data.into(),
+            id: None,
+            retry: None,
+        }
+    }
+
+    /// Set the event type.
+    pub fn with_event(mut self, event: impl Into<String>) -> Self {
+        self.event = Some(event.into());
+        self
+    }
+
+    /// Set the event ID.
+    pub fn with_id(mut self, id: impl Into<String>) -> Self {
+        self.id = Some(id.into());
+        self
+    }
+
+    /// Set the reconnection time in milliseconds.
+    pub fn with_retry(mut self, retry: u64) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+}
+
+impl fmt::Display for SseEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Line breaks would end the field early, so they are dropped from single-line fields
+        let single_line = |value: &str| value.replace(['\r', '\n'], "");
+
+        if let Some(id) = &self.id {
+            writeln!(f, "id: {}", single_line(id))?;
+        }
+        if let Some(event) = &self.event {
+            writeln!(f, "event: {}", single_line(event))?;
+        }
+        if let Some(retry) = self.retry {
+            writeln!(f, "retry: {retry}")?;
+        }
+        for line in self.data.split('\n') {
+            writeln!(f, "data: {}", line.strip_suffix('\r').unwrap_or(line))?;
+        }
+        writeln!(f)
+    }
+}
+
+/// The sending side of a Server-Sent Events response.
+///
+/// A handler creates the pair with [`SseResponse::new`], hands the `SseResponse` to a task
+/// that produces events, and returns the [`HttpResponse`]. Each event is written and flushed
+/// as soon as it is sent. Once the client disconnects, [`SseResponse::send`] fails and
+/// [`SseResponse::closed`] completes, so the producer can stop.
+///
+/// # Examples
+///
+/// ```
+/// use microhttp_rs::{HttpResponse, ServerError, SseEvent, SseResponse};
+///
+/// async fn events() -> Result<HttpResponse, ServerError> {
+///     let (sse, response) = SseResponse::new(16);
+///     tokio::spawn(async move {
+///         for i in 0.. {
+///             if sse.send(SseEvent::new(format!("tick {i}"))).await.is_err() {
+///                 break; // The client went away
+///             }
+///             tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+///         }
+///     });
+///     Ok(response)
+/// }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SseResponse {
+    sender: mpsc::Sender<Vec<u8>>,
+}
+
+impl SseResponse {
+    /// Create an event stream that buffers up to `buffer` unsent events.
+    ///
+    /// Returns the sender for events and the response to return from the handler, which has
+    /// `Content-Type: text/event-stream` and headers that disable caching and proxy
+    /// buffering.
+    pub fn new(buffer: usize) -> (Self, HttpResponse) {
+        let (sender, receiver) = mpsc::channel(buffer.max(1));
+        let response = HttpResponse::new(StatusCode::Ok)
+            .with_content_type("text/event-stream")
+            .with_header("Cache-Control", "no-cache")
+            .with_header("X-Accel-Buffering", "no")
+            .with_body_stream(receiver);
+        (Self { sender }, response)
+    }
+
+    /// Send an event, waiting for room in the buffer.
+    ///
+    /// Fails with [`Error::ClientDisconnected`] once the client has gone away.
+    pub async fn send(&self, event: SseEvent) -> Result<(), Error> {
+        self.sender
+            .send(event.to_string().into_bytes())
+            .await
+            .map_err(|_| Error::ClientDisconnected)
+    }
+
+    /// Check whether the client has gone away.
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+
+    /// Wait until the client has gone away.
+    pub async fn closed(&self) {
+        self.sender.closed().await
+    }
+}
@@ -0,0 +1,35 @@
+//! Response bodies that are produced over time.
+
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::mpsc;
+
+/// A response body whose chunks arrive over a channel while the response is being sent.
+///
+/// Clones share the same channel; the body is sent only once.
+#[derive(Clone)]
+pub struct BodyStream {
+    receiver: Arc<Mutex<Option<mpsc::Receiver<Vec<u8>>>>>,
+}
+
+impl BodyStream {
+    /// Create a body stream that sends the chunks received on the channel until every sender
+    /// is dropped.
+    pub fn new(receiver: mpsc::Receiver<Vec<u8>>) -> Self {
+        Self {
+            receiver: Arc::new(Mutex::new(Some(receiver))),
+        }
+    }
+
+    /// Take the receiving end of the channel, if the body hasn't been sent yet.
+    pub(crate) fn take(&self) -> Option<mpsc::Receiver<Vec<u8>>> {
+        self.receiver.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+}
+
+impl fmt::Debug for BodyStream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BodyStream").finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,46 @@
+//! TLS termination with rustls.
+
+use std::path::Path;
+use std::sync::Arc;
+
+use tokio_rustls::rustls;
+use tokio_rustls::rustls::pki_types::pem::PemObject;
+use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+use crate::server::error::Error;
+
+/// Build a TLS configuration from a PEM-encoded certificate chain and private key.
+///
+/// The configuration uses the ring crypto provider and advertises `http/1.1` through ALPN.
+/// Use it as [`ServerConfig::tls`](crate::ServerConfig::tls).
+pub fn tls_config_from_pem(cert_chain: &[u8], private_key: &[u8]) -> Result<Arc<rustls::ServerConfig>, Error> {
+    let certs = CertificateDer::pem_slice_iter(cert_chain)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| Error::InvalidConfig(format!("Invalid TLS certificate: {e}")))?;
+    if certs.is_empty() {
+        return Err(Error::InvalidConfig("No TLS certificate found".to_string()));
+    }
+    let key = PrivateKeyDer::from_pem_slice(private_key)
+        .map_err(|e| Error::InvalidConfig(format!("Invalid TLS private key: {e}")))?;
+
+    let provider = Arc::new(rustls::crypto::ring::default_provider());
+    let mut config = rustls::ServerConfig::builder_with_provider(provider)
+        .with_safe_default_protocol_versions()
+        .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+        .map_err(|e| Error::InvalidConfig(format!("Invalid TLS configuration: {e}")))?;
+    config.alpn_protocols = vec![b"http/1.1".to_vec()];
+
+    Ok(Arc::new(config))
+}
+
+/// Build a TLS configuration from PEM files holding a certificate chain and private key.
+///
+/// See [`tls_config_from_pem`].
+pub fn load_tls_config(
+    cert_path: impl AsRef<Path>,
+    key_path: impl AsRef<Path>,
+) -> Result<Arc<rustls::ServerConfig>, Error> {
+    let cert_chain = std::fs::read(cert_path)?;
+    let private_key = std::fs::read(key_path)?;
+    tls_config_from_pem(&cert_chain, &private_key)
+}
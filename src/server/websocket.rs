@@ -0,0 +1,494 @@
+//! WebSocket (RFC 6455) upgrades of HTTP/1.1 connections, with a minimal message framing.
+
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use log::{debug, error};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, DuplexStream};
+
+use crate::parser::{HttpRequest, HttpVersion, Method, base64_decode, base64_encode};
+use crate::server::catch_panic::{CatchPanic, panic_message};
+use crate::server::error::Error;
+use crate::server::response::{HttpResponse, StatusCode};
+
+/// The GUID appended to `Sec-WebSocket-Key` before hashing it into `Sec-WebSocket-Accept`.
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+
+/// The number of bytes buffered in each direction between the connection and the session.
+const BRIDGE_CAPACITY: usize = 64 * 1024;
+
+const OP_CONTINUATION: u8 = 0x0;
+const OP_TEXT: u8 = 0x1;
+const OP_BINARY: u8 = 0x2;
+const OP_CLOSE: u8 = 0x8;
+const OP_PING: u8 = 0x9;
+const OP_PONG: u8 = 0xA;
+
+/// Close codes sent when the client breaks the protocol.
+const CLOSE_PROTOCOL_ERROR: u16 = 1002;
+const CLOSE_INVALID_DATA: u16 = 1007;
+const CLOSE_TOO_BIG: u16 = 1009;
+
+/// A message sent or received over a WebSocket.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WebSocketMessage {
+    /// A UTF-8 text message
+    Text(String),
+    /// A binary message
+    Binary(Vec<u8>),
+    /// A ping, with up to 125 bytes of application data; received pings are answered with a
+    /// pong automatically
+    Ping(Vec<u8>),
+    /// A pong, with up to 125 bytes of application data
+    Pong(Vec<u8>),
+    /// A close frame, with an optional status code and reason
+    Close(Option<(u16, String)>),
+}
+
+/// Check whether a request asks to upgrade the connection to a WebSocket: an HTTP/1.1 GET
+/// with `Upgrade: websocket` and `Connection: Upgrade`.
+pub fn is_websocket_upgrade(request: &HttpRequest) -> bool {
+    let has_token = |name: &str, token: &str| {
+        request
+            .get_all_headers(name)
+            .iter()
+            .any(|v| v.split(',').any(|t| t.trim().eq_ignore_ascii_case(token)))
+    };
+    request.method == Method::GET
+        && request.version == HttpVersion::Http11
+        && has_token("Upgrade", "websocket")
+        && has_token("Connection", "upgrade")
+}
+
+/// Answer a WebSocket upgrade request, running `session` on the connection once the
+/// `101 Switching Protocols` response has been sent.
+///
+/// The session gets a [`WebSocket`] for exchanging messages, and the connection is closed when
+/// the session returns. Requests that aren't a valid version 13 upgrade are answered with
+/// `400 Bad Request` instead, and the session is not run. Messages are limited to the
+/// server's `max_body_size`.
+///
+/// # Examples
+///
+/// ```
+/// use microhttp_rs::{HttpServer, Method, ServerConfig, WebSocketMessage, websocket_upgrade};
+///
+/// # async fn example() {
+/// let server = HttpServer::new(ServerConfig::default());
+/// server
+///     .add_route("/echo", vec![Method::GET], |req| async move {
+///         websocket_upgrade(&req, |mut ws| async move {
+///             while let Ok(Some(message)) = ws.recv().await {
+///                 if let WebSocketMessage::Text(_) | WebSocketMessage::Binary(_) = message {
+///                     if ws.send(message).await.is_err() {
+///                         break;
+///                     }
+///                 }
+///             }
+///         })
+///     })
+///     .await;
+/// # }
+/// ```
+pub fn websocket_upgrade<F, Fut>(request: &HttpRequest, session: F) -> HttpResponse
+where
+    F: FnOnce(WebSocket) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    let bad_request = |message: &str| {
+        HttpResponse::new(StatusCode::BadRequest)
+            .with_content_type("text/plain")
+            .with_body_string(message)
+    };
+
+    if !is_websocket_upgrade(request) {
+        return bad_request("Expected a WebSocket upgrade request");
+    }
+    if request.get_header("Sec-WebSocket-Version").map(|v| v.trim()) != Some("13") {
+        return bad_request("Unsupported WebSocket version").with_header("Sec-WebSocket-Version", "13");
+    }
+    let key = match request.get_header("Sec-WebSocket-Key").map(|k| k.trim()) {
+        Some(key) if base64_decode(key).is_some_and(|nonce| nonce.len() == 16) => key,
+        _ => return bad_request("Missing or invalid Sec-WebSocket-Key header"),
+    };
+
+    let mut response = HttpResponse::new(StatusCode::SwitchingProtocols)
+        .with_header("Upgrade", "websocket")
+        .with_header("Connection", "Upgrade")
+        .with_header("Sec-WebSocket-Accept", accept_key(key));
+    response.upgrade = Some(WebSocketUpgrade::new(session));
+    response
+}
+
+/// Compute the `Sec-WebSocket-Accept` value answering a `Sec-WebSocket-Key`.
+pub(crate) fn accept_key(key: &str) -> String {
+    base64_encode(&sha1(format!("{key}{ACCEPT_GUID}").as_bytes()))
+}
+
+/// The boxed session of a WebSocket upgrade.
+type SessionFn = Box<dyn FnOnce(WebSocket) -> Pin<Box<dyn Future<Output = ()> + Send>> + Send>;
+
+/// The session a response runs on the connection after switching protocols.
+///
+/// Clones share the same session; it is run only once.
+#[derive(Clone)]
+pub(crate) struct WebSocketUpgrade {
+    session: Arc<Mutex<Option<SessionFn>>>,
+}
+
+impl WebSocketUpgrade {
+    fn new<F, Fut>(session: F) -> Self
+    where
+        F: FnOnce(WebSocket) -> Fut + Send + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let session: SessionFn = Box::new(move |ws| Box::pin(session(ws)));
+        Self {
+            session: Arc::new(Mutex::new(Some(session))),
+        }
+    }
+
+    /// Run the session on a connection whose `101` response has been written.
+    ///
+    /// `pending` holds bytes the client sent after the upgrade request, which are the start
+    /// of its first frames. Returns once the session has returned and the client has closed
+    /// its side, or the connection fails.
+    pub(crate) async fn run(
+        &self,
+        socket: &mut (impl AsyncRead + AsyncWrite + Unpin),
+        pending: &[u8],
+        max_message_size: usize,
+    ) {
+        let Some(session) = self.session.lock().unwrap_or_else(|e| e.into_inner()).take() else {
+            return;
+        };
+
+        // The session owns its end of an in-memory pipe, which is relayed to the connection,
+        // so it can outlive the borrow of the socket
+        let (session_end, mut bridge) = tokio::io::duplex(BRIDGE_CAPACITY.max(pending.len()));
+        if bridge.write_all(pending).await.is_err() {
+            return;
+        }
+        let session = CatchPanic::new(session(WebSocket::new(session_end, max_message_size)));
+        let (session, relayed) = tokio::join!(session, tokio::io::copy_bidirectional(socket, &mut bridge));
+
+        if let Err(payload) = session {
+            error!("WebSocket session panicked: {}", panic_message(payload.as_ref()));
+        }
+        if let Err(e) = relayed {
+            debug!("WebSocket connection ended with an error: {e}");
+        }
+    }
+}
+
+impl fmt::Debug for WebSocketUpgrade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocketUpgrade").finish_non_exhaustive()
+    }
+}
+
+/// A single frame as read from the client.
+struct Frame {
+    fin: bool,
+    opcode: u8,
+    payload: Vec<u8>,
+}
+
+/// The server side of a WebSocket connection.
+///
+/// Text, binary, ping, pong and close frames are supported; fragmented messages are
+/// reassembled, and extensions and subprotocols are not negotiated.
+pub struct WebSocket {
+    stream: DuplexStream,
+    buf: Vec<u8>,
+    max_message_size: usize,
+    /// The opcode and data of a fragmented message whose final frame hasn't arrived yet
+    fragments: Option<(u8, Vec<u8>)>,
+    close_sent: bool,
+    closed: bool,
+}
+
+impl WebSocket {
+    fn new(stream: DuplexStream, max_message_size: usize) -> Self {
+        Self {
+            stream,
+            buf: Vec::new(),
+            max_message_size,
+            fragments: None,
+            close_sent: false,
+            closed: false,
+        }
+    }
+
+    /// Receive the next message.
+    ///
+    /// Pings are answered with a pong before they are returned, and a close frame from the
+    /// client is echoed back unless a close frame has already been sent.
+    ///
+    /// # Returns
+    ///
+    /// The message, or None once the connection has been closed. A client breaking the
+    /// protocol gets a close frame and the error is returned.
+    pub async fn recv(&mut self) -> Result<Option<WebSocketMessage>, Error> {
+        loop {
+            if self.closed {
+                return Ok(None);
+            }
+            let Some(frame) = self.read_frame().await? else {
+                self.closed = true;
+                return Ok(None);
+            };
+
+            let (opcode, data) = match frame.opcode {
+                OP_CONTINUATION => {
+                    let Some((_, data)) = self.fragments.as_mut() else {
+                        return Err(self.fail(CLOSE_PROTOCOL_ERROR, "Unexpected continuation frame").await);
+                    };
+                    if data.len() + frame.payload.len() > self.max_message_size {
+                        return Err(self.fail(CLOSE_TOO_BIG, "Message too big").await);
+                    }
+                    data.extend_from_slice(&frame.payload);
+                    if !frame.fin {
+                        continue;
+                    }
+                    self.fragments.take().unwrap_or_default()
+                }
+                OP_TEXT | OP_BINARY if self.fragments.is_some() => {
+                    return Err(self.fail(CLOSE_PROTOCOL_ERROR, "Expected a continuation frame").await);
+                }
+                OP_TEXT | OP_BINARY if !frame.fin => {
+                    self.fragments = Some((frame.opcode, frame.payload));
+                    continue;
+                }
+                OP_TEXT | OP_BINARY => (frame.opcode, frame.payload),
+                OP_PING => {
+                    self.write_frame(OP_PONG, &frame.payload).await?;
+                    return Ok(Some(WebSocketMessage::Ping(frame.payload)));
+                }
+                OP_PONG => return Ok(Some(WebSocketMessage::Pong(frame.payload))),
+                OP_CLOSE => {
+                    let close = match frame.payload.len() {
+                        0 => None,
+                        1 => return Err(self.fail(CLOSE_PROTOCOL_ERROR, "Invalid close frame").await),
+                        _ => {
+                            let code = u16::from_be_bytes([frame.payload[0], frame.payload[1]]);
+                            match String::from_utf8(frame.payload[2..].to_vec()) {
+                                Ok(reason) => Some((code, reason)),
+                                Err(_) => return Err(self.fail(CLOSE_INVALID_DATA, "Invalid close reason").await),
+                            }
+                        }
+                    };
+                    if !self.close_sent {
+                        self.close_sent = true;
+                        self.write_frame(OP_CLOSE, &frame.payload[..frame.payload.len().min(2)]).await?;
+                    }
+                    self.closed = true;
+                    return Ok(Some(WebSocketMessage::Close(close)));
+                }
+                opcode => {
+                    let message = format!("Unknown opcode {opcode:#x}");
+                    return Err(self.fail(CLOSE_PROTOCOL_ERROR, &message).await);
+                }
+            };
+
+            return match opcode {
+                OP_TEXT => match String::from_utf8(data) {
+                    Ok(text) => Ok(Some(WebSocketMessage::Text(text))),
+                    Err(_) => Err(self.fail(CLOSE_INVALID_DATA, "Invalid UTF-8 in a text message").await),
+                },
+                _ => Ok(Some(WebSocketMessage::Binary(data))),
+            };
+        }
+    }
+
+    /// Send a message.
+    ///
+    /// Sending a close frame starts the closing handshake; nothing can be sent after it.
+    pub async fn send(&mut self, message: WebSocketMessage) -> Result<(), Error> {
+        if self.close_sent {
+            return Err(Error::WebSocket("A close frame has already been sent".to_string()));
+        }
+        match message {
+            WebSocketMessage::Text(text) => self.write_frame(OP_TEXT, text.as_bytes()).await,
+            WebSocketMessage::Binary(data) => self.write_frame(OP_BINARY, &data).await,
+            WebSocketMessage::Ping(data) | WebSocketMessage::Pong(data) if data.len() > 125 => {
+                Err(Error::WebSocket("Control frames carry at most 125 bytes".to_string()))
+            }
+            WebSocketMessage::Ping(data) => self.write_frame(OP_PING, &data).await,
+            WebSocketMessage::Pong(data) => self.write_frame(OP_PONG, &data).await,
+            WebSocketMessage::Close(close) => {
+                let mut payload = Vec::new();
+                if let Some((code, reason)) = close {
+                    payload.extend_from_slice(&code.to_be_bytes());
+                    payload.extend_from_slice(reason.as_bytes());
+                }
+                if payload.len() > 125 {
+                    return Err(Error::WebSocket("Control frames carry at most 125 bytes".to_string()));
+                }
+                self.close_sent = true;
+                self.write_frame(OP_CLOSE, &payload).await
+            }
+        }
+    }
+
+    /// Send a close frame for a protocol violation, and build the error to return for it.
+    async fn fail(&mut self, code: u16, message: &str) -> Error {
+        debug!("Closing WebSocket: {message}");
+        if !self.close_sent {
+            self.close_sent = true;
+            let _ = self.write_frame(OP_CLOSE, &code.to_be_bytes()).await;
+        }
+        self.closed = true;
+        Error::WebSocket(message.to_string())
+    }
+
+    /// Read the next frame, unmasking its payload.
+    ///
+    /// # Returns
+    ///
+    /// The frame, or None if the connection closed before a complete frame arrived
+    async fn read_frame(&mut self) -> Result<Option<Frame>, Error> {
+        if !self.fill(2).await? {
+            return Ok(None);
+        }
+        let (first, second) = (self.buf[0], self.buf[1]);
+        let fin = first & 0x80 != 0;
+        let opcode = first & 0x0F;
+        if first & 0x70 != 0 {
+            return Err(self.fail(CLOSE_PROTOCOL_ERROR, "Reserved bits set without an extension").await);
+        }
+        if second & 0x80 == 0 {
+            return Err(self.fail(CLOSE_PROTOCOL_ERROR, "Client frames must be masked").await);
+        }
+        let is_control = opcode & 0x8 != 0;
+
+        let (header_len, payload_len) = match second & 0x7F {
+            126 => {
+                if !self.fill(4).await? {
+                    return Ok(None);
+                }
+                (4, u16::from_be_bytes([self.buf[2], self.buf[3]]) as u64)
+            }
+            127 => {
+                if !self.fill(10).await? {
+                    return Ok(None);
+                }
+                let mut len = [0; 8];
+                len.copy_from_slice(&self.buf[2..10]);
+                (10, u64::from_be_bytes(len))
+            }
+            len => (2, len as u64),
+        };
+        if is_control && (!fin || payload_len > 125) {
+            return Err(self.fail(CLOSE_PROTOCOL_ERROR, "Invalid control frame").await);
+        }
+        if payload_len > self.max_message_size as u64 {
+            return Err(self.fail(CLOSE_TOO_BIG, "Message too big").await);
+        }
+
+        let frame_len = header_len + 4 + payload_len as usize;
+        if !self.fill(frame_len).await? {
+            return Ok(None);
+        }
+        let mut mask = [0; 4];
+        mask.copy_from_slice(&self.buf[header_len..header_len + 4]);
+        let mut payload: Vec<u8> = self.buf.drain(..frame_len).skip(header_len + 4).collect();
+        for (i, byte) in payload.iter_mut().enumerate() {
+            *byte ^= mask[i % 4];
+        }
+        Ok(Some(Frame { fin, opcode, payload }))
+    }
+
+    /// Read until at least `len` bytes are buffered.
+    ///
+    /// # Returns
+    ///
+    /// Whether they were, rather than the connection closing first
+    async fn fill(&mut self, len: usize) -> Result<bool, Error> {
+        let mut chunk = [0; 4096];
+        while self.buf.len() < len {
+            let n = self.stream.read(&mut chunk).await?;
+            if n == 0 {
+                return Ok(false);
+            }
+            self.buf.extend_from_slice(&chunk[..n]);
+        }
+        Ok(true)
+    }
+
+    /// Write a single unmasked frame with the final bit set.
+    async fn write_frame(&mut self, opcode: u8, payload: &[u8]) -> Result<(), Error> {
+        let mut frame = Vec::with_capacity(payload.len() + 10);
+        frame.push(0x80 | opcode);
+        match payload.len() {
+            len @ 0..=125 => frame.push(len as u8),
+            len @ 126..=0xFFFF => {
+                frame.push(126);
+                frame.extend_from_slice(&(len as u16).to_be_bytes());
+            }
+            len => {
+                frame.push(127);
+                frame.extend_from_slice(&(len as u64).to_be_bytes());
+            }
+        }
+        frame.extend_from_slice(payload);
+        self.stream.write_all(&frame).await?;
+        self.stream.flush().await?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for WebSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebSocket").field("closed", &self.closed).finish_non_exhaustive()
+    }
+}
+
+/// Compute the SHA-1 digest of some data, as the opening handshake requires.
+pub(crate) fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut state: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64 * 8).to_be_bytes());
+
+    for block in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in block.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = state;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a.rotate_left(5).wrapping_add(f).wrapping_add(e).wrapping_add(k).wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (s, v) in state.iter_mut().zip([a, b, c, d, e]) {
+            *s = s.wrapping_add(v);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (out, s) in digest.chunks_mut(4).zip(state) {
+        out.copy_from_slice(&s.to_be_bytes());
+    }
+    digest
+}
@@ -0,0 +1,61 @@
+//! Checks that borrowed parsing allocates less than owned parsing.
+//!
+//! This is its own test binary, so the counting allocator sees no other tests.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use microhttp_rs::{parse_request, parse_request_ref};
+
+/// The system allocator, counting every allocation.
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Count the allocations made while running `f`.
+fn count_allocations<T>(f: impl FnOnce() -> T) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let result = f();
+    let after = ALLOCATIONS.load(Ordering::Relaxed);
+    drop(result);
+    after - before
+}
+
+#[test]
+fn test_parse_request_ref_allocates_less() {
+    let request = b"GET /articles/2024/http-parsing?page=2&sort=recent HTTP/1.1\r\n\
+        Host: www.example.com\r\n\
+        User-Agent: Mozilla/5.0 (X11; Linux x86_64; rv:121.0) Gecko/20100101 Firefox/121.0\r\n\
+        Accept: text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8\r\n\
+        Accept-Language: en-US,en;q=0.5\r\n\
+        Connection: keep-alive\r\n\
+        Cookie: session=4f2a9c1e8b7d; theme=dark\r\n\
+        \r\n";
+
+    let owned = count_allocations(|| parse_request(request).unwrap());
+    let borrowed = count_allocations(|| parse_request_ref(request).unwrap());
+    assert!(borrowed < owned, "parse_request_ref made {borrowed} allocations, parse_request made {owned}");
+
+    // The borrowed request allocates little more than its list of headers, while every owned
+    // header takes allocations of its own
+    assert!(borrowed <= 2, "parse_request_ref made {borrowed} allocations");
+}